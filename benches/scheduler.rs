@@ -0,0 +1,49 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use reedbot::bench;
+use serenity::all::Http;
+
+const INPUTS: &[&str] = &[
+    "r 1w1h5m3s; stretch",
+    "r 1d (2pm, 4pm); take medication",
+    "r 2001-03-06 3:30pm; anniversary",
+    "r 1w tuesday 9am; standup notes",
+    "si 3 cron */15 9-17 * * 1-5",
+    "si 3 rrule FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+];
+
+fn parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for input in INPUTS {
+        assert!(
+            bench::parse(input),
+            "benchmark input doesn't parse: {input}"
+        );
+        group.bench_with_input(BenchmarkId::from_parameter(input), input, |b, input| {
+            b.iter(|| bench::parse(input))
+        });
+    }
+    group.finish();
+}
+
+fn scheduler(c: &mut Criterion) {
+    // Processing queues saves, keep them out of the checkout
+    let scratch = std::env::temp_dir().join("reedbot-bench");
+    std::fs::create_dir_all(&scratch).unwrap();
+    std::env::set_current_dir(&scratch).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let http = Http::new("");
+    for (users, per_user) in [(100, 100), (1000, 100)] {
+        let total = users * per_user;
+        runtime.block_on(bench::populate(users, per_user));
+        c.bench_function(&format!("process_reminders/{total}"), |b| {
+            b.to_async(&runtime).iter(|| bench::process(&http))
+        });
+        c.bench_function(&format!("serialize/{total}"), |b| {
+            b.to_async(&runtime).iter(bench::serialize)
+        });
+    }
+}
+
+criterion_group!(benches, parser, scheduler);
+criterion_main!(benches);
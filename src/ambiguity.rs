@@ -0,0 +1,206 @@
+//! Times that can be read more than one way, like `6/3` or `friday` said on a Friday, are offered
+//! back as buttons instead of guessing.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use jiff::Zoned;
+use serenity::all::{
+    ButtonStyle, ChannelId, Context, CreateActionRow, CreateButton, MessageId, UserId,
+};
+use tokio::sync::Mutex;
+use untwine::prelude::ParserContext;
+
+use crate::{
+    command::{self, Command},
+    format_time, handle_command, Preferences, Reply, WeekdayMode,
+};
+
+/// Discord allows 5 buttons in a row.
+const MAX_OPTIONS: usize = 5;
+
+/// Readings waiting for their author to pick one, along with the message they came from.
+struct Pending {
+    channel: ChannelId,
+    message: MessageId,
+    options: Vec<Command>,
+}
+
+/// Only the latest ambiguous command per user can be picked from.
+static PENDING: LazyLock<Mutex<HashMap<UserId, Pending>>> = LazyLock::new(Default::default);
+
+pub enum Resolution {
+    /// Read the input as usual
+    Clear,
+    /// Only one reading made sense, but the strict grammar couldn't have found it
+    Single(Command),
+    Ambiguous(Vec<Command>),
+}
+
+/// Both ways of reading a `6/3` style date, as `$r` dates. Dates with a part over 12 only have one.
+fn slash_dates(word: &str) -> Option<Vec<String>> {
+    let mut parts = word.split('/');
+    let first: u8 = parts.next()?.parse().ok()?;
+    let second: u8 = parts.next()?.parse().ok()?;
+    let year = match parts.next() {
+        Some(year) if year.len() == 4 => year.parse::<u16>().ok()?.to_string(),
+        Some(year) if year.len() == 2 => format!("20{year}"),
+        Some(_) => return None,
+        None => String::new(),
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let mut dates = vec![];
+    for (month, day) in [(first, second), (second, first)] {
+        let date = format!("{year}-{month:02}-{day:02}");
+        if (1..=12).contains(&month) && (1..=31).contains(&day) && !dates.contains(&date) {
+            dates.push(date);
+        }
+    }
+    Some(dates)
+}
+
+/// Every spelling of `input` with its slash dates read each possible way.
+fn variants(input: &str) -> Vec<String> {
+    let Some((when, message)) = input.split_once(';') else {
+        return vec![input.to_string()];
+    };
+    let mut variants = vec![String::new()];
+    for (index, word) in when.split(' ').enumerate() {
+        let readings = slash_dates(word).unwrap_or_else(|| vec![word.to_string()]);
+        variants = variants
+            .iter()
+            .flat_map(|variant| {
+                readings.iter().map(move |reading| match index {
+                    0 => reading.clone(),
+                    _ => format!("{variant} {reading}"),
+                })
+            })
+            .collect();
+    }
+    variants
+        .into_iter()
+        .map(|variant| format!("{variant};{message}"))
+        .collect()
+}
+
+/// Whether the time part of `input` names a weekday, the only case the weekday mode changes.
+fn names_weekday(input: &str) -> bool {
+    let when = input
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    when.split(' ').any(|word| {
+        ["mon", "tue", "wed", "thu", "fri", "sat", "sun"]
+            .iter()
+            .any(|day| word.starts_with(day))
+    })
+}
+
+fn times_of(command: &Command) -> Option<&Vec<Zoned>> {
+    match command {
+        Command::ScheduleReminder(times, ..) => Some(times),
+        _ => None,
+    }
+}
+
+/// Reads a `$r` or `$say` every way its time could mean, unless the user chose a weekday mode.
+pub fn resolve(input: &str, preferences: &Preferences) -> Resolution {
+    let word = input.split(' ').next().unwrap_or_default();
+    if !matches!(word, "r" | "remindme" | "reminder" | "say") {
+        return Resolution::Clear;
+    }
+    let modes = match preferences.weekday_mode {
+        None if names_weekday(input) => vec![WeekdayMode::Smart, WeekdayMode::Next],
+        _ => vec![preferences.weekday_mode()],
+    };
+    let mut readings: Vec<Command> = vec![];
+    for variant in variants(input) {
+        for mode in &modes {
+            let mut time_context = preferences.time_context();
+            time_context.weekday_mode = *mode;
+            let mut parser_context = ParserContext::new(&variant, time_context);
+            let Ok(command) = parser_context.result(command::command(&parser_context)) else {
+                continue;
+            };
+            let Some(times) = times_of(&command) else {
+                return Resolution::Clear;
+            };
+            if !readings
+                .iter()
+                .any(|reading| times_of(reading) == Some(times))
+            {
+                readings.push(command);
+            }
+        }
+    }
+    match readings.len() {
+        0 => Resolution::Clear,
+        1 if !input.contains('/') => Resolution::Clear,
+        1 => Resolution::Single(readings.remove(0)),
+        _ => Resolution::Ambiguous(readings),
+    }
+}
+
+/// Keeps the readings for `user` and asks them which they meant.
+pub async fn ask(
+    user: UserId,
+    channel: ChannelId,
+    message: MessageId,
+    mut options: Vec<Command>,
+    preferences: &Preferences,
+) -> Reply {
+    options.truncate(MAX_OPTIONS);
+    let buttons = options
+        .iter()
+        .enumerate()
+        .filter_map(|(index, option)| {
+            let times = times_of(option)?;
+            let mut label = format_time(times.first()?, preferences.time_format());
+            if times.len() > 1 {
+                label.push_str(&format!(" and {} more", times.len() - 1));
+            }
+            Some(
+                CreateButton::new(format!("pick:{user}:{index}"))
+                    .label(label)
+                    .style(ButtonStyle::Secondary),
+            )
+        })
+        .collect();
+    PENDING.lock().await.insert(
+        user,
+        Pending {
+            channel,
+            message,
+            options,
+        },
+    );
+    Reply {
+        components: vec![CreateActionRow::Buttons(buttons)],
+        ..Reply::from("That time could mean a few things, which one did you mean?")
+    }
+}
+
+/// Schedules the reading `user` picked, as if they'd sent it unambiguously.
+pub async fn pick(ctx: &Context, user: UserId, index: usize) -> Result<String, String> {
+    let mut pending = PENDING.lock().await;
+    let Some(choice) = pending.get_mut(&user) else {
+        return Err("This choice has expired, send the command again".into());
+    };
+    if index >= choice.options.len() {
+        return Err("That option isn't available".into());
+    }
+    let command = choice.options.swap_remove(index);
+    let (channel, message) = (choice.channel, choice.message);
+    pending.remove(&user);
+    drop(pending);
+    let original = channel
+        .message(ctx, message)
+        .await
+        .map_err(|_| "The message with the command is gone")?;
+    handle_command(ctx, &original, command)
+        .await
+        .map(|reply| reply.content)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,45 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use jiff::Zoned;
+use serenity::all::{CreateAllowedMentions, GuildId, Http};
+use tokio::sync::Mutex;
+
+use crate::{
+    delivery::{self, Recipient},
+    delivery_message,
+    holidays::HolidayCalendar,
+    log_error, reschedule, Reminder,
+};
+
+pub const ANNOUNCEMENTS_FILE: &str = "announcements.json";
+
+/// Recurring channel posts managed by server moderators, kept apart from personal reminders.
+pub static ANNOUNCEMENTS: LazyLock<Mutex<HashMap<GuildId, Vec<Reminder>>>> =
+    LazyLock::new(Default::default);
+
+pub async fn load_announcements() {
+    let Ok(announcements_json) = tokio::fs::read_to_string(ANNOUNCEMENTS_FILE).await else {
+        return;
+    };
+    let announcements = serde_json::from_str(&announcements_json).unwrap();
+    *ANNOUNCEMENTS.lock().await = announcements;
+}
+
+pub async fn process_announcements(http: &Http) {
+    let mut announcements = ANNOUNCEMENTS.lock().await;
+    let now = Zoned::now();
+    for list in announcements.values_mut() {
+        while list.first().is_some_and(|f| f.time < now) {
+            let first = list.remove(0);
+            reschedule(list, &first, &HolidayCalendar::default()).await;
+            let Some(channel) = first.channel else {
+                continue;
+            };
+            // Moderators can manage messages without being allowed to ping everyone
+            let message = delivery_message(http, &first)
+                .await
+                .allowed_mentions(CreateAllowedMentions::new().all_users(true));
+            log_error(delivery::send(http, Recipient::Channel(channel), message).await);
+        }
+    }
+}
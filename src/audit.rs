@@ -0,0 +1,149 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock,
+    },
+};
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::Reminder;
+
+pub const AUDIT_FILE: &str = "audit.json";
+
+/// Entries kept per user, the oldest are dropped first.
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Created,
+    Edited,
+    Cancelled,
+    Fired,
+    Retried,
+    Failed,
+    Skipped,
+    Paused,
+    Completed,
+    TransferredOut,
+    TransferredIn,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Action::Created => "created",
+            Action::Edited => "edited",
+            Action::Cancelled => "cancelled",
+            Action::Fired => "fired",
+            Action::Retried => "delivery failed, will retry",
+            Action::Failed => "delivery failed",
+            Action::Skipped => "skipped, its condition wasn't met",
+            Action::Paused => "not sent, it's paused",
+            Action::Completed => "marked done",
+            Action::TransferredOut => "handed over",
+            Action::TransferredIn => "received",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entry {
+    pub time: Zoned,
+    pub key: u64,
+    pub action: Action,
+    /// The command that caused the change, or "scheduler" for deliveries.
+    pub source: String,
+    pub message: String,
+}
+
+pub static AUDIT: LazyLock<Mutex<HashMap<UserId, VecDeque<Entry>>>> =
+    LazyLock::new(Default::default);
+
+// Reminder keys stay the same while their list IDs shift, 0 means the reminder predates keys
+static NEXT_KEY: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_key() -> u64 {
+    NEXT_KEY.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Gives keys to reminders saved before they existed and continues numbering after the rest.
+pub fn assign_keys<'a>(reminders: impl Iterator<Item = &'a mut Reminder>) {
+    let mut unkeyed = vec![];
+    for reminder in reminders {
+        match reminder.key {
+            0 => unkeyed.push(reminder),
+            key => {
+                NEXT_KEY.fetch_max(key + 1, Ordering::Relaxed);
+            }
+        }
+    }
+    for reminder in unkeyed {
+        reminder.key = next_key();
+    }
+}
+
+pub async fn load_audit() {
+    let Ok(audit_json) = tokio::fs::read_to_string(AUDIT_FILE).await else {
+        return;
+    };
+    let audit: HashMap<UserId, VecDeque<Entry>> = serde_json::from_str(&audit_json).unwrap();
+    // Keys of reminders that have since fired or been cancelled can still be in buttons, so they
+    // aren't handed out again
+    for entry in audit.values().flatten() {
+        NEXT_KEY.fetch_max(entry.key + 1, Ordering::Relaxed);
+    }
+    *AUDIT.lock().await = audit;
+}
+
+pub async fn record(user: UserId, reminder: &Reminder, action: Action, source: &str) {
+    record_key(user, reminder.key, &reminder.message, action, source).await;
+}
+
+/// Like `record`, for a reminder that's only known by its key, like a one-off that already fired.
+pub async fn record_key(user: UserId, key: u64, message: &str, action: Action, source: &str) {
+    let entry = Entry {
+        time: Zoned::now(),
+        key,
+        action,
+        source: source.to_string(),
+        message: message.to_string(),
+    };
+    let mut audit = AUDIT.lock().await;
+    let entries = audit.entry(user).or_default();
+    entries.push_back(entry);
+    while entries.len() > MAX_ENTRIES {
+        entries.pop_front();
+    }
+}
+
+/// Forgets entries recorded before `cutoff`.
+pub async fn purge(cutoff: &Zoned) {
+    let mut audit = AUDIT.lock().await;
+    for entries in audit.values_mut() {
+        entries.retain(|entry| entry.time >= *cutoff);
+    }
+    audit.retain(|_, entries| !entries.is_empty());
+}
+
+/// The most recent entries first, optionally only those for one reminder.
+pub async fn entries(user: UserId, key: Option<u64>, count: usize) -> Vec<Entry> {
+    let audit = AUDIT.lock().await;
+    let Some(entries) = audit.get(&user) else {
+        return vec![];
+    };
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| key.is_none_or(|key| entry.key == key))
+        .take(count)
+        .cloned()
+        .collect()
+}
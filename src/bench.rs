@@ -0,0 +1,49 @@
+//! Entry points for the benchmarks in `benches/`, not used by the bot itself. Only built with
+//! the `bench` feature, e.g. `cargo bench --features bench`.
+
+use jiff::{ToSpan, Zoned};
+use serenity::all::{Http, UserId};
+use untwine::prelude::ParserContext;
+
+use crate::{
+    command, process_reminders, reminders_json, Preferences, Reminder, TimeModifier, REMINDERS,
+};
+
+/// Parses a command without running it, returning whether it was valid.
+pub fn parse(input: &str) -> bool {
+    let mut parser_context = ParserContext::new(input, Preferences::default().time_context());
+    let result = parser_context.result(command::command(&parser_context));
+    result.is_ok()
+}
+
+/// Replaces all reminders with `per_user` synthetic ones for each of `users` users, spread over
+/// the next year with every fourth one repeating weekly. None of them are due yet.
+pub async fn populate(users: u64, per_user: u64) {
+    let now = Zoned::now();
+    let mut cache = REMINDERS.lock().await;
+    cache.clear();
+    for user in 1..=users {
+        let list = cache.entry(UserId::new(user)).or_default();
+        for n in 0..per_user {
+            let offset = (user * 7919 + n * 104_729) % (365 * 24 * 60);
+            list.push(Reminder {
+                interval: (n % 4 == 0).then(|| vec![TimeModifier::Delay(7 * 24 * 60 * 60 * 1000)]),
+                ..Reminder::new(
+                    now.checked_add((offset as i64 + 1).minutes()).unwrap(),
+                    format!("Synthetic reminder {n} for user {user}"),
+                )
+            });
+        }
+        list.sort_by(|a, b| a.time.cmp(&b.time));
+    }
+}
+
+/// One scheduler pass over the current reminders, this also queues a save to the working directory.
+pub async fn process(http: &Http) {
+    process_reminders(http).await;
+}
+
+/// Serializes all reminders the way saving does, returning the size in bytes.
+pub async fn serialize() -> usize {
+    reminders_json(&*REMINDERS.lock().await).map_or(0, |json| json.len())
+}
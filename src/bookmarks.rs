@@ -0,0 +1,176 @@
+use std::{collections::HashMap, fmt::Display, sync::LazyLock};
+
+use jiff::{Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, GuildId, Message, MessageId, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    format_time, save, stats, validate_message, validate_times, CommandError, Preferences,
+    Reminder, Reply,
+};
+
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+const MAX_BOOKMARKS: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MessageLink {
+    /// Unset for messages in DMs
+    guild: Option<GuildId>,
+    channel: ChannelId,
+    message: MessageId,
+}
+
+impl MessageLink {
+    /// Returns `None` if any of the IDs is 0.
+    pub fn new(guild: Option<u64>, channel: u64, message: u64) -> Option<Self> {
+        let guild = match guild {
+            Some(0) => return None,
+            guild => guild.map(GuildId::new),
+        };
+        if channel == 0 || message == 0 {
+            return None;
+        }
+        Some(MessageLink {
+            guild,
+            channel: ChannelId::new(channel),
+            message: MessageId::new(message),
+        })
+    }
+
+    /// The message `msg` is a reply to.
+    pub fn replied_to(msg: &Message) -> Option<Self> {
+        let reference = msg.message_reference.as_ref()?;
+        Some(MessageLink {
+            guild: reference.guild_id.or(msg.guild_id),
+            channel: reference.channel_id,
+            message: reference.message_id?,
+        })
+    }
+}
+
+impl Display for MessageLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let guild = match self.guild {
+            Some(guild) => guild.to_string(),
+            None => "@me".into(),
+        };
+        write!(
+            f,
+            "https://discord.com/channels/{guild}/{}/{}",
+            self.channel, self.message
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    link: MessageLink,
+    note: Option<String>,
+    saved: Timestamp,
+}
+
+impl Bookmark {
+    fn describe(&self) -> String {
+        match &self.note {
+            Some(note) => format!("{note} {}", self.link),
+            None => self.link.to_string(),
+        }
+    }
+}
+
+pub static BOOKMARKS: LazyLock<Mutex<HashMap<UserId, Vec<Bookmark>>>> =
+    LazyLock::new(Default::default);
+
+pub enum BookmarkCommand {
+    /// Without a link the message being replied to is bookmarked
+    Add(Option<MessageLink>, Option<String>),
+    List,
+    Remove(u64),
+    Remind(u64, Vec<Zoned>),
+}
+
+pub async fn load_bookmarks() {
+    let Ok(bookmarks_json) = tokio::fs::read_to_string(BOOKMARKS_FILE).await else {
+        return;
+    };
+    let bookmarks = serde_json::from_str(&bookmarks_json).unwrap();
+    *BOOKMARKS.lock().await = bookmarks;
+}
+
+pub async fn handle(
+    user: UserId,
+    command: BookmarkCommand,
+    replied: Option<MessageLink>,
+    list: &mut Vec<Reminder>,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let mut bookmarks = BOOKMARKS.lock().await;
+    let mine = bookmarks.entry(user).or_default();
+    let response = match command {
+        BookmarkCommand::Add(link, note) => {
+            let link = link.or(replied).ok_or(BookmarkNeedsMessage)?;
+            if mine.len() >= MAX_BOOKMARKS {
+                return Err(TooManyBookmarks(MAX_BOOKMARKS));
+            }
+            let note = note.map(|note| validate_message(&note)).transpose()?;
+            mine.push(Bookmark {
+                link,
+                note,
+                saved: Timestamp::now(),
+            });
+            format!(
+                "Bookmarked {link} (#{}), `$bookmarks remind {} <time>` turns it into a reminder",
+                mine.len() - 1,
+                mine.len() - 1
+            )
+        }
+        BookmarkCommand::List => {
+            if mine.is_empty() {
+                return Ok("You don't have any bookmarks".into());
+            }
+            let lines: Vec<_> = mine
+                .iter()
+                .enumerate()
+                .map(|(id, bookmark)| format!("{id}: {}", bookmark.describe()))
+                .collect();
+            return Ok(format!("Your bookmarks:\n{}", lines.join("\n")).into());
+        }
+        BookmarkCommand::Remove(id) => {
+            if mine.len() <= id as usize {
+                return Err(InvalidID(id));
+            }
+            let bookmark = mine.remove(id as usize);
+            format!("Removed bookmark {}", bookmark.link)
+        }
+        BookmarkCommand::Remind(id, times) => {
+            let bookmark = mine.get(id as usize).ok_or(InvalidID(id))?;
+            let message = validate_message(&bookmark.describe())?;
+            validate_times(&times, preferences)?;
+            mine.remove(id as usize);
+            let mut lines = vec![];
+            for time in times {
+                let reminder = Reminder::new(time.clone(), message.clone());
+                audit::record(user, &reminder, Action::Created, "$bookmarks").await;
+                list.push(reminder);
+                list.sort_by(|a, b| a.time.cmp(&b.time));
+                stats::record(user, |stats| stats.scheduled += 1).await;
+                let id = list
+                    .iter()
+                    .position(|r| r.time == time && r.message == message)
+                    .expect("Reminder was not inserted");
+                lines.push(format!(
+                    "Scheduled reminder for {} (#{id})",
+                    format_time(&time, preferences.time_format())
+                ));
+            }
+            lines.push("The bookmark was removed".into());
+            lines.join("\n")
+        }
+    };
+    save();
+    Ok(response.into())
+}
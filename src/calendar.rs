@@ -0,0 +1,442 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use jiff::Timestamp;
+use reqwest::{StatusCode, Url};
+use ring::aead::LessSafeKey;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    save,
+    secret::{self, open, seal},
+    web::CLIENT,
+    CommandError, Reminder, Reply, REMINDERS,
+};
+
+pub const CALENDAR_FILE: &str = "calendar.json";
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars/primary/events";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+/// Google redirects here after consent. Nothing listens on it, users copy the address they land
+/// on back to the bot instead.
+const REDIRECT_URI: &str = "http://localhost";
+/// How often linked calendars are synced.
+pub const INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Length of the calendar event for a reminder.
+const EVENT_MINUTES: i64 = 15;
+
+/// The event created for a reminder, and the time it was last set to.
+#[derive(Serialize, Deserialize, Clone)]
+struct SyncedEvent {
+    id: String,
+    time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CalendarLink {
+    /// The refresh token, sealed with the key in `CALENDAR_KEY`
+    refresh_token: String,
+    /// Links saved before tokens were sealed have theirs in the clear until a key is set
+    #[serde(default)]
+    sealed: bool,
+    /// Flagged reminders by key, without an event until the next sync
+    #[serde(default)]
+    events: BTreeMap<u64, Option<SyncedEvent>>,
+}
+
+pub static CALENDARS: LazyLock<Mutex<HashMap<UserId, CalendarLink>>> =
+    LazyLock::new(Default::default);
+
+/// Short-lived access tokens, refreshed from the saved refresh token as needed.
+static ACCESS: LazyLock<Mutex<HashMap<UserId, (String, Instant)>>> =
+    LazyLock::new(Default::default);
+
+pub enum CalendarCommand {
+    Link,
+    Code(String),
+    Unlink,
+    Flag(u64, bool),
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    id: String,
+    #[serde(default)]
+    status: String,
+}
+
+/// The integration is enabled by setting `GOOGLE_CLIENT_ID` and `GOOGLE_CLIENT_SECRET`.
+fn credentials() -> Option<(String, String)> {
+    let id = std::env::var("GOOGLE_CLIENT_ID").ok()?;
+    let secret = std::env::var("GOOGLE_CLIENT_SECRET").ok()?;
+    Some((id, secret))
+}
+
+/// Linking a calendar also needs `CALENDAR_KEY` set to 64 hex digits, which seal saved tokens.
+fn key() -> Option<LessSafeKey> {
+    secret::key("CALENDAR_KEY")
+}
+
+pub async fn load_calendars() {
+    let Ok(calendars_json) = tokio::fs::read_to_string(CALENDAR_FILE).await else {
+        return;
+    };
+    let mut calendars: HashMap<UserId, CalendarLink> =
+        serde_json::from_str(&calendars_json).unwrap();
+    let unsealed = calendars.values().filter(|link| !link.sealed).count();
+    if unsealed > 0 {
+        match key() {
+            Some(key) => {
+                for link in calendars.values_mut().filter(|link| !link.sealed) {
+                    link.refresh_token = seal(&key, &link.refresh_token);
+                    link.sealed = true;
+                }
+                save();
+            }
+            None => eprintln!(
+                "{unsealed} Google Calendar refresh tokens are saved in the clear, set CALENDAR_KEY to seal them"
+            ),
+        }
+    }
+    *CALENDARS.lock().await = calendars;
+}
+
+impl CalendarLink {
+    fn refresh_token(&self) -> Result<String, String> {
+        if !self.sealed {
+            return Ok(self.refresh_token.clone());
+        }
+        let key = key().ok_or("CALENDAR_KEY isn't set")?;
+        open(&key, &self.refresh_token)
+            .ok_or_else(|| "The saved refresh token couldn't be unsealed".into())
+    }
+}
+
+async fn request_token(form: &[(&str, &str)]) -> Result<Token, String> {
+    CLIENT
+        .post(TOKEN_URL)
+        .form(form)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())
+}
+
+async fn access_token(user: UserId, refresh_token: &str) -> Result<String, String> {
+    if let Some((token, expires)) = ACCESS.lock().await.get(&user) {
+        if *expires > Instant::now() {
+            return Ok(token.clone());
+        }
+    }
+    let (id, secret) = credentials().ok_or("Google Calendar isn't set up")?;
+    let token = request_token(&[
+        ("client_id", &id),
+        ("client_secret", &secret),
+        ("refresh_token", refresh_token),
+        ("grant_type", "refresh_token"),
+    ])
+    .await?;
+    // Refreshed a minute early so a token doesn't expire mid-sync
+    let expires = Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+    ACCESS
+        .lock()
+        .await
+        .insert(user, (token.access_token.clone(), expires));
+    Ok(token.access_token)
+}
+
+/// Accepts either the bare code or the whole address Google redirected to.
+fn auth_code(pasted: &str) -> String {
+    let code = Url::parse(pasted).ok().and_then(|url| {
+        url.query_pairs()
+            .find(|(name, _)| name == "code")
+            .map(|(_, code)| code.into_owned())
+    });
+    code.unwrap_or_else(|| pasted.trim().to_string())
+}
+
+/// Called without the reminders locked, since linking waits on Google.
+pub async fn handle(
+    user: UserId,
+    command: CalendarCommand,
+    private: bool,
+) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let (id, secret) = credentials().ok_or(CalendarDisabled)?;
+    let response = match command {
+        CalendarCommand::Link => {
+            key().ok_or(CalendarDisabled)?;
+            let url = Url::parse_with_params(
+                AUTH_URL,
+                [
+                    ("client_id", id.as_str()),
+                    ("redirect_uri", REDIRECT_URI),
+                    ("response_type", "code"),
+                    ("scope", SCOPE),
+                    ("access_type", "offline"),
+                    ("prompt", "consent"),
+                ],
+            )
+            .expect("Invalid Google auth URL");
+            format!(
+                "Open <{url}> and allow access. Your browser will then fail to load a localhost page, send me its address in a DM with `$cal code <address>`"
+            )
+        }
+        CalendarCommand::Code(pasted) => {
+            if !private {
+                return Err(CalendarInGuild);
+            }
+            let key = key().ok_or(CalendarDisabled)?;
+            let code = auth_code(&pasted);
+            let token = request_token(&[
+                ("client_id", &id),
+                ("client_secret", &secret),
+                ("code", &code),
+                ("redirect_uri", REDIRECT_URI),
+                ("grant_type", "authorization_code"),
+            ])
+            .await
+            .map_err(Calendar)?;
+            let refresh_token = token
+                .refresh_token
+                .ok_or_else(|| Calendar("Google didn't grant offline access".into()))?;
+            let expires = Instant::now() + Duration::from_secs(token.expires_in);
+            ACCESS
+                .lock()
+                .await
+                .insert(user, (token.access_token, expires));
+            let mut calendars = CALENDARS.lock().await;
+            let link = calendars.entry(user).or_default();
+            link.refresh_token = seal(&key, &refresh_token);
+            link.sealed = true;
+            "Linked your Google Calendar, add reminders to it with `$cal <id> on`".into()
+        }
+        CalendarCommand::Unlink => {
+            if CALENDARS.lock().await.remove(&user).is_none() {
+                return Ok("You haven't linked a Google Calendar".into());
+            }
+            ACCESS.lock().await.remove(&user);
+            "Unlinked your Google Calendar, events already created were left in place".into()
+        }
+        CalendarCommand::Flag(id, enabled) => {
+            // Taken before the calendars, in the same order as `sync`
+            let cache = REMINDERS.lock().await;
+            let reminder = cache
+                .get(&user)
+                .and_then(|list| list.get(id as usize))
+                .ok_or(InvalidID(id))?;
+            let mut calendars = CALENDARS.lock().await;
+            let link = calendars.get_mut(&user).ok_or(CalendarNotLinked)?;
+            if enabled {
+                link.events.entry(reminder.key).or_default();
+                format!(
+                    "Reminder '{}' will be added to your calendar within {} minutes, deleting the event there cancels it",
+                    reminder.message,
+                    INTERVAL.as_secs() / 60
+                )
+            } else {
+                // Its event stays, so it's only cancelled by deleting the reminder here
+                link.events.remove(&reminder.key);
+                format!(
+                    "Reminder '{}' won't be synced to your calendar any more",
+                    reminder.message
+                )
+            }
+        }
+    };
+    save();
+    Ok(response.into())
+}
+
+fn event_body(reminder: &Reminder) -> serde_json::Value {
+    let start = reminder.time.timestamp();
+    let end = start + jiff::SignedDuration::from_mins(EVENT_MINUTES);
+    json!({
+        "summary": reminder.message,
+        "start": {"dateTime": start.to_string()},
+        "end": {"dateTime": end.to_string()},
+    })
+}
+
+/// What a sync found a flagged reminder's event should become.
+enum Outcome {
+    Synced(SyncedEvent),
+    /// The event was deleted in the calendar
+    Deleted,
+}
+
+async fn sync_event(
+    token: &str,
+    reminder: &Reminder,
+    event: Option<SyncedEvent>,
+) -> Result<Outcome, String> {
+    let Some(event) = event else {
+        let created: Event = CLIENT
+            .post(EVENTS_URL)
+            .bearer_auth(token)
+            .json(&event_body(reminder))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?
+            .json()
+            .await
+            .map_err(|err| err.to_string())?;
+        return Ok(Outcome::Synced(SyncedEvent {
+            id: created.id,
+            time: reminder.time.timestamp(),
+        }));
+    };
+    let url = format!("{EVENTS_URL}/{}", event.id);
+    let response = CLIENT
+        .get(&url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+        return Ok(Outcome::Deleted);
+    }
+    let current: Event = response
+        .error_for_status()
+        .map_err(|err| err.to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())?;
+    if current.status == "cancelled" {
+        return Ok(Outcome::Deleted);
+    }
+    if event.time != reminder.time.timestamp() {
+        // Recurring reminders move their event on to the next occurrence
+        CLIENT
+            .patch(&url)
+            .bearer_auth(token)
+            .json(&event_body(reminder))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(Outcome::Synced(SyncedEvent {
+        id: event.id,
+        time: reminder.time.timestamp(),
+    }))
+}
+
+async fn delete_event(token: &str, event: &SyncedEvent) -> Result<(), String> {
+    CLIENT
+        .delete(format!("{EVENTS_URL}/{}", event.id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Creates and moves events for flagged reminders, cancels reminders whose events were deleted in
+/// the calendar, and deletes events for reminders cancelled here.
+pub async fn sync() {
+    if credentials().is_none() {
+        return;
+    }
+    let users: Vec<_> = CALENDARS
+        .lock()
+        .await
+        .iter()
+        .map(|(user, link)| (*user, link.refresh_token(), link.events.clone()))
+        .collect();
+    for (user, refresh_token, events) in users {
+        let token = match refresh_token {
+            Ok(refresh_token) => access_token(user, &refresh_token).await,
+            Err(err) => Err(err),
+        };
+        let token = match token {
+            Ok(token) => token,
+            Err(err) => {
+                eprintln!("Failed to refresh Google Calendar access for {user}: {err}");
+                continue;
+            }
+        };
+        // Copied out so the reminders aren't locked during requests
+        let reminders: HashMap<u64, Reminder> = REMINDERS
+            .lock()
+            .await
+            .get(&user)
+            .into_iter()
+            .flatten()
+            .filter(|reminder| events.contains_key(&reminder.key))
+            .map(|reminder| (reminder.key, reminder.clone()))
+            .collect();
+        let mut outcomes = vec![];
+        for (key, event) in events {
+            let Some(reminder) = reminders.get(&key) else {
+                // Gone here, either delivered for the last time or cancelled
+                if let Some(event) = &event {
+                    let cancelled = audit::entries(user, Some(key), 1)
+                        .await
+                        .first()
+                        .is_some_and(|entry| entry.action == Action::Cancelled);
+                    if cancelled {
+                        if let Err(err) = delete_event(&token, event).await {
+                            eprintln!("Failed to delete a Google Calendar event: {err}");
+                            continue;
+                        }
+                    }
+                }
+                outcomes.push((key, None));
+                continue;
+            };
+            match sync_event(&token, reminder, event).await {
+                Ok(outcome) => outcomes.push((key, Some(outcome))),
+                Err(err) => eprintln!("Failed to sync a Google Calendar event: {err}"),
+            }
+        }
+
+        let mut cache = REMINDERS.lock().await;
+        let mut calendars = CALENDARS.lock().await;
+        let Some(link) = calendars.get_mut(&user) else {
+            continue;
+        };
+        for (key, outcome) in outcomes {
+            match outcome {
+                Some(Outcome::Synced(event)) => {
+                    if let Some(synced) = link.events.get_mut(&key) {
+                        *synced = Some(event);
+                    }
+                }
+                Some(Outcome::Deleted) => {
+                    link.events.remove(&key);
+                    let list = cache.entry(user).or_default();
+                    if let Some(index) = list.iter().position(|r| r.key == key) {
+                        let reminder = list.remove(index);
+                        audit::record(user, &reminder, Action::Cancelled, "calendar").await;
+                    }
+                }
+                None => {
+                    link.events.remove(&key);
+                }
+            }
+        }
+    }
+    save();
+}
@@ -0,0 +1,45 @@
+//! `$callout`, a role pinged when a `$say` reminder is posted, like a raid start announced in a
+//! voice channel's text chat.
+
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage, RoleId, UserId};
+
+use crate::{emphasis, guild, CommandError};
+
+/// Checks that the role belongs to the channel's server and that both the user and the bot are
+/// allowed to ping it there, returning its name.
+pub async fn validate(
+    ctx: &Context,
+    user: UserId,
+    channel: ChannelId,
+    role: RoleId,
+) -> Result<String, CommandError> {
+    let server = guild::channel_guild(ctx, channel)
+        .await
+        .ok_or(CommandError::CannotPostIn(channel))?;
+    let roles = server.roles(ctx).await.unwrap_or_default();
+    let role = roles.get(&role).ok_or(CommandError::InvalidRole)?;
+    if !role.mentionable {
+        let bot = ctx.cache.current_user().id;
+        for member in [user, bot] {
+            let permissions = guild::channel_permissions(ctx, channel, member)
+                .await
+                .unwrap_or_default();
+            if !permissions.mention_everyone() {
+                return Err(CommandError::CannotCallOut(role.name.clone()));
+            }
+        }
+    }
+    Ok(role.name.clone())
+}
+
+/// Mentions the role at the start of a channel reminder, keeping an urgent reminder's mention of
+/// its owner working alongside it.
+pub fn apply(message: CreateMessage, role: RoleId, owner: Option<UserId>) -> CreateMessage {
+    let mentioned = format!("<@&{role}> {}", emphasis::content(&message));
+    let message = if mentioned.chars().count() <= 2000 {
+        message.content(mentioned)
+    } else {
+        message
+    };
+    message.allowed_mentions(CreateAllowedMentions::new().roles([role]).users(owner))
+}
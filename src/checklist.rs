@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::LazyLock,
+};
+
+use jiff::{Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, UserId};
+use tokio::sync::Mutex;
+
+use crate::{save, Reminder};
+
+pub const CHECKLISTS_FILE: &str = "checklists.json";
+
+/// Items a checklist can have, Discord allows 5 rows of buttons and the first is the reminder's.
+const MAX_ITEMS: usize = 20;
+/// Delivered checklists kept per user, the oldest stop responding to their buttons first.
+const MAX_CHECKLISTS: usize = 50;
+/// Discord's limit on button labels.
+const MAX_LABEL_LENGTH: usize = 80;
+
+const UNCHECKED: &str = "⬜ ";
+const CHECKED: &str = "✅ ";
+
+/// A `name: item, item, ...` message.
+pub struct Parsed<'a> {
+    pub name: &'a str,
+    pub items: Vec<&'a str>,
+}
+
+/// Reads a checklist out of a message with at least two comma separated items after a `: `.
+pub fn parse(message: &str) -> Option<Parsed<'_>> {
+    let (name, rest) = message.split_once(": ")?;
+    let items: Vec<_> = rest.split(',').map(str::trim).collect();
+    if name.trim().is_empty()
+        || !(2..=MAX_ITEMS).contains(&items.len())
+        || items.iter().any(|item| item.is_empty())
+    {
+        return None;
+    }
+    Some(Parsed {
+        name: name.trim(),
+        items,
+    })
+}
+
+/// One line per item, ticked off if done.
+pub fn lines(items: &[impl AsRef<str>], done: &[bool]) -> String {
+    items
+        .iter()
+        .zip(done.iter().chain(std::iter::repeat(&false)))
+        .map(|(item, done)| {
+            let mark = if *done { CHECKED } else { UNCHECKED };
+            format!("{mark}{}", item.as_ref())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Swaps the item lines at the end of a delivered message for new ones.
+pub fn replace_lines(content: &str, items: &[impl AsRef<str>], done: &[bool]) -> String {
+    let mut kept: Vec<_> = content.lines().collect();
+    while kept
+        .last()
+        .is_some_and(|line| line.starts_with(UNCHECKED) || line.starts_with(CHECKED))
+    {
+        kept.pop();
+    }
+    format!("{}\n{}", kept.join("\n"), lines(items, done))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Checklist {
+    key: u64,
+    items: Vec<String>,
+    done: Vec<bool>,
+    /// Checklists saved before this was kept count from when they were loaded
+    #[serde(default = "Timestamp::now")]
+    delivered: Timestamp,
+}
+
+/// Checklists of delivered reminders by their reminder's key, newest last.
+pub static CHECKLISTS: LazyLock<Mutex<HashMap<UserId, VecDeque<Checklist>>>> =
+    LazyLock::new(Default::default);
+
+pub async fn load_checklists() {
+    let Ok(checklists_json) = tokio::fs::read_to_string(CHECKLISTS_FILE).await else {
+        return;
+    };
+    let checklists = serde_json::from_str(&checklists_json).unwrap();
+    *CHECKLISTS.lock().await = checklists;
+}
+
+fn rows(user: UserId, checklist: &Checklist) -> Vec<CreateActionRow> {
+    let buttons: Vec<_> = checklist
+        .items
+        .iter()
+        .zip(&checklist.done)
+        .enumerate()
+        .map(|(index, (item, done))| {
+            let label: String = item.chars().take(MAX_LABEL_LENGTH).collect();
+            let style = if *done {
+                ButtonStyle::Success
+            } else {
+                ButtonStyle::Secondary
+            };
+            CreateButton::new(format!("check:{user}:{}:{index}", checklist.key))
+                .label(label)
+                .style(style)
+        })
+        .collect();
+    buttons
+        .chunks(5)
+        .map(|row| CreateActionRow::Buttons(row.to_vec()))
+        .collect()
+}
+
+/// Starts a fresh checklist for a delivery of `reminder`, returning its buttons. Repeating
+/// reminders start over each time they're delivered.
+pub async fn start(user: UserId, reminder: &Reminder) -> Vec<CreateActionRow> {
+    let Some(parsed) = parse(&reminder.message) else {
+        return vec![];
+    };
+    let checklist = Checklist {
+        key: reminder.origin(),
+        items: parsed.items.iter().map(|item| item.to_string()).collect(),
+        done: vec![false; parsed.items.len()],
+        delivered: Timestamp::now(),
+    };
+    let rows = rows(user, &checklist);
+    let mut checklists = CHECKLISTS.lock().await;
+    let mine = checklists.entry(user).or_default();
+    mine.retain(|other| other.key != reminder.origin());
+    mine.push_back(checklist);
+    while mine.len() > MAX_CHECKLISTS {
+        mine.pop_front();
+    }
+    drop(checklists);
+    save();
+    rows
+}
+
+/// Ticks an item off or back on, returning the new message content and checklist buttons.
+pub async fn toggle(
+    user: UserId,
+    key: u64,
+    index: usize,
+    content: &str,
+) -> Option<(String, Vec<CreateActionRow>)> {
+    let mut checklists = CHECKLISTS.lock().await;
+    let checklist = checklists
+        .get_mut(&user)?
+        .iter_mut()
+        .find(|checklist| checklist.key == key)?;
+    let done = checklist.done.get_mut(index)?;
+    *done = !*done;
+    let content = replace_lines(content, &checklist.items, &checklist.done);
+    let rows = rows(user, checklist);
+    drop(checklists);
+    save();
+    Some((content, rows))
+}
+
+/// Forgets checklists delivered before `cutoff` whose reminder isn't in `live` any more, because
+/// it was a one-off or stopped repeating.
+pub async fn purge(cutoff: &Zoned, live: &HashMap<UserId, HashSet<u64>>) {
+    let cutoff = cutoff.timestamp();
+    let mut checklists = CHECKLISTS.lock().await;
+    for (user, mine) in checklists.iter_mut() {
+        let keys = live.get(user);
+        mine.retain(|checklist| {
+            checklist.delivered >= cutoff || keys.is_some_and(|keys| keys.contains(&checklist.key))
+        });
+    }
+    checklists.retain(|_, mine| !mine.is_empty());
+}
@@ -1,10 +1,41 @@
-use std::num::ParseIntError;
+#![allow(clippy::manual_is_ascii_check)]
 
-use jiff::{civil::Weekday, Zoned};
+use std::{
+    collections::BTreeMap,
+    num::{ParseFloatError, ParseIntError},
+    ops::Range,
+};
+
+use jiff::{
+    civil::{Date, Time, Weekday},
+    tz::TimeZone,
+    Zoned,
+};
+use serenity::all::{ChannelId, UserId};
 use thiserror::Error;
 use untwine::prelude::*;
 
-use crate::{TimeFormat, TimeModifier};
+use crate::{
+    bookmarks::{BookmarkCommand, MessageLink},
+    calendar::CalendarCommand,
+    condition::{Check, Guard, Online, Url, Weather, Window},
+    emphasis::Emphasis,
+    feeds::FeedCommand,
+    holidays::HolidayMode,
+    humanize::Language,
+    interval::{self, IntervalEdit, ModifierKind},
+    listing::{ListGroup, ListOption, ListOptions, ListSort, ListStyle},
+    matrix::MatrixCommand,
+    presence::DEFAULT_MAX_HOLD,
+    quarter::QuarterPosition,
+    sequence::SequenceCommand,
+    shared::SharedListCommand,
+    solar::{Location, SunEvent},
+    tasks::{ProviderKind, TasksCommand},
+    telegram::TelegramCommand,
+    trigger::{Trigger, TriggerCommand},
+    Period, PeriodDay, TimeFormat, TimeModifier, WeekdayMode,
+};
 
 #[derive(Error, Debug)]
 pub enum ParseTimeError {
@@ -12,24 +43,461 @@ pub enum ParseTimeError {
     Untwine(#[from] ParserError),
     #[error("{0}")]
     ParseInt(#[from] ParseIntError),
+    #[error("{0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("{0}")]
+    Jiff(#[from] jiff::Error),
+    #[error("{0} must be {1}")]
+    OutOfRange(&'static str, &'static str),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("You don't have an offset called {0}, set one with `$offset set {0} <duration>`")]
+    UnknownOffset(String),
+    #[error("Set your location with `$location <latitude> <longitude>` to use sunrise and sunset")]
+    NoLocation,
+    #[error("That isn't a valid user, mention them like @name")]
+    InvalidUser,
+    #[error("That isn't a valid channel, mention it like #name")]
+    InvalidChannel,
+    #[error("That isn't a valid message link")]
+    InvalidLink,
+    #[error("That delay is too long")]
+    DelayTooLong,
 }
 
 pub enum Command {
-    ScheduleReminder(Vec<Zoned>, String),
+    ScheduleReminder(Vec<Zoned>, Option<Guard>, String, Option<u64>),
+    ScheduleAfter(u64, u64, String),
+    ScheduleShared(String, Vec<Zoned>, String),
+    SharedList(SharedListCommand),
+    Sequence(SequenceCommand),
     CancelReminder(u64),
     SetInterval(u64, Vec<TimeModifier>),
+    SetCronInterval(u64, String),
+    SetRRuleInterval(u64, String),
+    EditInterval(u64, IntervalEdit),
     ClearInterval(u64),
+    SkipOccurrence(u64),
+    Simulate(u64, u64),
+    Transfer(u64, u64),
+    SetPublic(u64, bool),
+    Follow(u64, u64),
+    Unfollow(u64, u64),
+    SkipHolidays(u64, HolidayMode),
+    Holidays(HolidayCommand),
     SetTimezone(String),
     SetTimeFormat(TimeFormat),
-    ListReminders,
-    Help,
+    SetWeekdayMode(WeekdayMode),
+    SetListStyle(ListStyle),
+    SetFiscalYearStart(u64),
+    SetLocation(f64, f64),
+    SetOffset(String, u64),
+    RemoveOffset(String),
+    ListOffsets,
+    ClearLocation,
+    SetDelivery(Option<u64>),
+    Telegram(TelegramCommand),
+    Matrix(MatrixCommand),
+    Dashboard,
+    Calendar(CalendarCommand),
+    Tasks(TasksCommand),
+    Feed(FeedCommand),
+    ScheduleTrigger(Trigger, String),
+    Trigger(TriggerCommand),
+    Bookmark(BookmarkCommand),
+    ShowPreferences,
+    SetTemplate(Option<String>),
+    SetNatural(bool),
+    SetHorizon(Option<u64>),
+    SetLanguage(Language),
+    SetPeriodDay(PeriodDay),
+    AddNote(u64, String),
+    SetJitter(u64, Option<u64>),
+    SetExpiry(u64, Option<u64>),
+    SetDefaultExpiry(Option<u64>),
+    SetNextUp(bool),
+    SetReview(bool),
+    Done(u64),
+    SetHabit(u64, bool),
+    SetEmphasis(u64, Emphasis),
+    SetCallout(u64, Option<u64>),
+    ImportCsv,
+    ExportCsv,
+    ListReminders(ListOptions),
+    History,
+    Audit(Option<u64>),
+    Help(Option<String>),
+    About,
+    Ping,
+    Stats,
+    Server(ServerCommand),
+    Announce(AnnouncementCommand),
+    Admin(AdminCommand),
+}
+
+pub enum ServerCommand {
+    SetTimezone(String),
+    SetTimeFormat(TimeFormat),
+    Listen(u64, bool),
+}
+
+pub enum HolidayCommand {
+    Show,
+    SetCountry(String),
+    Clear,
+    Add(Date),
+    Remove(Date),
+}
+
+pub enum AnnouncementCommand {
+    Add(u64, Vec<Zoned>, Vec<TimeModifier>, String),
+    List,
+    Cancel(u64),
+}
+
+pub enum AdminCommand {
+    Report,
+    Tasks,
+    Stats,
+    Reload,
 }
 
+impl Command {
+    pub fn requires_admin(&self) -> bool {
+        matches!(self, Command::Server(_))
+    }
+
+    pub fn requires_moderator(&self) -> bool {
+        matches!(self, Command::Announce(_))
+    }
+
+    pub fn requires_operator(&self) -> bool {
+        matches!(self, Command::Admin(_))
+    }
+
+    /// What sort of command this is for usage counts, without any of what was in it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Command::ScheduleReminder(..) => "remind",
+            Command::ScheduleAfter(..) => "remind after",
+            Command::ScheduleShared(..) => "remind list",
+            Command::ScheduleTrigger(..) => "remind when",
+            Command::SharedList(_) => "list",
+            Command::Sequence(_) => "sequence",
+            Command::CancelReminder(_) => "cancel",
+            Command::SetInterval(..) => "interval",
+            Command::SetCronInterval(..) => "interval cron",
+            Command::SetRRuleInterval(..) => "interval rrule",
+            Command::EditInterval(..) => "interval edit",
+            Command::ClearInterval(_) => "clear interval",
+            Command::SkipOccurrence(_) => "skip",
+            Command::Simulate(..) => "simulate",
+            Command::Transfer(..) => "transfer",
+            Command::SetPublic(..) => "public",
+            Command::Follow(..) => "follow",
+            Command::Unfollow(..) => "unfollow",
+            Command::SkipHolidays(..) => "skip holidays",
+            Command::Holidays(_) => "holidays",
+            Command::SetTimezone(_) => "timezone",
+            Command::SetTimeFormat(_) => "time format",
+            Command::SetWeekdayMode(_) => "weekdays",
+            Command::SetListStyle(_) => "list style",
+            Command::SetFiscalYearStart(_) => "fiscal year",
+            Command::SetLocation(..) | Command::ClearLocation => "location",
+            Command::SetOffset(..) | Command::RemoveOffset(_) | Command::ListOffsets => "offset",
+            Command::SetDelivery(_) => "delivery",
+            Command::Telegram(_) => "telegram",
+            Command::Matrix(_) => "matrix",
+            Command::Dashboard => "dashboard",
+            Command::Calendar(_) => "calendar",
+            Command::Tasks(_) => "tasks",
+            Command::Feed(_) => "feed",
+            Command::Trigger(_) => "trigger",
+            Command::Bookmark(_) => "bookmark",
+            Command::ShowPreferences => "prefs",
+            Command::SetTemplate(_) => "template",
+            Command::SetNatural(_) => "natural",
+            Command::SetHorizon(_) => "horizon",
+            Command::SetLanguage(_) => "language",
+            Command::SetPeriodDay(_) => "next period",
+            Command::AddNote(..) => "note",
+            Command::SetJitter(..) => "jitter",
+            Command::SetExpiry(..) | Command::SetDefaultExpiry(_) => "expire",
+            Command::SetNextUp(_) => "next up",
+            Command::SetReview(_) => "review",
+            Command::Done(_) => "done",
+            Command::SetHabit(..) => "habit",
+            Command::SetEmphasis(..) => "emphasis",
+            Command::SetCallout(..) => "callout",
+            Command::ImportCsv => "import",
+            Command::ExportCsv => "export",
+            Command::ListReminders(_) => "reminders",
+            Command::History => "history",
+            Command::Audit(_) => "audit",
+            Command::Help(_) => "help",
+            Command::About => "about",
+            Command::Ping => "ping",
+            Command::Stats => "stats",
+            Command::Server(_) => "server",
+            Command::Announce(_) => "announce",
+            Command::Admin(_) => "admin",
+        }
+    }
+}
+
+/// One space separated part of a time, permutations like `(2pm, 4pm)` included.
+#[derive(Debug, Clone)]
 pub enum Modifier {
     TimeModifier(TimeModifier),
     ModifierPermutations(Vec<Vec<TimeModifier>>),
 }
 
+pub const COMMAND_NAMES: &[&str] = &[
+    "remindme",
+    "reminder",
+    "r",
+    "help",
+    "h",
+    "setinterval",
+    "si",
+    "clearinterval",
+    "ci",
+    "cancelreminder",
+    "cr",
+    "reminders",
+    "rs",
+    "timezone",
+    "tz",
+    "timeformat",
+    "tf",
+    "weekdays",
+    "liststyle",
+    "fiscal",
+    "location",
+    "offset",
+    "offsets",
+    "delivery",
+    "telegram",
+    "matrix",
+    "dashboard",
+    "cal",
+    "calendar",
+    "tasks",
+    "push",
+    "watch",
+    "watches",
+    "unwatch",
+    "triggers",
+    "bookmark",
+    "bookmarks",
+    "preferences",
+    "prefs",
+    "about",
+    "ping",
+    "version",
+    "stats",
+    "server",
+    "sv",
+    "admin",
+    "skip",
+    "note",
+    "jitter",
+    "expire",
+    "nextup",
+    "review",
+    "done",
+    "habit",
+    "emphasis",
+    "callout",
+    "import",
+    "export",
+    "simulate",
+    "say",
+    "list",
+    "seq",
+    "sequence",
+    "transfer",
+    "history",
+    "audit",
+    "public",
+    "follow",
+    "unfollow",
+    "announce",
+    "an",
+    "skipholidays",
+    "sh",
+    "holidays",
+];
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Undoes what mobile keyboards do to typed commands: curly quotes, non-breaking spaces, doubled
+/// spaces and capitalized command names. Only the part before the first `;` or quote is squeezed,
+/// so reminder messages keep their spacing.
+pub fn normalize(input: &str) -> String {
+    let input: String = input
+        .chars()
+        .map(|c| match c {
+            '\u{a0}' | '\u{2007}' | '\u{202f}' | '\t' => ' ',
+            '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{ab}' | '\u{bb}' => '"',
+            '\u{2018}' | '\u{2019}' | '\u{201a}' => '\'',
+            c => c,
+        })
+        .collect();
+    let (head, tail) = match input.find([';', '"']) {
+        Some(index) => (&input[..index], Some(&input[index..])),
+        None => (input.as_str(), None),
+    };
+    let mut normalized = String::with_capacity(input.len());
+    for (index, word) in head.split(' ').filter(|word| !word.is_empty()).enumerate() {
+        // Command names are matched in lowercase by the grammar
+        let word = match index {
+            0 => word.to_ascii_lowercase(),
+            _ => word.to_string(),
+        };
+        if !normalized.is_empty() && !word.starts_with(',') {
+            normalized.push(' ');
+        }
+        normalized.push_str(&word);
+    }
+    if let Some(tail) = tail {
+        if tail.starts_with('"') && !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.push_str(tail.trim_end());
+    }
+    normalized
+}
+
+pub enum CommandName {
+    Known,
+    Unknown(Option<&'static str>),
+}
+
+/// Classifies the command word of an input, suggesting the closest known command for typos.
+pub fn match_command_name(input: &str) -> CommandName {
+    let word = input.split_whitespace().next().unwrap_or_default();
+    let word = word.split(';').next().unwrap_or_default().to_lowercase();
+    if COMMAND_NAMES.contains(&word.as_str()) {
+        return CommandName::Known;
+    }
+    let suggestion = COMMAND_NAMES
+        .iter()
+        .filter(|name| name.len() > 2)
+        .map(|name| (edit_distance(&word, name), *name))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name);
+    CommandName::Unknown(suggestion)
+}
+
+/// What time expressions are relative to, from the user's preferences.
+pub struct TimeContext {
+    pub time_zone: TimeZone,
+    pub weekday_mode: WeekdayMode,
+    pub fiscal_start: i8,
+    pub period_day: PeriodDay,
+    pub location: Option<Location>,
+    pub offsets: BTreeMap<String, u64>,
+}
+
+/// Applies modifiers left to right. In smart mode a weekday naming today stays on today when a
+/// later time of day is still ahead, so `tuesday 9am` on a Tuesday morning means this morning.
+/// Alongside a date a weekday only confirms it, `conflict` checks that it matches.
+fn resolve(
+    start: Zoned,
+    now: &Zoned,
+    modifiers: &[TimeModifier],
+    mode: WeekdayMode,
+) -> Result<Zoned, jiff::Error> {
+    let dated = modifiers
+        .iter()
+        .any(|m| matches!(m, TimeModifier::Date { .. }));
+    let mut date = start;
+    for (i, modifier) in modifiers.iter().enumerate() {
+        if let TimeModifier::Weekday(weekday) = modifier {
+            if dated {
+                continue;
+            }
+            let rest = &modifiers[i + 1..];
+            let today = date.weekday().to_monday_zero_offset() == *weekday;
+            let timed = rest
+                .iter()
+                .any(|m| matches!(m, TimeModifier::TimeOfDay { .. } | TimeModifier::Sun { .. }));
+            if mode == WeekdayMode::Smart && today && timed {
+                let kept = resolve(date.clone(), now, rest, mode)?;
+                if kept > *now {
+                    return Ok(kept);
+                }
+            }
+        }
+        date = modifier.modify(date)?;
+    }
+    Ok(date)
+}
+
+/// Adds up the parts of a delay like `1w2d`, None if they don't fit in a `TimeModifier::Delay`.
+fn total_delay(delays: &[u64]) -> Option<i64> {
+    let total = delays
+        .iter()
+        .try_fold(0u64, |total, ms| total.checked_add(*ms))?;
+    i64::try_from(total).ok()
+}
+
+/// Moves negative delays after everything else, so `-30m friday 5pm` means the same as
+/// `friday 5pm -30m` instead of the time of day overriding the subtraction.
+fn subtractions_last(modifiers: Vec<TimeModifier>) -> Vec<TimeModifier> {
+    let (subtractions, mut rest): (Vec<_>, Vec<_>) = modifiers
+        .into_iter()
+        .partition(|m| matches!(m, TimeModifier::Delay(ms) if *ms < 0));
+    rest.extend(subtractions);
+    rest
+}
+
+/// Explains why modifiers contradict each other, such as two times of day or a date that falls on
+/// a different weekday than the one given.
+fn conflict(modifiers: &[TimeModifier], date: &Zoned) -> Option<String> {
+    let times: Vec<_> = modifiers
+        .iter()
+        .filter_map(|m| match m {
+            TimeModifier::TimeOfDay { hour, minute } => Some(format!("{hour}:{minute:02}")),
+            TimeModifier::Sun { event, .. } => Some(event.to_string()),
+            _ => None,
+        })
+        .collect();
+    if let [first, second, ..] = &times[..] {
+        return Some(format!(
+            "{first} and {second} are both times of day, use `({first}, {second})` for one reminder at each"
+        ));
+    }
+    let dated = modifiers
+        .iter()
+        .any(|m| matches!(m, TimeModifier::Date { .. }));
+    let actual = date.weekday().to_monday_zero_offset();
+    modifiers.iter().find_map(|m| match m {
+        TimeModifier::Weekday(weekday) if dated && *weekday != actual => Some(format!(
+            "{} is a {}, not a {}",
+            date.date(),
+            interval::weekday_name(actual),
+            interval::weekday_name(*weekday)
+        )),
+        _ => None,
+    })
+}
+
 impl Modifier {
     pub fn into_time_modifiers(modifiers: Vec<Modifier>) -> Vec<Vec<TimeModifier>> {
         let mut final_modifiers = vec![vec![]];
@@ -57,9 +525,10 @@ impl Modifier {
 }
 
 parser! {
-    [error = ParseTimeError, data = jiff::tz::TimeZone]
+    [error = ParseTimeError, data = TimeContext]
     num: num=<'0'-'9'+> -> u64 { num.parse()? }
     comma = ' '* ',' ' '*;
+    semicolon = ' '* ';' ' '*;
 
     unit = match {
         "w" => 7 * 24 * 60 * 60 * 1000,
@@ -70,7 +539,11 @@ parser! {
     } -> u64;
 
     delay: num=num unit=unit -> u64 {
-        num * unit
+        let Some(ms) = num.checked_mul(unit) else {
+            __ctx.replace_err(ParseTimeError::DelayTooLong);
+            return Err(ParseTimeError::DelayTooLong);
+        };
+        ms
     }
 
     months: num=num "mo" -> TimeModifier {
@@ -81,28 +554,80 @@ parser! {
         TimeModifier::Years(num)
     }
 
-    weekday = match {
-        ("monday" | "Monday") => Weekday::Monday,
-        ("tuesday" | "Tuesday") => Weekday::Tuesday,
-        ("wednesday" | "Wednesday") => Weekday::Wednesday,
-        ("thursday" | "Thursday") => Weekday::Thursday,
-        ("friday" | "Friday") => Weekday::Friday,
-        ("saturday" | "Saturday") => Weekday::Saturday,
-        ("sunday" | "Sunday") => Weekday::Sunday,
-    } -> Weekday;
+    period = match {
+        "month" => Period::Month,
+        "year" => Period::Year,
+    } -> Period;
+
+    next_period: "next" " " period=period -> TimeModifier {
+        TimeModifier::Next { period, day: __ctx.data().period_day }
+    }
+
+    // Any word starting like a weekday is read as one, so other words keep their usual errors
+    weekday: name=<('m' | 'M' | 't' | 'T' | 'w' | 'W' | 'f' | 'F' | 's' | 'S') ('a'-'z' | 'A'-'Z')*> -> Weekday {
+        match name.to_ascii_lowercase().as_str() {
+            "monday" => Weekday::Monday,
+            "tuesday" => Weekday::Tuesday,
+            "wednesday" => Weekday::Wednesday,
+            "thursday" => Weekday::Thursday,
+            "friday" => Weekday::Friday,
+            "saturday" => Weekday::Saturday,
+            "sunday" => Weekday::Sunday,
+            _ => Err(ParserError::ExpectedToken("weekday"))?,
+        }
+    }
 
     weekday_modifier: weekday=weekday -> TimeModifier {
         TimeModifier::Weekday(weekday.to_monday_zero_offset())
     }
 
-    delays: delays=delay+ -> TimeModifier { TimeModifier::Delay(delays.into_iter().sum()) }
+    delays: delays=delay+ -> TimeModifier {
+        let Some(ms) = total_delay(&delays) else {
+            __ctx.replace_err(ParseTimeError::DelayTooLong);
+            return Err(ParseTimeError::DelayTooLong);
+        };
+        TimeModifier::Delay(ms)
+    }
+
+    signed_delays: sign=<('+' | '-')> delays=delay+ -> TimeModifier {
+        let Some(ms) = total_delay(&delays) else {
+            __ctx.replace_err(ParseTimeError::DelayTooLong);
+            return Err(ParseTimeError::DelayTooLong);
+        };
+        TimeModifier::Delay(if sign == "-" { -ms } else { ms })
+    }
+
+    offset_name: name=<('a'-'z' | 'A'-'Z') ('a'-'z' | 'A'-'Z' | '0'-'9' | '-' | '_')*> -> String { name.to_lowercase() }
 
-    time_of_day: hour=num minute=(":" num)? specifier=<("am"|"pm")?> -> TimeModifier {
+    named_offset: sign=<('+' | '-')> name=offset_name -> TimeModifier {
+        let Some(ms) = __ctx.data().offsets.get(&name).copied() else {
+            // Reported at the end of the name so it outranks errors from other alternatives
+            __ctx.replace_err(ParseTimeError::UnknownOffset(name.clone()));
+            return Err(ParseTimeError::UnknownOffset(name));
+        };
+        let ms = ms as i64;
+        TimeModifier::Delay(if sign == "-" { -ms } else { ms })
+    }
+
+    time_of_day: hour=num minute=(":" num)? specifier=<(('a' | 'A' | 'p' | 'P') ('m' | 'M'))?> -> TimeModifier {
+        let specifier = specifier.to_ascii_lowercase();
+        let specifier = specifier.as_str();
         let minute = minute.unwrap_or(0);
+        let range = match specifier {
+            _ if minute > 59 => Some(("Minutes", "0-59")),
+            "am" | "pm" if !(1..=12).contains(&hour) => Some(("Hours", "1-12 with am/pm")),
+            "" if hour > 23 => Some(("Hours", "0-23")),
+            _ => None,
+        };
+        if let Some((field, range)) = range {
+            // Reported at the end of the time so it outranks errors from other alternatives
+            __ctx.replace_err(ParseTimeError::OutOfRange(field, range));
+            return Err(ParseTimeError::OutOfRange(field, range));
+        }
         let hour = match specifier {
             "am" => hour % 12,
             "pm" => (hour % 12) + 12,
-            "" => hour % 24,
+            "" => hour,
             _ => unreachable!("Unexpected time of day specifier")
         };
         TimeModifier::TimeOfDay { minute, hour }
@@ -115,7 +640,50 @@ parser! {
         TimeModifier::Date { year, month, day }
     }
 
-    time_modifier = (months | years | delays | date | time_of_day | weekday_modifier) -> TimeModifier;
+    iso_week: "w" week=num weekday=(" " weekday)? -> TimeModifier {
+        if !(1..=53).contains(&week) {
+            // Reported at the end of the week so it outranks errors from other alternatives
+            __ctx.replace_err(ParseTimeError::OutOfRange("Week numbers", "1-53"));
+            return Err(ParseTimeError::OutOfRange("Week numbers", "1-53"));
+        }
+        let weekday = weekday.unwrap_or(Weekday::Monday).to_monday_zero_offset();
+        TimeModifier::IsoWeek { week: week as i8, weekday }
+    }
+
+    quarter_position = match {
+        ("q" | "Q") quarter=num => {
+            if !(1..=4).contains(&quarter) {
+                // Reported at the end of the quarter so it outranks errors from other alternatives
+                __ctx.replace_err(ParseTimeError::OutOfRange("Quarters", "1-4"));
+                return Err(ParseTimeError::OutOfRange("Quarters", "1-4"));
+            }
+            QuarterPosition::Start(quarter as i8)
+        },
+        "end of quarter" => QuarterPosition::End,
+        "next quarter" => QuarterPosition::Next,
+    } -> QuarterPosition;
+
+    quarter: position=quarter_position -> TimeModifier {
+        TimeModifier::Quarter { position, fiscal_start: __ctx.data().fiscal_start }
+    }
+
+    sun_event = match {
+        "sunrise" => SunEvent::Sunrise,
+        "sunset" => SunEvent::Sunset,
+    } -> SunEvent;
+
+    sun: event=sun_event -> TimeModifier {
+        let Some(location) = __ctx.data().location else {
+            // Reported at the end of the keyword so it outranks errors from other alternatives
+            __ctx.replace_err(ParseTimeError::NoLocation);
+            return Err(ParseTimeError::NoLocation);
+        };
+        TimeModifier::Sun { event, location }
+    }
+
+    time_modifier = (months | years | next_period | delays | date | signed_delays | named_offset | time_of_day | iso_week | quarter | sun | weekday_modifier) -> TimeModifier;
+
+    coordinate: value=<'-'? '0'-'9'+ ('.' '0'-'9'+)?> -> f64 { value.parse()? }
 
     modifier_permutations = "(" (time_modifier$" "+)$comma+ ")" -> Vec<Vec<TimeModifier>>;
 
@@ -124,38 +692,363 @@ parser! {
         permutations=modifier_permutations => Modifier::ModifierPermutations(permutations),
     } -> Modifier;
 
+    list_option = match {
+        "sort:" "time" => ListOption::Sort(ListSort::Time),
+        "sort:" "created" => ListOption::Sort(ListSort::Created),
+        "sort:" "alpha" => ListOption::Sort(ListSort::Alpha),
+        "group:" "day" => ListOption::Group(ListGroup::Day),
+        "group:" "tag" => ListOption::Group(ListGroup::Tag),
+    } -> ListOption;
+
+    list_style = match {
+        "compact" => ListStyle::Compact,
+        "verbose" => ListStyle::Verbose,
+    } -> ListStyle;
+
+    period_day = match {
+        "first" => PeriodDay::First,
+        "same" => PeriodDay::Same,
+    } -> PeriodDay;
+
+    language = match {
+        ("english" | "en") => Language::En,
+        ("french" | "français" | "fr") => Language::Fr,
+        ("spanish" | "español" | "es") => Language::Es,
+        ("german" | "deutsch" | "de") => Language::De,
+    } -> Language;
+
+    weekday_mode = match {
+        "smart" => WeekdayMode::Smart,
+        "next" => WeekdayMode::Next,
+    } -> WeekdayMode;
+
     time_format = match {
         "12h" => TimeFormat::H12,
         "24h" => TimeFormat::H24,
     } -> TimeFormat;
 
-    match_commands = match {
-        ("r" | "remindme" | "reminder") " " time=time ";" " "? message=<.+> => Command::ScheduleReminder(time, message.to_string()),
-        ("h" | "help") => Command::Help,
+    channel = match {
+        "<#" id=num ">" => id,
+        id=num => id,
+    } -> u64;
+
+    role = match {
+        "<@&" id=num ">" => id,
+        id=num => id,
+    } -> u64;
+
+    user = match {
+        "<@!" id=num ">" => id,
+        "<@" id=num ">" => id,
+        id=num => id,
+    } -> u64;
+
+    list_name: name=<('a'-'z' | 'A'-'Z' | '0'-'9' | '-' | '_')+> -> String { name.to_lowercase() }
+
+    // Seconds to spread copies over, None goes back to the configured default
+    jitter = match {
+        "reset" => None,
+        minutes=num "m" => Some(minutes * 60),
+        seconds=num "s"? => Some(seconds),
+    } -> Option<u64>;
+
+    emphasis = match {
+        ("quiet" | "silent") => Emphasis::Quiet,
+        "normal" => Emphasis::Normal,
+        ("urgent" | "loud") => Emphasis::Urgent,
+    } -> Emphasis;
+
+    // Milliseconds a delivered reminder is kept for, 0 keeps it
+    expire_after = match {
+        ("off" | "keep" | "never") => 0,
+        delays=delay+ => delays.into_iter().sum(),
+    } -> u64;
+
+    shared_list_command = match {
+        "create" " " name=list_name => SharedListCommand::Create(name),
+        "delete" " " name=list_name => SharedListCommand::Delete(name),
+        "add" " " name=list_name " " user=user => SharedListCommand::Add(name, user),
+        "remove" " " name=list_name " " user=user => SharedListCommand::Remove(name, user),
+        "leave" " " name=list_name => SharedListCommand::Leave(name),
+        "accept" " " name=list_name => SharedListCommand::Accept(name),
+        "decline" " " name=list_name => SharedListCommand::Decline(name),
+        "cancel" " " name=list_name " " id=num => SharedListCommand::Cancel(name, id),
+        "repeat" " " name=list_name " " id=num " " modifiers=time_modifier$" "+ => SharedListCommand::Repeat(name, id, modifiers),
+        "jitter" " " name=list_name " " id=num " " jitter=jitter => SharedListCommand::Jitter(name, id, jitter),
+        name=list_name => SharedListCommand::Show(name),
+    } -> SharedListCommand;
+
+    quoted: '"' text=<[^"\""]+> '"' -> String { text.to_string() }
+
+    sequence_name = match {
+        name=quoted => name.to_lowercase(),
+        name=list_name => name,
+    } -> String;
+
+    sequence_step: message=quoted " " "+" delays=delay+ -> (String, u64) { (message, delays.into_iter().sum()) }
+
+    sequence_command = match {
+        "create" " " name=sequence_name ":" " "? message=quoted " " time=times rest=(comma sequence_step)* => SequenceCommand::Create { name, first: (message, time), rest },
+        "done" " " name=sequence_name => SequenceCommand::Done(name),
+        "cancel" " " name=sequence_name => SequenceCommand::Cancel(name),
+    } -> SequenceCommand;
+
+    voice_join: user=user " " "joins" " " "voice" -> Trigger {
+        if user == 0 {
+            __ctx.replace_err(ParseTimeError::InvalidUser);
+            return Err(ParseTimeError::InvalidUser);
+        }
+        Trigger::VoiceJoin(UserId::new(user))
+    }
+
+    keyword: channel=channel " " "mentions" " " keyword=quoted -> Trigger {
+        if channel == 0 {
+            __ctx.replace_err(ParseTimeError::InvalidChannel);
+            return Err(ParseTimeError::InvalidChannel);
+        }
+        Trigger::Keyword { channel: ChannelId::new(channel), keyword: keyword.to_lowercase() }
+    }
+
+    link_guild = match {
+        "@me" => None,
+        id=num => Some(id),
+    } -> Option<u64>;
+
+    message_link: "https://" ("ptb." | "canary.")? "discord.com/channels/" guild=link_guild "/" channel=num "/" message=num -> MessageLink {
+        let Some(link) = MessageLink::new(guild, channel, message) else {
+            __ctx.replace_err(ParseTimeError::InvalidLink);
+            return Err(ParseTimeError::InvalidLink);
+        };
+        link
+    }
+
+    trigger = match {
+        trigger=voice_join => trigger,
+        trigger=keyword => trigger,
+    } -> Trigger;
+
+    weather = match {
+        ("raining" | "rainy" | "rain") => Weather::Rain,
+        ("snowing" | "snowy" | "snow") => Weather::Snow,
+        ("stormy" | "storm" | "thunder") => Weather::Storm,
+        ("clear" | "sunny") => Weather::Clear,
+    } -> Weather;
+
+    clock: modifier=time_of_day -> Time {
+        let TimeModifier::TimeOfDay { hour, minute } = modifier else {
+            unreachable!("time_of_day returned another modifier")
+        };
+        // Both were range checked by time_of_day
+        Time::new(hour as i8, minute as i8, 0, 0).unwrap()
+    }
+
+    check = match {
+        "between" " " start=clock " " "and" " " end=clock => Check::Window(Window { start, end }),
+        "up" " " url=<[^" ;"]+> => Check::Url(Url(url.to_string())),
+        "online" => Check::Online(Online),
+        weather=weather => Check::Weather(weather),
+    } -> Check;
+
+    condition = match {
+        "if" " " check=check => Guard { check, unless: false },
+        "unless" " " check=check => Guard { check, unless: true },
+    } -> Guard;
+
+    task_provider = match {
+        "todoist" => ProviderKind::Todoist,
+        "notion" => ProviderKind::Notion,
+    } -> ProviderKind;
+
+    holiday_mode = match {
+        "roll" => HolidayMode::Roll,
+        "on" => HolidayMode::Skip,
+        "off" => HolidayMode::Send,
+    } -> HolidayMode;
+
+    toggle = match {
+        "on" => true,
+        "off" => false,
+    } -> bool;
+
+    calendar_date: year=num '-' month=num '-' day=num -> Date {
+        Date::new(year as i16, month as i8, day as i8)?
+    }
+
+    holiday_command = match {
+        "add" " " date=calendar_date => HolidayCommand::Add(date),
+        "remove" " " date=calendar_date => HolidayCommand::Remove(date),
+        "off" => HolidayCommand::Clear,
+        country=<('a'-'z' | 'A'-'Z')+> => HolidayCommand::SetCountry(country.to_string()),
+    } -> HolidayCommand;
+
+    modifier_kind = match {
+        ("delay" | "delays") => ModifierKind::Delay,
+        ("weekday" | "weekdays") => ModifierKind::Weekday,
+        "time" => ModifierKind::Time,
+        "date" => ModifierKind::Date,
+        ("months" | "month") => ModifierKind::Months,
+        ("years" | "year") => ModifierKind::Years,
+        "week" => ModifierKind::Week,
+        "quarter" => ModifierKind::Quarter,
+        "sun" => ModifierKind::Sun,
+    } -> ModifierKind;
+
+    interval_edit = match {
+        "+" modifiers=time_modifier$" "+ => IntervalEdit::Add(modifiers),
+        "remove" " " kind=modifier_kind => IntervalEdit::Remove(kind),
+    } -> IntervalEdit;
+
+    announcement_command = match {
+        "add" " " channel=channel " " time=times semicolon interval=time_modifier$" "+ semicolon message=<.+> => AnnouncementCommand::Add(channel, time, interval, message.to_string()),
+        "list" => AnnouncementCommand::List,
+        "cancel" " " id=num => AnnouncementCommand::Cancel(id),
+    } -> AnnouncementCommand;
+
+    server_command = match {
+        ("tz" | "timezone") " " timezone=<.+> => ServerCommand::SetTimezone(timezone.to_string()),
+        ("tf" | "timeformat") " " time_format=time_format => ServerCommand::SetTimeFormat(time_format),
+        "listen" " " channel=channel " " enabled=toggle => ServerCommand::Listen(channel, enabled),
+    } -> ServerCommand;
+
+    pub command = match {
+        ("remindme" | "reminder" | "r") " " "list:" name=list_name " " time=times semicolon message=<.+> => Command::ScheduleShared(name, time, message.to_string()),
+        ("remindme" | "reminder" | "r") " " "after" " " "#"? id=num " " offset=delay+ semicolon message=<.+> => Command::ScheduleAfter(id, offset.into_iter().sum(), message.to_string()),
+        ("remindme" | "reminder" | "r") " " "when" " " trigger=trigger semicolon message=<.+> => Command::ScheduleTrigger(trigger, message.to_string()),
+        ("remindme" | "reminder" | "r") " " message=quoted " "+ ("in" " ")? time=times condition=(" " condition)? => Command::ScheduleReminder(time, condition, message, None),
+        ("remindme" | "reminder" | "r") " " time=times condition=(" " condition)? " "+ message=quoted => Command::ScheduleReminder(time, condition, message, None),
+        ("remindme" | "reminder" | "r") " " time=times condition=(" " condition)? semicolon message=<.+> => Command::ScheduleReminder(time, condition, message.to_string(), None),
+        "say" " " channel=channel " " time=times condition=(" " condition)? semicolon message=<.+> => Command::ScheduleReminder(time, condition, message.to_string(), Some(channel)),
+        "holidays" command=(" " holiday_command)? => Command::Holidays(command.unwrap_or(HolidayCommand::Show)),
+        ("help" | "h") topic=(" " <.+>)? => Command::Help(topic.map(str::to_string)),
+        ("setinterval" | "si") " " id=num " " edit=interval_edit => Command::EditInterval(id, edit),
+        ("setinterval" | "si") " " id=num " " ("rrule" | "RRULE") (" " | ":") rule=<.+> => Command::SetRRuleInterval(id, rule.to_string()),
+        ("setinterval" | "si") " " id=num " " "cron" " " expression=<.+> => Command::SetCronInterval(id, expression.to_string()),
         ("setinterval" | "si") " " id=num " " modifiers=time_modifier$" "+ => Command::SetInterval(id, modifiers),
         ("clearinterval" | "ci") " " id=num => Command::ClearInterval(id),
         ("cancelreminder" | "cr") " " id=num => Command::CancelReminder(id),
-        ("reminders" | "rs") => Command::ListReminders,
+        ("skipholidays" | "sh") " " id=num " " mode=holiday_mode => Command::SkipHolidays(id, mode),
+        "skip" " " id=num => Command::SkipOccurrence(id),
+        "note" " " id=num " "+ note=<.+> => Command::AddNote(id, note.to_string()),
+        "jitter" " " id=num " " jitter=jitter => Command::SetJitter(id, jitter),
+        "expire" " " id=num " " ("default" | "reset") => Command::SetExpiry(id, None),
+        "expire" " " id=num " " after=expire_after => Command::SetExpiry(id, Some(after)),
+        "expire" " " after=expire_after => Command::SetDefaultExpiry((after > 0).then_some(after)),
+        "nextup" " " enabled=toggle => Command::SetNextUp(enabled),
+        "review" " " enabled=toggle => Command::SetReview(enabled),
+        "done" " " "#"? id=num => Command::Done(id),
+        "habit" " " id=num " " enabled=toggle => Command::SetHabit(id, enabled),
+        "emphasis" " " id=num " " emphasis=emphasis => Command::SetEmphasis(id, emphasis),
+        "callout" " " id=num " " "off" => Command::SetCallout(id, None),
+        "callout" " " id=num " " role=role => Command::SetCallout(id, Some(role)),
+        "import" " " "csv" => Command::ImportCsv,
+        "export" " " "csv" => Command::ExportCsv,
+        "simulate" " " id=num " " count=num => Command::Simulate(id, count),
+        "public" " " id=num " " public=toggle => Command::SetPublic(id, public),
+        "follow" " " organizer=user " " id=num => Command::Follow(organizer, id),
+        "unfollow" " " organizer=user " " id=num => Command::Unfollow(organizer, id),
+        "transfer" " " id=num " " recipient=user => Command::Transfer(id, recipient),
+        "list" command=(" " shared_list_command)? => Command::SharedList(command.unwrap_or(SharedListCommand::Mine)),
+        ("sequence" | "seq") command=(" " sequence_command)? => Command::Sequence(command.unwrap_or(SequenceCommand::Mine)),
+        "history" => Command::History,
+        "audit" id=(" " num)? => Command::Audit(id),
+        ("reminders" | "rs") options=(" " list_option)* => Command::ListReminders(options.into_iter().collect()),
         ("tz" | "timezone") " " timezone=<.+> => Command::SetTimezone(timezone.to_string()),
-        ("tf" | "timeformat") " " time_format=time_format => Command::SetTimeFormat(time_format)
+        ("tf" | "timeformat") " " time_format=time_format => Command::SetTimeFormat(time_format),
+        "weekdays" " " mode=weekday_mode => Command::SetWeekdayMode(mode),
+        "liststyle" " " style=list_style => Command::SetListStyle(style),
+        "fiscal" " " month=num => Command::SetFiscalYearStart(month),
+        "location" " " "clear" => Command::ClearLocation,
+        "delivery" " " "online" max_hold=(" " delay+)? => Command::SetDelivery(Some(max_hold.map_or(DEFAULT_MAX_HOLD, |delays| delays.into_iter().sum()))),
+        "delivery" " " ("now" | "immediate") => Command::SetDelivery(None),
+        "offset" " " "set" " " name=offset_name " " delays=delay+ => Command::SetOffset(name, delays.into_iter().sum()),
+        "offset" " " ("remove" | "delete") " " name=offset_name => Command::RemoveOffset(name),
+        ("offsets" | "offset") => Command::ListOffsets,
+        "location" " " latitude=coordinate (comma | " "+) longitude=coordinate => Command::SetLocation(latitude, longitude),
+        "telegram" " " "link" => Command::Telegram(TelegramCommand::Link),
+        "telegram" " " "unlink" => Command::Telegram(TelegramCommand::Unlink),
+        ("cal" | "calendar") " " "link" => Command::Calendar(CalendarCommand::Link),
+        ("cal" | "calendar") " " "unlink" => Command::Calendar(CalendarCommand::Unlink),
+        ("cal" | "calendar") " " "code" " " code=<.+> => Command::Calendar(CalendarCommand::Code(code.to_string())),
+        ("cal" | "calendar") " " id=num " " enabled=toggle => Command::Calendar(CalendarCommand::Flag(id, enabled)),
+        "bookmarks" " " ("remove" | "delete") " " id=num => Command::Bookmark(BookmarkCommand::Remove(id)),
+        "bookmarks" " " "remind" " " id=num " " time=times => Command::Bookmark(BookmarkCommand::Remind(id, time)),
+        "bookmarks" => Command::Bookmark(BookmarkCommand::List),
+        "bookmark" " " link=message_link note=(" "+ <.+>)? => Command::Bookmark(BookmarkCommand::Add(Some(link), note.map(str::to_string))),
+        "bookmark" note=(" "+ <.+>)? => Command::Bookmark(BookmarkCommand::Add(None, note.map(str::to_string))),
+        "triggers" " " "cancel" " " id=num => Command::Trigger(TriggerCommand::Cancel(id)),
+        "triggers" => Command::Trigger(TriggerCommand::List),
+        "watch" " " url=<[^" "]+> filter=(" "+ <.+>)? => Command::Feed(FeedCommand::Watch(url.to_string(), filter.map(str::to_string))),
+        ("watches" | "watch") => Command::Feed(FeedCommand::List),
+        "unwatch" " " id=num => Command::Feed(FeedCommand::Unwatch(id)),
+        "tasks" " " provider=task_provider " " ("remove" | "unlink") => Command::Tasks(TasksCommand::Unlink(provider)),
+        "tasks" " " "todoist" " " token=<[^" "]+> => Command::Tasks(TasksCommand::Link {
+            provider: ProviderKind::Todoist,
+            token: token.to_string(),
+            database: None,
+        }),
+        "tasks" " " "notion" " " token=<[^" "]+> " "+ database=<[^" "]+> => Command::Tasks(TasksCommand::Link {
+            provider: ProviderKind::Notion,
+            token: token.to_string(),
+            database: Some(database.to_string()),
+        }),
+        "tasks" => Command::Tasks(TasksCommand::Show),
+        "push" " " id=num " " provider=task_provider => Command::Tasks(TasksCommand::Push(id, provider)),
+        "matrix" " " "clear" => Command::Matrix(MatrixCommand::Clear),
+        "matrix" " " homeserver=<[^" "]+> " "+ room=<[^" "]+> " "+ token=<[^" "]+> => Command::Matrix(MatrixCommand::Set {
+            homeserver: homeserver.to_string(),
+            room: room.to_string(),
+            token: token.to_string(),
+        }),
+        "matrix" => Command::Matrix(MatrixCommand::Show),
+        "dashboard" => Command::Dashboard,
+        ("prefs" | "preferences") " " "natural" " " natural=toggle => Command::SetNatural(natural),
+        ("prefs" | "preferences") " " "language" " " language=language => Command::SetLanguage(language),
+        ("prefs" | "preferences") " " "next" " " day=period_day => Command::SetPeriodDay(day),
+        ("prefs" | "preferences") " " "horizon" " " "reset" => Command::SetHorizon(None),
+        ("prefs" | "preferences") " " "horizon" " " years=num "y"? => Command::SetHorizon(Some(years)),
+        ("prefs" | "preferences") " " "template" " " ("clear" | "reset") => Command::SetTemplate(None),
+        ("prefs" | "preferences") " " "template" " " template=quoted => Command::SetTemplate(Some(template)),
+        ("prefs" | "preferences") => Command::ShowPreferences,
+        ("about" | "version") => Command::About,
+        "ping" => Command::Ping,
+        "stats" => Command::Stats,
+        ("server" | "sv") " " command=server_command => Command::Server(command),
+        ("announce" | "an") command=(" " announcement_command)? => Command::Announce(command.unwrap_or(AnnouncementCommand::List)),
+        "admin" " " "tasks" => Command::Admin(AdminCommand::Tasks),
+        "admin" " " "stats" => Command::Admin(AdminCommand::Stats),
+        "admin" " " "reload" => Command::Admin(AdminCommand::Reload),
+        "admin" => Command::Admin(AdminCommand::Report),
     } -> Command;
 
-    pub command = "$" match_commands -> Command;
+    position: "" -> usize { __ctx.cursor() }
+
+    spanned_modifier: start=position modifier=modifier end=position -> (Range<usize>, Modifier) {
+        (start..end, modifier)
+    }
 
-    pub time: modifiers=modifier$" "+ -> Vec<Zoned> {
+    // The modifiers of a time with where each one is in the input, along with the times they
+    // resolve to
+    pub time: spanned=spanned_modifier$" "+ -> (Vec<(Range<usize>, Modifier)>, Vec<Zoned>) {
+        let modifiers = spanned.iter().map(|(_, modifier)| modifier.clone()).collect();
         let modifier_permutations = Modifier::into_time_modifiers(modifiers);
-        let date = Zoned::now().with_time_zone(__ctx.data().clone());
+        let context = __ctx.data();
+        let now = Zoned::now().with_time_zone(context.time_zone.clone());
 
         let mut dates = vec![];
         for permutation in modifier_permutations {
-            let mut date = date.clone();
-            for modifier in permutation {
-                date = modifier.modify(date).unwrap();
+            let permutation = subtractions_last(permutation);
+            let date = resolve(now.clone(), &now, &permutation, context.weekday_mode)?;
+            if let Some(conflict) = conflict(&permutation, &date) {
+                // Reported at the end of the time so it outranks errors from other alternatives
+                __ctx.replace_err(ParseTimeError::Conflict(conflict.clone()));
+                return Err(ParseTimeError::Conflict(conflict));
             }
             dates.push(date);
         }
         dates.sort();
-        dates
+        (spanned, dates)
     }
 
+    times: time=time -> Vec<Zoned> { time.1 }
+
 }
@@ -0,0 +1,91 @@
+//! Marking reminders done, from the button on a delivery or with `$done`. Repeating reminders
+//! keep a streak of occurrences done in a row, broken when one fires before the last was done.
+
+use jiff::{Timestamp, Zoned};
+use serenity::all::UserId;
+
+use crate::{
+    audit::{self, Action},
+    delivery,
+    stats::{self, STATS},
+    Reminder, REMINDERS,
+};
+
+/// Opens the new occurrence of a repeating reminder once it's delivered, ending the streak if the
+/// one before was never marked done.
+pub async fn delivered(user: UserId, reminder: &Reminder) {
+    if reminder.interval.is_none() {
+        return;
+    }
+    stats::record(user, |stats| {
+        let streak = stats.streaks.entry(reminder.origin()).or_default();
+        if streak.open {
+            streak.current = 0;
+            streak.misses += 1;
+        }
+        streak.open = true;
+        streak.opened = Some(Timestamp::now());
+        streak.nudged = false;
+    })
+    .await;
+}
+
+/// Records a reminder as done. Repeating ones can only be done once per delivered occurrence, so
+/// the streak can't be run up by marking the same one again.
+pub async fn complete(
+    user: UserId,
+    key: u64,
+    message: &str,
+    recurring: bool,
+) -> Result<String, &'static str> {
+    let mut streak = None;
+    {
+        let mut all = STATS.write().await;
+        let stats = all.entry(user).or_default();
+        if recurring {
+            let entry = stats.streaks.entry(key).or_default();
+            if !entry.open {
+                return Err("This reminder is already marked done until it's sent again");
+            }
+            entry.open = false;
+            entry.current += 1;
+            entry.best = entry.best.max(entry.current);
+            streak = Some(entry.current);
+        }
+        stats.completed += 1;
+    }
+    audit::record_key(user, key, message, Action::Completed, "done").await;
+    delivery::completed(user, message).await;
+    Ok(match streak {
+        Some(streak) if streak > 1 => {
+            format!("Marked '{message}' done, that's {streak} in a row")
+        }
+        _ => format!("Marked '{message}' done"),
+    })
+}
+
+/// Marks a delivered reminder done from its button. One-off reminders are gone from the list
+/// once they fire, so they're found through the audit log instead.
+pub async fn from_button(user: UserId, key: u64) -> Result<String, &'static str> {
+    let recurring = REMINDERS
+        .lock()
+        .await
+        .get(&user)
+        .is_some_and(|list| list.iter().any(|r| r.key == key && r.interval.is_some()));
+    let entry = audit::entries(user, Some(key), 1)
+        .await
+        .pop()
+        .ok_or("This reminder is no longer available")?;
+    if !recurring && entry.action == Action::Completed {
+        return Err("This reminder is already marked done");
+    }
+    complete(user, key, &entry.message, recurring).await
+}
+
+/// Whether the reminder with `key` was marked done at or after `since`.
+pub async fn done_since(user: UserId, key: u64, since: &Zoned) -> bool {
+    audit::entries(user, Some(key), 10)
+        .await
+        .iter()
+        .any(|entry| entry.action == Action::Completed && entry.time >= *since)
+}
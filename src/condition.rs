@@ -0,0 +1,244 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, OnceLock},
+};
+
+use jiff::{civil::Time, tz::TimeZone, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{Cache, UserId};
+
+use crate::{presence, solar::Location, web::CLIENT};
+
+const WEATHER_API: &str = "https://api.open-meteo.com/v1/forecast";
+
+/// Set at startup, presences are only in it when `features.presence` is on.
+pub static CACHE: OnceLock<Arc<Cache>> = OnceLock::new();
+
+/// What a condition is checked against.
+pub struct Context {
+    pub user: UserId,
+    pub location: Option<Location>,
+    pub time_zone: TimeZone,
+}
+
+/// Something checked when a reminder fires, deciding whether it's sent.
+pub trait Condition {
+    async fn met(&self, context: &Context) -> Result<bool, String>;
+
+    /// A note for the delivered reminder on what was found, like "it is raining".
+    fn describe(&self, met: bool) -> String;
+}
+
+/// Weather a reminder can wait for.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    Rain,
+    Snow,
+    Storm,
+    Clear,
+}
+
+impl Weather {
+    /// Whether a WMO weather code, as reported by the weather API, is this weather.
+    fn matches(self, code: u64) -> bool {
+        match self {
+            Weather::Rain => matches!(code, 51..=67 | 80..=82),
+            Weather::Snow => matches!(code, 71..=77 | 85 | 86),
+            Weather::Storm => matches!(code, 95..=99),
+            Weather::Clear => matches!(code, 0 | 1),
+        }
+    }
+}
+
+impl Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Weather::Rain => "rain",
+            Weather::Snow => "snow",
+            Weather::Storm => "storm",
+            Weather::Clear => "clear",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Deserialize)]
+struct Forecast {
+    current: Current,
+}
+
+#[derive(Deserialize)]
+struct Current {
+    weather_code: u64,
+}
+
+impl Condition for Weather {
+    async fn met(&self, context: &Context) -> Result<bool, String> {
+        let location = context
+            .location
+            .ok_or("set your location with `$location` to check the weather")?;
+        let forecast: Forecast = CLIENT
+            .get(WEATHER_API)
+            .query(&[
+                ("latitude", location.latitude.to_string()),
+                ("longitude", location.longitude.to_string()),
+                ("current", "weather_code".to_string()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.without_url().to_string())?
+            .json()
+            .await
+            .map_err(|err| err.without_url().to_string())?;
+        Ok(self.matches(forecast.current.weather_code))
+    }
+
+    fn describe(&self, met: bool) -> String {
+        let weather = match self {
+            Weather::Rain => "raining",
+            Weather::Snow => "snowing",
+            Weather::Storm => "stormy",
+            Weather::Clear => "clear",
+        };
+        let not = if met { "" } else { "n't" };
+        format!("it is{not} {weather}")
+    }
+}
+
+/// A time of day range in the owner's timezone, which may wrap past midnight.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Window {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl Condition for Window {
+    async fn met(&self, context: &Context) -> Result<bool, String> {
+        let now = Zoned::now()
+            .with_time_zone(context.time_zone.clone())
+            .time();
+        Ok(if self.start <= self.end {
+            self.start <= now && now < self.end
+        } else {
+            now >= self.start || now < self.end
+        })
+    }
+
+    fn describe(&self, met: bool) -> String {
+        let not = if met { "" } else { "n't" };
+        format!("it is{not} {self}")
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "between {} and {}",
+            self.start.strftime("%-H:%M"),
+            self.end.strftime("%-H:%M")
+        )
+    }
+}
+
+/// A page that's up when it answers with a success status.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Url(pub String);
+
+impl Condition for Url {
+    async fn met(&self, _: &Context) -> Result<bool, String> {
+        // A page that can't be reached at all counts as down, not as a failed check
+        Ok(CLIENT
+            .get(&self.0)
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success()))
+    }
+
+    fn describe(&self, met: bool) -> String {
+        let state = if met { "up" } else { "down" };
+        format!("<{}> is {state}", self.0)
+    }
+}
+
+/// Whether the owner shows as online, idle or busy in a server the bot shares with them.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Online;
+
+impl Condition for Online {
+    async fn met(&self, context: &Context) -> Result<bool, String> {
+        presence::online(context.user).ok_or_else(|| "presences aren't available yet".into())
+    }
+
+    fn describe(&self, met: bool) -> String {
+        let state = if met { "online" } else { "offline" };
+        format!("you're {state}")
+    }
+}
+
+/// Every kind of condition a reminder can have.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Check {
+    Weather(Weather),
+    Window(Window),
+    Url(Url),
+    Online(Online),
+}
+
+impl Condition for Check {
+    async fn met(&self, context: &Context) -> Result<bool, String> {
+        match self {
+            Check::Weather(weather) => weather.met(context).await,
+            Check::Window(window) => window.met(context).await,
+            Check::Url(url) => url.met(context).await,
+            Check::Online(online) => online.met(context).await,
+        }
+    }
+
+    fn describe(&self, met: bool) -> String {
+        match self {
+            Check::Weather(weather) => weather.describe(met),
+            Check::Window(window) => window.describe(met),
+            Check::Url(url) => url.describe(met),
+            Check::Online(online) => online.describe(met),
+        }
+    }
+}
+
+/// A reminder's condition, `unless` sends it only when the check isn't met.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Guard {
+    pub check: Check,
+    pub unless: bool,
+}
+
+impl Display for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let word = if self.unless { "unless" } else { "if" };
+        match &self.check {
+            Check::Weather(weather) => write!(f, "{word} {weather}"),
+            Check::Window(window) => write!(f, "{word} {window}"),
+            Check::Url(url) => write!(f, "{word} up <{}>", url.0),
+            Check::Online(_) => write!(f, "{word} online"),
+        }
+    }
+}
+
+/// What to do with a reminder whose condition was checked.
+pub enum Verdict {
+    /// Send it, with a note on what was found
+    Deliver(String),
+    Skip,
+}
+
+/// Reminders whose condition can't be checked are sent anyway, with a note saying so, since
+/// missing one is worse than getting it needlessly.
+pub async fn evaluate(guard: &Guard, context: &Context) -> Verdict {
+    match guard.check.met(context).await {
+        Ok(met) if met == guard.unless => Verdict::Skip,
+        Ok(met) => Verdict::Deliver(format!("({})", guard.check.describe(met))),
+        Err(err) => Verdict::Deliver(format!("(couldn't check the condition, {err})")),
+    }
+}
@@ -0,0 +1,268 @@
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, GatewayIntents, UserId};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use crate::delivery::MAX_JITTER_SECS;
+
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Couldn't read {CONFIG_FILE}: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("Couldn't parse {CONFIG_FILE}: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Invalid config: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Features {
+    pub guild_mode: bool,
+    /// Tracks presences for `if online`, needs the privileged presence intent
+    pub presence: bool,
+    /// Serves the dashboard, which also needs `DASHBOARD_ADDR`, `DASHBOARD_URL` and a Discord app
+    pub http_api: bool,
+    /// The failed delivery digest in front of replies and `$review`'s weekly DM
+    pub digests: bool,
+    /// Reacting to a message with ⏰ schedules a reminder about it
+    pub reactions: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            guild_mode: false,
+            presence: false,
+            http_api: false,
+            // Digests don't need any setup, so they're on unless turned off
+            digests: true,
+            reactions: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+    pub cooldown_secs: u64,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            burst: 5,
+            per_minute: 20,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+/// Days old records are kept before maintenance removes them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Retention {
+    pub history_days: u32,
+    pub audit_days: u32,
+    /// Checklists and streaks left behind by reminders that fired for the last time
+    pub fired_days: u32,
+    /// Records quarantined in `broken.json` while loading
+    pub quarantine_days: u32,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            history_days: 90,
+            audit_days: 365,
+            fired_days: 30,
+            quarantine_days: 90,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub prefix: String,
+    pub features: Features,
+    pub admins: Vec<UserId>,
+    pub rate_limit: RateLimit,
+    /// Where crash reports go, admins are DMed instead when unset.
+    pub alert_channel: Option<ChannelId>,
+    pub retention: Retention,
+    /// Where `--dry-run` posts the messages it would have sent, they're only logged when unset.
+    pub dry_run_channel: Option<ChannelId>,
+    /// Seconds to spread a reminder's copies over when it goes to many people, for reminders
+    /// without their own `$jitter`. 0 sends them all at once.
+    pub delivery_jitter_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            prefix: "$".into(),
+            features: Features::default(),
+            admins: vec![],
+            rate_limit: RateLimit::default(),
+            alert_channel: None,
+            retention: Retention::default(),
+            dry_run_channel: None,
+            delivery_jitter_secs: 0,
+        }
+    }
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.prefix.is_empty() || self.prefix.contains(char::is_whitespace) {
+            return Err(ConfigError::Invalid(
+                "prefix must be non-empty and have no spaces".into(),
+            ));
+        }
+        if self.rate_limit.burst == 0 || self.rate_limit.per_minute == 0 {
+            return Err(ConfigError::Invalid(
+                "rate_limit burst and per_minute must be above 0".into(),
+            ));
+        }
+        let retention = &self.retention;
+        if [
+            retention.history_days,
+            retention.audit_days,
+            retention.fired_days,
+            retention.quarantine_days,
+        ]
+        .contains(&0)
+        {
+            return Err(ConfigError::Invalid(
+                "retention days must be above 0".into(),
+            ));
+        }
+        if self.delivery_jitter_secs > MAX_JITTER_SECS {
+            return Err(ConfigError::Invalid(format!(
+                "delivery_jitter_secs must be at most {MAX_JITTER_SECS}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Describes what differs from `old`, in the order the fields are declared.
+    fn changes(&self, old: &Config) -> Vec<String> {
+        let mut changes = vec![];
+        if self.prefix != old.prefix {
+            changes.push(format!("prefix is now `{}`", self.prefix));
+        }
+        if self.features.guild_mode != old.features.guild_mode {
+            changes.push(format!(
+                "guild_mode is now {}, this takes effect after a restart",
+                self.features.guild_mode
+            ));
+        }
+        if self.features.presence != old.features.presence {
+            changes.push(format!(
+                "presence is now {}, this takes effect after a restart",
+                self.features.presence
+            ));
+        }
+        if self.features.http_api != old.features.http_api {
+            changes.push(format!(
+                "http_api is now {}, this takes effect after a restart",
+                self.features.http_api
+            ));
+        }
+        if self.features.digests != old.features.digests {
+            changes.push(format!("digests is now {}", self.features.digests));
+        }
+        if self.features.reactions != old.features.reactions {
+            changes.push(format!(
+                "reactions is now {}, this takes effect after a restart",
+                self.features.reactions
+            ));
+        }
+        if self.admins != old.admins {
+            changes.push(format!("{} admins are now configured", self.admins.len()));
+        }
+        if self.rate_limit != old.rate_limit {
+            let limit = &self.rate_limit;
+            changes.push(format!(
+                "rate limit is now {} burst, {}/minute, {}s cooldown",
+                limit.burst, limit.per_minute, limit.cooldown_secs
+            ));
+        }
+        if self.alert_channel != old.alert_channel {
+            changes.push("alert channel changed".into());
+        }
+        if self.retention != old.retention {
+            let retention = &self.retention;
+            changes.push(format!(
+                "history kept {} days, audit kept {} days, fired reminders' data kept {} days, quarantined records kept {} days",
+                retention.history_days,
+                retention.audit_days,
+                retention.fired_days,
+                retention.quarantine_days
+            ));
+        }
+        if self.dry_run_channel != old.dry_run_channel {
+            changes.push("dry run channel changed".into());
+        }
+        if self.delivery_jitter_secs != old.delivery_jitter_secs {
+            changes.push(format!(
+                "deliveries to many people are now spread over {}s",
+                self.delivery_jitter_secs
+            ));
+        }
+        changes
+    }
+
+    pub fn intents(&self) -> GatewayIntents {
+        let mut intents = GatewayIntents::DIRECT_MESSAGES;
+        if self.features.guild_mode {
+            intents |= GatewayIntents::GUILD_MESSAGES
+                | GatewayIntents::MESSAGE_CONTENT
+                | GatewayIntents::GUILD_VOICE_STATES;
+        }
+        if self.features.presence {
+            intents |= GatewayIntents::GUILDS | GatewayIntents::GUILD_PRESENCES;
+        }
+        if self.features.reactions {
+            intents |= GatewayIntents::DIRECT_MESSAGE_REACTIONS;
+            if self.features.guild_mode {
+                intents |= GatewayIntents::GUILD_MESSAGE_REACTIONS;
+            }
+        }
+        intents
+    }
+}
+
+static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(Default::default);
+
+pub async fn config() -> Config {
+    CONFIG.read().await.clone()
+}
+
+pub async fn load_config() {
+    let Ok(config_json) = tokio::fs::read_to_string(CONFIG_FILE).await else {
+        return;
+    };
+    let config: Config = serde_json::from_str(&config_json).expect("Invalid config file");
+    config.validate().expect("Invalid config file");
+    *CONFIG.write().await = config;
+}
+
+/// Rereads the config file, keeping the current config if the new one doesn't parse or validate.
+/// Returns what changed.
+pub async fn reload() -> Result<Vec<String>, ConfigError> {
+    let config_json = tokio::fs::read_to_string(CONFIG_FILE).await?;
+    let config: Config = serde_json::from_str(&config_json)?;
+    config.validate()?;
+    let mut current = CONFIG.write().await;
+    let changes = config.changes(&current);
+    *current = config;
+    Ok(changes)
+}
@@ -0,0 +1,77 @@
+//! Structure read out of a reminder's message: `🦷 Dentist | bring insurance card` has an emoji,
+//! a title and a body. Messages stay plain strings so everything else can keep treating them as
+//! text.
+
+/// Discord's limit on embed titles, longer ones are left in the body.
+const MAX_TITLE_LENGTH: usize = 256;
+
+pub struct Content<'a> {
+    pub emoji: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub body: &'a str,
+}
+
+impl Content<'_> {
+    /// The title if there is one, otherwise the body.
+    pub fn summary(&self) -> &str {
+        self.title.unwrap_or(self.body)
+    }
+
+    /// The message without its emoji, `Title: body` if it has a title.
+    pub fn text(&self) -> String {
+        match self.title {
+            Some(title) => format!("{title}: {}", self.body),
+            None => self.body.to_string(),
+        }
+    }
+}
+
+fn is_emoji_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF
+    )
+}
+
+/// Variation selectors, zero width joiners and skin tones that continue an emoji.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E | 0xFE0F | 0x200D | 0x20E3 | 0xE0020..=0xE007F
+    )
+}
+
+/// Whether `word` is a single emoji, either unicode or a custom `<:name:id>` one.
+fn is_emoji(word: &str) -> bool {
+    if let Some(inner) = word
+        .strip_prefix("<:")
+        .or_else(|| word.strip_prefix("<a:"))
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        return inner
+            .split_once(':')
+            .is_some_and(|(name, id)| !name.is_empty() && id.parse::<u64>().is_ok());
+    }
+    let mut chars = word.chars();
+    chars.next().is_some_and(is_emoji_char)
+        && chars.all(|c| is_emoji_char(c) || is_emoji_modifier(c))
+}
+
+pub fn parse(message: &str) -> Content<'_> {
+    let (emoji, rest) = match message.split_once(' ') {
+        Some((first, rest)) if is_emoji(first) && !rest.trim().is_empty() => {
+            (Some(first), rest.trim_start())
+        }
+        _ => (None, message),
+    };
+    let (title, body) = match rest.split_once('|') {
+        Some((title, body))
+            if !title.trim().is_empty()
+                && !body.trim().is_empty()
+                && title.trim().chars().count() <= MAX_TITLE_LENGTH =>
+        {
+            (Some(title.trim()), body.trim())
+        }
+        _ => (None, rest),
+    };
+    Content { emoji, title, body }
+}
@@ -0,0 +1,103 @@
+use std::{
+    future::Future,
+    panic::{AssertUnwindSafe, PanicHookInfo},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serenity::all::{CreateMessage, Http};
+
+use crate::{
+    config::config,
+    delivery::{self, Recipient},
+    flush, log_error, save,
+};
+
+/// How long the panic hook waits on state that the panicking code may still hold locked.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set while a crash report is being handled, so a panic while reporting one, or a burst of them,
+/// doesn't set off another.
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// Flushes state and alerts the operator whenever anything panics, on top of the default hook.
+pub fn install(http: Arc<Http>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if REPORTING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let report = report(info);
+        let http = http.clone();
+        // The hook may run on a runtime worker, so the async work gets its own thread and runtime.
+        // It isn't waited for, the panicking thread goes on unwinding straight away
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build();
+            if let Ok(runtime) = runtime {
+                let reported = AssertUnwindSafe(|| {
+                    runtime.block_on(async {
+                        let content = format!("**reedbot crash report**\n```\n{report}\n```");
+                        alert(&http, &content).await;
+                        if tokio::time::timeout(FLUSH_TIMEOUT, flush()).await.is_err() {
+                            eprintln!("Timed out saving state after a panic");
+                        }
+                    })
+                });
+                let _ = std::panic::catch_unwind(reported);
+            }
+            REPORTING.store(false, Ordering::SeqCst);
+        });
+    }));
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let payload = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    let location = info
+        .location()
+        .map(|location| format!(" at {}:{}", location.file(), location.line()))
+        .unwrap_or_default();
+    format!("Panicked{location}: {payload}")
+}
+
+/// Posts to the alert channel, or DMs the admins when there isn't one.
+pub async fn alert(http: &Http, content: &str) {
+    let config = config().await;
+    match config.alert_channel {
+        Some(channel) => {
+            let message = CreateMessage::new().content(content);
+            log_error(delivery::send(http, Recipient::Channel(channel), message).await);
+        }
+        None => {
+            for admin in config.admins {
+                let message = CreateMessage::new().content(content);
+                log_error(delivery::send(http, Recipient::User(admin), message).await);
+            }
+        }
+    }
+}
+
+/// Runs a future as its own task so a panic in it is contained, saving whatever state survived.
+pub async fn catch<T: Send + 'static>(
+    name: &str,
+    future: impl Future<Output = T> + Send + 'static,
+) -> Option<T> {
+    match tokio::spawn(future).await {
+        Ok(value) => Some(value),
+        Err(err) => {
+            eprintln!("{name} failed: {err}");
+            save();
+            None
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use jiff::{civil::Date, Zoned};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CronError {
+    #[error("A cron expression needs 5 fields (minute hour day month weekday), got {0}")]
+    FieldCount(usize),
+    #[error("Invalid {0} field in cron expression: {1}")]
+    InvalidField(&'static str, String),
+    #[error("Cron expression {0} never matches a real date")]
+    NeverMatches(String),
+}
+
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+}
+
+const FIELDS: [Field; 5] = [
+    Field {
+        name: "minute",
+        min: 0,
+        max: 59,
+        names: &[],
+    },
+    Field {
+        name: "hour",
+        min: 0,
+        max: 23,
+        names: &[],
+    },
+    Field {
+        name: "day of month",
+        min: 1,
+        max: 31,
+        names: &[],
+    },
+    Field {
+        name: "month",
+        min: 1,
+        max: 12,
+        names: &[
+            "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+        ],
+    },
+    // 7 is accepted as an alias for Sunday
+    Field {
+        name: "weekday",
+        min: 0,
+        max: 7,
+        names: &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+    },
+];
+
+impl Field {
+    fn value(&self, input: &str) -> Option<u32> {
+        let upper = input.to_ascii_uppercase();
+        if let Some(index) = self.names.iter().position(|name| *name == upper) {
+            return Some(index as u32 + self.min);
+        }
+        input
+            .parse()
+            .ok()
+            .filter(|value| (self.min..=self.max).contains(value))
+    }
+
+    /// Parses a comma separated list of `*`, `a`, `a-b` and `/step` items into a bitmask.
+    fn parse(&self, input: &str) -> Option<u64> {
+        let mut mask = 0;
+        for item in input.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, step.parse().ok().filter(|step| *step > 0)?),
+                None => (item, 1),
+            };
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (self.min, self.max),
+                Some((start, end)) => (self.value(start)?, self.value(end)?),
+                // `a/n` means every n starting at a
+                None if item.contains('/') => (self.value(range)?, self.max),
+                None => {
+                    let value = self.value(range)?;
+                    (value, value)
+                }
+            };
+            if start > end {
+                return None;
+            }
+            for value in (start..=end).step_by(step) {
+                mask |= 1 << value;
+            }
+        }
+        Some(mask)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn days_in_month(month: u32) -> u32 {
+    match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<CronSchedule, CronError> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        if fields.len() != FIELDS.len() {
+            return Err(CronError::FieldCount(fields.len()));
+        }
+        let mut masks = [0; 5];
+        for ((mask, field), input) in masks.iter_mut().zip(&FIELDS).zip(&fields) {
+            *mask = field
+                .parse(input)
+                .ok_or_else(|| CronError::InvalidField(field.name, input.to_string()))?;
+        }
+        let [minutes, hours, days, months, mut weekdays] = masks;
+        if weekdays & 1 << 7 != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        let schedule = CronSchedule {
+            expression: fields.join(" "),
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            days_restricted: fields[2] != "*",
+            weekdays_restricted: fields[4] != "*",
+        };
+        let possible = (1..=12).any(|month| {
+            schedule.months & 1 << month != 0
+                && (schedule.weekdays_restricted
+                    || (1..=days_in_month(month)).any(|day| schedule.days & 1 << day != 0))
+        });
+        if !possible {
+            return Err(CronError::NeverMatches(schedule.expression));
+        }
+        Ok(schedule)
+    }
+
+    fn matches_date(&self, date: Date) -> bool {
+        if self.months & 1 << date.month() == 0 {
+            return false;
+        }
+        let day = self.days & 1 << date.day() != 0;
+        let weekday = self.weekdays & 1 << date.weekday().to_sunday_zero_offset() != 0;
+        // Standard cron matches either field when both are restricted
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// Finds the first time after `time` matching the schedule, in `time`'s timezone.
+    pub fn next_after(&self, time: &Zoned) -> Result<Zoned, jiff::Error> {
+        let start = time.datetime();
+        let mut date = start.date();
+        // Parsing guarantees a match exists, otherwise this stops at the end of the calendar
+        loop {
+            if self.matches_date(date) {
+                for hour in (0..24).filter(|hour| self.hours & 1 << hour != 0) {
+                    for minute in (0..60).filter(|minute| self.minutes & 1 << minute != 0) {
+                        let candidate = date.at(hour, minute, 0, 0);
+                        if candidate <= start {
+                            continue;
+                        }
+                        let zoned = candidate.to_zoned(time.time_zone().clone())?;
+                        if zoned > *time {
+                            return Ok(zoned);
+                        }
+                    }
+                }
+            }
+            date = date.tomorrow()?;
+        }
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = CronError;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        CronSchedule::parse(&expression)
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
@@ -0,0 +1,498 @@
+//! An optional web dashboard showing a user's reminders on a calendar, where they can be
+//! scheduled, moved, reworded and cancelled. It's served with a small JSON API on
+//! `DASHBOARD_ADDR`, and `$dashboard` replies with the page at `DASHBOARD_URL`. Users sign in
+//! with Discord, through the application in `DISCORD_CLIENT_ID` and `DISCORD_CLIENT_SECRET`.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    body::HttpBody,
+    header::{CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use reqwest::Url;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    config::config,
+    dependency, get_preferences, leader, manage, save, stats, validate_message,
+    web::CLIENT,
+    CommandError, Reminder, Reply, TimeFormat, REMINDERS,
+};
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const TOKEN_URL: &str = "https://discord.com/api/oauth2/token";
+const USER_URL: &str = "https://discord.com/api/users/@me";
+
+/// How long a sign in lasts. Sessions are only kept in memory, a restart signs everyone out.
+const SESSION_LIFETIME: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Largest request body accepted, a reminder is at most 2000 characters.
+const MAX_BODY: usize = 16 * 1024;
+/// How long someone has to approve the sign in on Discord.
+const STATE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+/// Sign ins waiting on Discord at once, so anonymous visitors can't fill memory.
+const MAX_STATES: usize = 1000;
+const SESSION_COOKIE: &str = "session";
+const STATE_COOKIE: &str = "oauth_state";
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Signed in browsers, by the secret in their cookie.
+static SESSIONS: LazyLock<Mutex<HashMap<String, (UserId, Instant)>>> =
+    LazyLock::new(Default::default);
+/// Sign ins sent to Discord, by the state they'll come back with.
+static STATES: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(Default::default);
+
+struct Settings {
+    addr: SocketAddr,
+    url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+/// The dashboard is enabled by setting `DASHBOARD_ADDR` to the address to listen on,
+/// `DASHBOARD_URL` to where it can be reached from outside, and the Discord application to sign
+/// in with. There's no other way to sign in, so without the application there's no dashboard.
+fn settings() -> Option<Settings> {
+    let addr = std::env::var("DASHBOARD_ADDR").ok()?.parse().ok()?;
+    let url = std::env::var("DASHBOARD_URL").ok()?;
+    Some(Settings {
+        addr,
+        url: url.trim_end_matches('/').to_string(),
+        client_id: std::env::var("DISCORD_CLIENT_ID").ok()?,
+        client_secret: std::env::var("DISCORD_CLIENT_SECRET").ok()?,
+    })
+}
+
+pub fn address() -> Option<SocketAddr> {
+    settings().map(|settings| settings.addr)
+}
+
+fn secret() -> String {
+    let mut bytes = [0; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("No secure random source");
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `$dashboard`, the address of the page. Signing in happens there, so it can be shared anywhere.
+pub async fn link() -> Result<Reply, CommandError> {
+    if !config().await.features.http_api {
+        return Err(CommandError::DashboardDisabled);
+    }
+    let settings = settings().ok_or(CommandError::DashboardDisabled)?;
+    Ok(format!("Sign in to your dashboard with Discord at {}", settings.url).into())
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct DiscordUser {
+    id: UserId,
+}
+
+#[derive(Serialize)]
+struct Entry {
+    key: u64,
+    id: usize,
+    message: String,
+    /// The day it's due in the user's timezone, as YYYY-MM-DD
+    date: String,
+    /// The time of day it's due in the user's preferred format
+    time: String,
+    repeats: bool,
+    paused: bool,
+    channel: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewReminder {
+    time: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Change {
+    time: Option<String>,
+    message: Option<String>,
+}
+
+fn respond(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn error(status: StatusCode, message: impl std::fmt::Display) -> Response<Body> {
+    respond(status, json!({ "error": message.to_string() }))
+}
+
+fn redirect(location: &str, cookies: &[String]) -> Response<Body> {
+    let mut response = Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(LOCATION, location);
+    for cookie in cookies {
+        response = response.header(SET_COOKIE, cookie);
+    }
+    response.body(Body::empty()).unwrap()
+}
+
+fn cookie(value: &str, max_age: Duration) -> String {
+    format!(
+        "{SESSION_COOKIE}={value}; Max-Age={}; Path=/; HttpOnly; Secure; SameSite=Strict",
+        max_age.as_secs()
+    )
+}
+
+/// Lax rather than strict, since it has to come along when Discord sends the browser back.
+fn state_cookie(value: &str, max_age: Duration) -> String {
+    format!(
+        "{STATE_COOKIE}={value}; Max-Age={}; Path=/callback; HttpOnly; Secure; SameSite=Lax",
+        max_age.as_secs()
+    )
+}
+
+fn query<'a>(request: &'a Request<Body>, name: &str) -> Option<&'a str> {
+    request.uri().query()?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then_some(value)
+    })
+}
+
+fn read_cookie(request: &Request<Body>, name: &str) -> Option<String> {
+    let cookies = request.headers().get(COOKIE)?.to_str().ok()?;
+    cookies.split(';').find_map(|cookie| {
+        let (key, value) = cookie.trim().split_once('=')?;
+        (key == name).then(|| value.to_string())
+    })
+}
+
+fn session_secret(request: &Request<Body>) -> Option<String> {
+    read_cookie(request, SESSION_COOKIE)
+}
+
+async fn signed_in(request: &Request<Body>) -> Option<UserId> {
+    let secret = session_secret(request)?;
+    let mut sessions = SESSIONS.lock().await;
+    sessions.retain(|_, (_, created)| created.elapsed() < SESSION_LIFETIME);
+    sessions.get(&secret).map(|(user, _)| *user)
+}
+
+/// Reads a JSON body, refusing anything else so another site's form can't post here.
+async fn read_json<T: for<'de> Deserialize<'de>>(request: Request<Body>) -> Result<T, String> {
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return Err("Expected a JSON body".into());
+    }
+    let mut body = request.into_body();
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|err| err.to_string())?;
+        if bytes.len() + chunk.len() > MAX_BODY {
+            return Err("The request is too large".into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&bytes).map_err(|err| err.to_string())
+}
+
+async fn start_session(user: UserId, mut cookies: Vec<String>) -> Response<Body> {
+    let secret = secret();
+    SESSIONS
+        .lock()
+        .await
+        .insert(secret.clone(), (user, Instant::now()));
+    cookies.push(cookie(&secret, SESSION_LIFETIME));
+    redirect("/", &cookies)
+}
+
+async fn sign_out(request: &Request<Body>) -> Response<Body> {
+    if let Some(secret) = session_secret(request) {
+        SESSIONS.lock().await.remove(&secret);
+    }
+    redirect("/", &[cookie("", Duration::ZERO)])
+}
+
+/// Sends the browser to Discord to approve signing in, remembering the state it should come
+/// back with both here and in a cookie, so a callback started by someone else is refused.
+async fn authorize(settings: &Settings) -> Response<Body> {
+    let state = secret();
+    let mut states = STATES.lock().await;
+    states.retain(|_, created| created.elapsed() < STATE_LIFETIME);
+    if states.len() >= MAX_STATES {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many sign ins at once, try again in a few minutes",
+        );
+    }
+    states.insert(state.clone(), Instant::now());
+    drop(states);
+    let location = Url::parse_with_params(
+        AUTHORIZE_URL,
+        [
+            ("response_type", "code"),
+            ("client_id", &settings.client_id),
+            ("scope", "identify"),
+            ("state", &state),
+            ("redirect_uri", &format!("{}/callback", settings.url)),
+            ("prompt", "none"),
+        ],
+    )
+    .expect("The authorize URL is valid");
+    redirect(location.as_str(), &[state_cookie(&state, STATE_LIFETIME)])
+}
+
+/// Trades the code from Discord for a token, only to look up who signed in. The token isn't kept.
+async fn identify(settings: &Settings, code: &str) -> Result<UserId, String> {
+    let redirect_uri = format!("{}/callback", settings.url);
+    let token: Token = CLIENT
+        .post(TOKEN_URL)
+        .form(&[
+            ("client_id", settings.client_id.as_str()),
+            ("client_secret", &settings.client_secret),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &redirect_uri),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())?;
+    let user: DiscordUser = CLIENT
+        .get(USER_URL)
+        .bearer_auth(token.access_token)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(user.id)
+}
+
+async fn callback(request: &Request<Body>, settings: &Settings) -> Response<Body> {
+    let state = query(request, "state").unwrap_or_default();
+    let issued = STATES.lock().await.remove(state);
+    let same_browser = read_cookie(request, STATE_COOKIE).is_some_and(|cookie| cookie == state);
+    let fresh = issued.is_some_and(|created| created.elapsed() < STATE_LIFETIME);
+    if !(fresh && same_browser) {
+        return error(
+            StatusCode::FORBIDDEN,
+            "That sign in has expired or was started elsewhere, try again",
+        );
+    }
+    let Some(code) = query(request, "code") else {
+        return error(StatusCode::FORBIDDEN, "Signing in was cancelled on Discord");
+    };
+    match identify(settings, code).await {
+        Ok(user) => start_session(user, vec![state_cookie("", Duration::ZERO)]).await,
+        Err(err) => {
+            eprintln!("Couldn't sign in to the dashboard with Discord: {err}");
+            error(StatusCode::BAD_GATEWAY, "Couldn't sign in with Discord")
+        }
+    }
+}
+
+async fn list(user: UserId) -> Response<Body> {
+    let preferences = get_preferences(user, None).await;
+    let time_zone = preferences.time_zone();
+    let clock = match preferences.time_format() {
+        TimeFormat::H12 => "%-I:%M%P",
+        TimeFormat::H24 => "%-H:%M",
+    };
+    let cache = REMINDERS.lock().await;
+    let reminders: Vec<_> = cache
+        .get(&user)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(id, reminder)| {
+            let local = reminder.time.with_time_zone(time_zone.clone());
+            Entry {
+                key: reminder.key,
+                id,
+                message: reminder.message.clone(),
+                date: local.date().to_string(),
+                time: local.strftime(clock).to_string(),
+                repeats: reminder.interval.is_some(),
+                paused: reminder.paused,
+                channel: reminder.channel.map(|channel| channel.to_string()),
+            }
+        })
+        .collect();
+    drop(cache);
+    let today = jiff::Zoned::now().with_time_zone(time_zone).date();
+    respond(
+        StatusCode::OK,
+        json!({ "today": today.to_string(), "reminders": reminders }),
+    )
+}
+
+async fn create(user: UserId, new: NewReminder) -> Result<Response<Body>, String> {
+    let preferences = get_preferences(user, None).await;
+    let message = validate_message(&new.message).map_err(|e| e.to_string())?;
+    let time = manage::read_time(&new.time, &preferences)?;
+    let reminder = Reminder::new(time, message);
+    audit::record(user, &reminder, Action::Created, "dashboard").await;
+    let key = reminder.key;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    list.push(reminder);
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+    drop(cache);
+    stats::record(user, |stats| stats.scheduled += 1).await;
+    save();
+    Ok(respond(StatusCode::CREATED, json!({ "key": key })))
+}
+
+async fn change(user: UserId, key: u64, change: Change) -> Result<Response<Body>, String> {
+    let preferences = get_preferences(user, None).await;
+    let message = change
+        .message
+        .map(|message| validate_message(&message).map_err(|e| e.to_string()))
+        .transpose()?;
+    let time = change
+        .time
+        .map(|time| manage::read_time(&time, &preferences))
+        .transpose()?;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    let reminder = list
+        .iter_mut()
+        .find(|r| r.key == key)
+        .ok_or("That reminder no longer exists")?;
+    if let Some(message) = message {
+        reminder.message = message;
+    }
+    let moved = time.is_some();
+    if let Some(time) = time {
+        reminder.time = time;
+        reminder.anchor_day = None;
+        reminder.rolled_from = None;
+    }
+    audit::record(user, reminder, Action::Edited, "dashboard").await;
+    if moved {
+        dependency::shift(list, key);
+        list.sort_by(|a, b| a.time.cmp(&b.time));
+    }
+    drop(cache);
+    save();
+    Ok(respond(StatusCode::OK, json!({ "key": key })))
+}
+
+async fn cancel(user: UserId, key: u64) -> Result<Response<Body>, String> {
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    let position = list
+        .iter()
+        .position(|r| r.key == key)
+        .ok_or("That reminder no longer exists")?;
+    let reminder = list.remove(position);
+    audit::record(user, &reminder, Action::Cancelled, "dashboard").await;
+    drop(cache);
+    stats::record(user, |stats| stats.cancelled += 1).await;
+    save();
+    Ok(respond(StatusCode::OK, json!({ "key": key })))
+}
+
+async fn route(request: Request<Body>) -> Response<Body> {
+    // Standby instances don't write save files, so changes made through them would be lost
+    if !leader::is_leader() {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "This instance is on standby",
+        );
+    }
+    // The server only starts with these set, but they're read again in case the environment changed
+    let Some(settings) = settings() else {
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The dashboard isn't set up",
+        );
+    };
+    let path = request.uri().path().to_string();
+    let path: Vec<_> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match (request.method(), path.as_slice()) {
+        (&Method::GET, []) => Response::builder()
+            .header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Body::from(PAGE))
+            .unwrap(),
+        (&Method::GET, ["oauth"]) => authorize(&settings).await,
+        (&Method::GET, ["callback"]) => callback(&request, &settings).await,
+        (&Method::POST, ["logout"]) => sign_out(&request).await,
+        (_, ["api", ..]) => {
+            let Some(user) = signed_in(&request).await else {
+                return error(StatusCode::UNAUTHORIZED, "Sign in with Discord first");
+            };
+            let result = match (request.method().clone(), &path[1..]) {
+                (Method::GET, ["reminders"]) => Ok(list(user).await),
+                (Method::POST, ["reminders"]) => match read_json(request).await {
+                    Ok(new) => create(user, new).await,
+                    Err(err) => Err(err),
+                },
+                (Method::PATCH, ["reminders", key]) => {
+                    match (key.parse(), read_json(request).await) {
+                        (Ok(key), Ok(body)) => change(user, key, body).await,
+                        (Err(_), _) => Err("That isn't a reminder".into()),
+                        (_, Err(err)) => Err(err),
+                    }
+                }
+                (Method::DELETE, ["reminders", key]) => match key.parse() {
+                    Ok(key) => cancel(user, key).await,
+                    Err(_) => Err("That isn't a reminder".into()),
+                },
+                _ => return error(StatusCode::NOT_FOUND, "Not found"),
+            };
+            result.unwrap_or_else(|err| error(StatusCode::BAD_REQUEST, err))
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+/// Serves the dashboard until the process exits.
+pub async fn serve(addr: SocketAddr) {
+    let service = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|request| async {
+            Ok::<_, Infallible>(route(request).await)
+        }))
+    });
+    let server = match Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(service),
+        Err(err) => {
+            eprintln!("Couldn't start the dashboard on {addr}: {err}");
+            return;
+        }
+    };
+    if let Err(err) = server.await {
+        eprintln!("The dashboard stopped: {err}");
+    }
+}
@@ -0,0 +1,76 @@
+//! Remembers which occurrences have gone to whom, so a retry, crash recovery or a second
+//! instance taking over never sends the same occurrence twice.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
+
+use jiff::{Timestamp, ToSpan};
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{delivery::Recipient, write_state, Reminder};
+
+pub const SENT_FILE: &str = "sent.json";
+
+/// How long sends are remembered, well past any retry or recovery delay.
+const KEEP_DAYS: i64 = 7;
+
+/// When each occurrence was sent, by `key`.
+static SENT: LazyLock<Mutex<HashMap<String, Timestamp>>> = LazyLock::new(Default::default);
+/// Whether sends were marked since the file was last written.
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+pub async fn load_sent() {
+    let Ok(sent_json) = tokio::fs::read_to_string(SENT_FILE).await else {
+        return;
+    };
+    let sent = serde_json::from_str(&sent_json).unwrap();
+    *SENT.lock().await = sent;
+}
+
+/// Identifies one occurrence of a reminder going to one recipient. Retry copies share the key of
+/// the occurrence they're retrying, through the original's key rather than their own.
+pub fn key(reminder: &Reminder, recipient: Recipient) -> String {
+    let id = match recipient {
+        Recipient::User(user) => user.get(),
+        Recipient::Channel(channel) => channel.get(),
+    };
+    format!(
+        "{}:{}:{id}",
+        reminder.origin(),
+        reminder.occurrence().as_millisecond()
+    )
+}
+
+/// Identifies one occurrence of a reminder going to one of its followers, apart from the owner's
+/// own copy even if they follow it too.
+pub fn follower_key(reminder: &Reminder, follower: UserId) -> String {
+    format!("{}:follower", key(reminder, Recipient::User(follower)))
+}
+
+pub async fn sent(key: &str) -> bool {
+    SENT.lock().await.contains_key(key)
+}
+
+/// Records a finished send, which reaches the disk with the next `flush`. A crash before then can
+/// still repeat it, everything after can't.
+pub async fn mark(key: String) {
+    SENT.lock().await.insert(key, Timestamp::now());
+    CHANGED.store(true, Ordering::Relaxed);
+}
+
+/// Writes the sends marked since the last call, once per scheduler pass rather than per send.
+pub async fn flush() {
+    if !CHANGED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let mut sent = SENT.lock().await;
+    let cutoff = Timestamp::now() - (KEEP_DAYS * 24).hours();
+    sent.retain(|_, at| *at > cutoff);
+    write_state(SENT_FILE, serde_json::to_string(&*sent)).await;
+}
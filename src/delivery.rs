@@ -0,0 +1,404 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
+
+use jiff::{ToSpan, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, CreateMessage, Http, Message, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    config::config, dedup, diagnostics, humanize, log_error, matrix, stats, telegram, CommandError,
+    Preferences, Reminder,
+};
+
+pub const HISTORY_FILE: &str = "history.json";
+
+/// Deliveries kept per user, the oldest are dropped first.
+const MAX_HISTORY: usize = 50;
+/// Failed messages listed in a digest before the rest are summed up.
+const DIGEST_LENGTH: usize = 5;
+/// Characters of each failed message shown in a digest.
+const DIGEST_MESSAGE_LENGTH: usize = 60;
+/// Minutes to wait before each retry of a failed delivery.
+const RETRY_DELAYS: [i64; 3] = [1, 5, 15];
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    #[default]
+    Pending,
+    Delivered,
+    Failed,
+    Retrying,
+    Completed,
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+            DeliveryStatus::Retrying => "retrying",
+            DeliveryStatus::Completed => "done",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Delivery {
+    pub attempted: Zoned,
+    pub message: String,
+    pub status: DeliveryStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Whether the user has been told about this failure
+    #[serde(default)]
+    pub reported: bool,
+}
+
+/// Where a scheduled message goes.
+#[derive(Clone, Copy)]
+pub enum Recipient {
+    User(UserId),
+    Channel(ChannelId),
+}
+
+impl Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Not mentions, so dry-run posts don't ping the real recipients
+        match self {
+            Recipient::User(user) => write!(f, "user {user}"),
+            Recipient::Channel(channel) => write!(f, "channel {channel}"),
+        }
+    }
+}
+
+/// Set by `--dry-run`, for replaying production data in staging without messaging anyone.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn enable_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Sends a scheduled message, returning what was sent. In a dry run it's logged instead, and
+/// posted to the configured `dry_run_channel` if there is one.
+pub async fn send(
+    http: &Http,
+    recipient: Recipient,
+    message: CreateMessage,
+) -> Result<Option<Message>, serenity::Error> {
+    if dry_run() {
+        let message = serde_json::to_value(&message).unwrap_or_default();
+        let content = message["content"].as_str().unwrap_or_default();
+        let line = format!("[dry run] to {recipient}: {content}");
+        println!("{line}");
+        if let Some(channel) = config().await.dry_run_channel {
+            let post = CreateMessage::new().content(line);
+            diagnostics::track("delivery", channel.send_message(http, post)).await?;
+        }
+        return Ok(None);
+    }
+    match recipient {
+        Recipient::User(user) => diagnostics::track("delivery", user.dm(http, message))
+            .await
+            .map(Some),
+        Recipient::Channel(channel) => {
+            diagnostics::track("delivery", channel.send_message(http, message))
+                .await
+                .map(Some)
+        }
+    }
+}
+
+/// Longest window `$jitter` and `delivery_jitter_secs` can spread copies over.
+pub const MAX_JITTER_SECS: u64 = 3600;
+
+/// Checks a window set with `$jitter`, describing it for the reply.
+pub fn describe_jitter(jitter: Option<u64>) -> Result<String, CommandError> {
+    Ok(match jitter {
+        None => "spread over the default window".into(),
+        Some(0) => "sent to everyone at once".into(),
+        Some(secs) if secs <= MAX_JITTER_SECS => format!("spread over {secs} seconds"),
+        Some(_) => return Err(CommandError::InvalidJitter),
+    })
+}
+
+/// A copy of a reminder that's waiting for its turn in a spread out fan-out.
+struct Spread {
+    due: Zoned,
+    user: UserId,
+    message: CreateMessage,
+    /// From `dedup::follower_key`
+    occurrence: String,
+    /// Kept in the recipient's history and mirrored when set
+    record: Option<String>,
+}
+
+/// Only kept in memory, the windows are short enough that a restart loses little.
+static SPREAD: LazyLock<Mutex<Vec<Spread>>> = LazyLock::new(Default::default);
+
+/// Sends a copy of `reminder` to each user. With a jitter window the copies are spaced evenly
+/// over it, so a long list doesn't use up the rate limit at once. The first goes out now and the
+/// scheduler sends the rest as they come due.
+pub async fn fan_out(
+    http: &Http,
+    users: &[UserId],
+    message: CreateMessage,
+    reminder: &Reminder,
+    record: Option<&str>,
+) {
+    let jitter = match reminder.jitter {
+        Some(jitter) => jitter,
+        None => config().await.delivery_jitter_secs,
+    };
+    let now = Zoned::now();
+    let mut waiting = vec![];
+    for (i, user) in users.iter().enumerate() {
+        let offset = (jitter * 1000 * i as u64 / users.len() as u64) as i64;
+        let occurrence = dedup::follower_key(reminder, *user);
+        if offset == 0 {
+            send_copy(http, *user, message.clone(), occurrence, record).await;
+            continue;
+        }
+        waiting.push(Spread {
+            due: now
+                .checked_add(offset.milliseconds())
+                .unwrap_or_else(|_| now.clone()),
+            user: *user,
+            message: message.clone(),
+            occurrence,
+            record: record.map(str::to_string),
+        });
+    }
+    SPREAD.lock().await.extend(waiting);
+}
+
+async fn send_copy(
+    http: &Http,
+    user: UserId,
+    message: CreateMessage,
+    occurrence: String,
+    record: Option<&str>,
+) {
+    if dedup::sent(&occurrence).await {
+        return;
+    }
+    let result = send(http, Recipient::User(user), message).await;
+    if result.is_ok() {
+        dedup::mark(occurrence).await;
+    }
+    if let Some(text) = record {
+        let status = match result {
+            Ok(_) => DeliveryStatus::Delivered,
+            Err(_) => DeliveryStatus::Failed,
+        };
+        self::record(user, text, &result, status).await;
+        if result.is_ok() {
+            stats::record(user, |stats| stats.delivered += 1).await;
+        }
+        mirror(user, text).await;
+    }
+    log_error(result);
+}
+
+/// How many copies from `fan_out` are still waiting for their turn.
+pub async fn spread_waiting() -> usize {
+    SPREAD.lock().await.len()
+}
+
+/// Sends the copies from `fan_out` that have come due.
+pub async fn release_spread(http: &Http) {
+    let now = Zoned::now();
+    let due: Vec<_> = {
+        let mut spread = SPREAD.lock().await;
+        let (due, waiting) = std::mem::take(&mut *spread)
+            .into_iter()
+            .partition(|copy| copy.due <= now);
+        *spread = waiting;
+        due
+    };
+    for copy in due {
+        send_copy(
+            http,
+            copy.user,
+            copy.message,
+            copy.occurrence,
+            copy.record.as_deref(),
+        )
+        .await;
+    }
+}
+
+/// Somewhere outside Discord that a user's reminders are copied to.
+pub trait Notifier {
+    /// Describes the destination for logs, without any credentials.
+    fn name(&self) -> String;
+
+    async fn notify(&self, text: &str) -> Result<(), String>;
+}
+
+async fn notify(notifier: impl Notifier, text: &str) {
+    if dry_run() {
+        println!("[dry run] to {}: {text}", notifier.name());
+        return;
+    }
+    log_error(notifier.notify(text).await);
+}
+
+/// Copies a delivered reminder to every other platform the user has set up.
+pub async fn mirror(user: UserId, text: &str) {
+    if let Some(chat) = telegram::notifier(user).await {
+        notify(chat, text).await;
+    }
+    if let Some(room) = matrix::notifier(user).await {
+        notify(room, text).await;
+    }
+}
+
+pub static HISTORY: LazyLock<Mutex<HashMap<UserId, VecDeque<Delivery>>>> =
+    LazyLock::new(Default::default);
+
+pub async fn load_history() {
+    let Ok(history_json) = tokio::fs::read_to_string(HISTORY_FILE).await else {
+        return;
+    };
+    let history = serde_json::from_str(&history_json).unwrap();
+    *HISTORY.lock().await = history;
+}
+
+pub async fn record<T, E: Display>(
+    user: UserId,
+    message: &str,
+    result: &Result<T, E>,
+    status: DeliveryStatus,
+) {
+    let delivery = Delivery {
+        attempted: Zoned::now(),
+        message: message.to_string(),
+        status,
+        error: result.as_ref().err().map(ToString::to_string),
+        reported: false,
+    };
+    push(user, delivery).await;
+}
+
+/// Adds a reminder being marked done to the history, `attempted` being when it was done.
+pub async fn completed(user: UserId, message: &str) {
+    let delivery = Delivery {
+        attempted: Zoned::now(),
+        message: message.to_string(),
+        status: DeliveryStatus::Completed,
+        error: None,
+        reported: false,
+    };
+    push(user, delivery).await;
+}
+
+async fn push(user: UserId, delivery: Delivery) {
+    let mut history = HISTORY.lock().await;
+    let deliveries = history.entry(user).or_default();
+    deliveries.push_back(delivery);
+    while deliveries.len() > MAX_HISTORY {
+        deliveries.pop_front();
+    }
+}
+
+/// Describes failed deliveries the user hasn't been told about yet and marks them as reported.
+/// Messages are only listed when `private`, elsewhere they're left for `$history`.
+pub async fn digest(user: UserId, private: bool, preferences: &Preferences) -> Option<String> {
+    if !config().await.features.digests {
+        return None;
+    }
+    let mut history = HISTORY.lock().await;
+    let failed: Vec<_> = history
+        .get_mut(&user)?
+        .iter_mut()
+        .filter(|delivery| delivery.status == DeliveryStatus::Failed && !delivery.reported)
+        .map(|delivery| {
+            delivery.reported = true;
+            delivery.clone()
+        })
+        .collect();
+    let first = failed.first()?;
+    let ago = Zoned::now().duration_since(&first.attempted).unsigned_abs();
+    let since = format!(
+        "{} ({})",
+        first
+            .attempted
+            .with_time_zone(preferences.time_zone())
+            .strftime("%A, %B %-d"),
+        humanize::since(ago, preferences.language())
+    );
+    let count = match failed.len() {
+        1 => "1 reminder".to_string(),
+        count => format!("{count} reminders"),
+    };
+    if !private {
+        return Some(format!(
+            "{count} couldn't be delivered since {since}, check that I can DM you and see `$history`"
+        ));
+    }
+    let mut messages: Vec<_> = failed
+        .iter()
+        .take(DIGEST_LENGTH)
+        .map(
+            |delivery| match delivery.message.char_indices().nth(DIGEST_MESSAGE_LENGTH) {
+                Some((end, _)) => format!("'{}...'", &delivery.message[..end]),
+                None => format!("'{}'", delivery.message),
+            },
+        )
+        .collect();
+    if failed.len() > DIGEST_LENGTH {
+        messages.push(format!("and {} more", failed.len() - DIGEST_LENGTH));
+    }
+    Some(format!(
+        "{count} couldn't be delivered since {since}: {}",
+        messages.join(", ")
+    ))
+}
+
+/// Forgets deliveries attempted before `cutoff`.
+pub async fn purge(cutoff: &Zoned) {
+    let mut history = HISTORY.lock().await;
+    for deliveries in history.values_mut() {
+        deliveries.retain(|delivery| delivery.attempted >= *cutoff);
+    }
+    history.retain(|_, deliveries| !deliveries.is_empty());
+}
+
+pub async fn recent(user: UserId, count: usize) -> Vec<Delivery> {
+    let history = HISTORY.lock().await;
+    let Some(deliveries) = history.get(&user) else {
+        return vec![];
+    };
+    deliveries.iter().rev().take(count).cloned().collect()
+}
+
+/// A one-off copy of a reminder whose delivery failed, or None once retries are used up.
+/// The copy doesn't repeat or notify followers, the original was already rescheduled.
+pub fn retry(reminder: &Reminder) -> Option<Reminder> {
+    let delay = RETRY_DELAYS.get(reminder.attempts as usize)?;
+    let copy = Reminder {
+        time: Zoned::now().checked_add(delay.minutes()).ok()?,
+        interval: None,
+        public: false,
+        followers: vec![],
+        attempts: reminder.attempts + 1,
+        after: None,
+        condition: None,
+        ..reminder.clone()
+    };
+    Some(copy.stand_in_for(reminder))
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+
+use crate::Reminder;
+
+/// Ties a reminder to `offset` milliseconds after the reminder with `key`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Dependency {
+    pub key: u64,
+    pub offset: u64,
+}
+
+impl Dependency {
+    pub fn time_after(&self, reference: &Zoned) -> Zoned {
+        reference + Duration::from_millis(self.offset)
+    }
+}
+
+/// Moves everything scheduled after the reminder with `key` to follow its current time, and
+/// whatever depends on those in turn. Called when a reminder is moved by hand, not when it fires,
+/// so a pending follow up isn't pushed back by its reference repeating.
+pub fn shift(list: &mut [Reminder], key: u64) {
+    let mut pending = vec![key];
+    while let Some(key) = pending.pop() {
+        let Some(time) = list
+            .iter()
+            .find(|reminder| reminder.key == key)
+            .map(|reminder| reminder.time.clone())
+        else {
+            continue;
+        };
+        for reminder in list.iter_mut() {
+            if let Some(dependency) = reminder.after.filter(|d| d.key == key) {
+                reminder.time = dependency.time_after(&time);
+                // A repeating follow up carries on from its new day of the month
+                reminder.anchor_day = None;
+                reminder.rolled_from = None;
+                pending.push(reminder.key);
+            }
+        }
+    }
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+}
@@ -0,0 +1,95 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, LazyLock, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use serenity::all::{ShardId, ShardManager};
+
+use crate::{format_duration, storage};
+
+/// A task still running after this long is reported as possibly stalled.
+const STALL_THRESHOLD: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct TaskStats {
+    runs: u64,
+    in_flight: Vec<Instant>,
+    last: Option<Duration>,
+    longest: Duration,
+    finished: Option<Instant>,
+}
+
+static TASKS: LazyLock<Mutex<BTreeMap<&'static str, TaskStats>>> = LazyLock::new(Default::default);
+
+/// Runs a future while recording it under `name` for `$admin tasks`.
+pub async fn track<T>(name: &'static str, future: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    TASKS
+        .lock()
+        .unwrap()
+        .entry(name)
+        .or_default()
+        .in_flight
+        .push(started);
+    let value = future.await;
+    let elapsed = started.elapsed();
+    let mut tasks = TASKS.lock().unwrap();
+    let stats = tasks.entry(name).or_default();
+    stats.runs += 1;
+    stats.in_flight.retain(|start| *start != started);
+    stats.last = Some(elapsed);
+    stats.longest = stats.longest.max(elapsed);
+    stats.finished = Some(Instant::now());
+    value
+}
+
+/// How long ago a run of the task called `name` last finished, None if none has.
+pub fn since_finished(name: &str) -> Option<Duration> {
+    let tasks = TASKS.lock().unwrap();
+    tasks.get(name)?.finished.map(|finished| finished.elapsed())
+}
+
+/// Set at startup, for reading gateway latency.
+pub static SHARDS: OnceLock<Arc<ShardManager>> = OnceLock::new();
+
+/// The latest heartbeat round trip of a shard, None before the first one.
+pub async fn latency(shard: ShardId) -> Option<Duration> {
+    let runners = SHARDS.get()?.runners.lock().await;
+    runners.get(&shard)?.latency
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+pub fn report() -> String {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    let mut lines = vec![format!(
+        "Runtime: {} workers, {} alive tasks, {} queued",
+        metrics.num_workers(),
+        metrics.num_alive_tasks(),
+        metrics.global_queue_depth()
+    )];
+    lines.push(storage::summary());
+    let tasks = TASKS.lock().unwrap();
+    for (name, stats) in tasks.iter() {
+        let mut line = format!(
+            "{name}: {} runs, {} in flight, last took {}, longest {}",
+            stats.runs,
+            stats.in_flight.len(),
+            stats.last.map(millis).unwrap_or_else(|| "-".into()),
+            millis(stats.longest)
+        );
+        let oldest = stats.in_flight.iter().map(Instant::elapsed).max();
+        if let Some(running) = oldest.filter(|running| *running >= STALL_THRESHOLD) {
+            line.push_str(&format!(
+                " (one has been running for {}, possibly stalled)",
+                format_duration(running)
+            ));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
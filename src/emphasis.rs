@@ -0,0 +1,148 @@
+//! `$emphasis`, how hard a reminder tries to get noticed: delivered silently, as usual, or
+//! with a mention read aloud and pinged again until it's marked done.
+
+use std::{fmt::Display, sync::LazyLock};
+
+use jiff::{ToSpan, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateAllowedMentions, CreateButton, CreateMessage, Http,
+    MessageFlags, UserId,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    completion,
+    delivery::{self, Recipient},
+    log_error, Reminder,
+};
+
+/// Minutes after delivery that an urgent reminder is pinged again, unless it's been done.
+const PING_MINUTES: [i64; 2] = [2, 5];
+const PING_MESSAGE_LENGTH: usize = 200;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Emphasis {
+    /// Delivered without a notification
+    Quiet,
+    #[default]
+    Normal,
+    Urgent,
+}
+
+impl Display for Emphasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Emphasis::Quiet => write!(f, "quiet"),
+            Emphasis::Normal => write!(f, "normal"),
+            Emphasis::Urgent => write!(f, "urgent"),
+        }
+    }
+}
+
+/// Builders can't be read back, so the content is taken out the way a dry run logs it.
+pub fn content(message: &CreateMessage) -> String {
+    serde_json::to_value(message)
+        .ok()
+        .and_then(|message| message["content"].as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Applies a reminder's emphasis to its delivery.
+pub fn apply(message: CreateMessage, emphasis: Emphasis, owner: UserId) -> CreateMessage {
+    match emphasis {
+        Emphasis::Quiet => message.flags(MessageFlags::SUPPRESS_NOTIFICATIONS),
+        Emphasis::Normal => message,
+        Emphasis::Urgent => {
+            let mentioned = format!("<@{owner}> {}", content(&message));
+            // Longest messages go without the mention, the repeat pings still have it
+            let message = if mentioned.chars().count() <= 2000 {
+                message.content(mentioned)
+            } else {
+                message
+            };
+            message
+                .tts(true)
+                .allowed_mentions(CreateAllowedMentions::new().users([owner]))
+        }
+    }
+}
+
+/// A repeat ping for an urgent reminder.
+struct Ping {
+    due: Zoned,
+    recipient: Recipient,
+    owner: UserId,
+    key: u64,
+    message: String,
+    delivered: Zoned,
+}
+
+/// Only kept in memory, a restart skipping a ping a few minutes out is no great loss.
+static PINGS: LazyLock<Mutex<Vec<Ping>>> = LazyLock::new(Default::default);
+
+/// Queues the repeat pings for a delivered reminder, if it's urgent.
+pub async fn follow_up(recipient: Recipient, owner: UserId, reminder: &Reminder) {
+    if reminder.emphasis != Emphasis::Urgent {
+        return;
+    }
+    let delivered = Zoned::now();
+    let message = match reminder.message.char_indices().nth(PING_MESSAGE_LENGTH) {
+        Some((end, _)) => format!("{}...", &reminder.message[..end]),
+        None => reminder.message.clone(),
+    };
+    let mut pings = PINGS.lock().await;
+    for minutes in PING_MINUTES {
+        let Ok(due) = delivered.checked_add(minutes.minutes()) else {
+            continue;
+        };
+        pings.push(Ping {
+            due,
+            recipient,
+            owner,
+            key: reminder.key,
+            message: message.clone(),
+            delivered: delivered.clone(),
+        });
+    }
+}
+
+/// Sends the repeat pings that have come due for reminders that still aren't done.
+pub async fn release_pings(http: &Http) {
+    let now = Zoned::now();
+    let due: Vec<_> = {
+        let mut pings = PINGS.lock().await;
+        let (due, waiting) = std::mem::take(&mut *pings)
+            .into_iter()
+            .partition(|ping| ping.due <= now);
+        *pings = waiting;
+        due
+    };
+    for ping in due {
+        if completion::done_since(ping.owner, ping.key, &ping.delivered).await {
+            continue;
+        }
+        let button = CreateButton::new(format!("reminder:done:{}:{}", ping.owner, ping.key))
+            .label("Done")
+            .style(ButtonStyle::Success);
+        let message = CreateMessage::new()
+            .content(format!(
+                "🔔 <@{}> Still waiting on: {}",
+                ping.owner, ping.message
+            ))
+            .allowed_mentions(CreateAllowedMentions::new().users([ping.owner]))
+            .components(vec![CreateActionRow::Buttons(vec![button])]);
+        log_error(delivery::send(http, ping.recipient, message).await);
+    }
+}
+
+/// Pulls a `!tts` flag out of a reminder's message, returning the message without it.
+pub fn take_tts(message: &str) -> (String, bool) {
+    let is_flag = |word: &str| word.eq_ignore_ascii_case("!tts");
+    if !message.split_whitespace().any(is_flag) {
+        return (message.to_string(), false);
+    }
+    let words: Vec<_> = message.split(' ').filter(|word| !is_flag(word)).collect();
+    (words.join(" ").trim().to_string(), true)
+}
@@ -0,0 +1,101 @@
+//! Deletes delivered reminder DMs after a while, for people who'd rather not keep them around.
+//! Deletions are kept on disk so they still happen after a restart.
+
+use std::sync::LazyLock;
+
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Http, Message, MessageId};
+use tokio::sync::Mutex;
+
+use crate::{delivery, log_error, write_state, CommandError, Preferences, Reminder};
+
+pub const EXPIRING_FILE: &str = "expiring.json";
+
+pub const MIN_LIFETIME_MS: u64 = 60 * 1000;
+pub const MAX_LIFETIME_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize)]
+struct Expiring {
+    channel: ChannelId,
+    message: MessageId,
+    at: Timestamp,
+}
+
+static EXPIRING: LazyLock<Mutex<Vec<Expiring>>> = LazyLock::new(Default::default);
+
+pub async fn load_expiring() {
+    let Ok(expiring_json) = tokio::fs::read_to_string(EXPIRING_FILE).await else {
+        return;
+    };
+    match serde_json::from_str(&expiring_json) {
+        Ok(expiring) => *EXPIRING.lock().await = expiring,
+        // Losing pending deletions isn't worth refusing to start, the file is set aside instead
+        Err(e) => {
+            let broken = format!("{EXPIRING_FILE}.broken");
+            eprintln!("Couldn't read {EXPIRING_FILE}, moving it to {broken}: {e}");
+            if let Err(e) = tokio::fs::rename(EXPIRING_FILE, &broken).await {
+                eprintln!("Failed to move {EXPIRING_FILE}: {e}");
+            }
+        }
+    }
+}
+
+async fn write(expiring: &[Expiring]) {
+    write_state(EXPIRING_FILE, serde_json::to_string(expiring)).await;
+}
+
+/// How long a delivery of `reminder` is kept in milliseconds, None to keep it. A reminder's own
+/// setting wins over the user's, with 0 keeping it even when the user's would delete it.
+pub fn lifetime(reminder: &Reminder, preferences: &Preferences) -> Option<u64> {
+    match reminder.expire {
+        Some(0) => None,
+        Some(lifetime) => Some(lifetime),
+        None => preferences.expire_after,
+    }
+}
+
+/// Queues `sent` for deletion if the reminder it delivered shouldn't be kept.
+pub async fn track(reminder: &Reminder, preferences: &Preferences, sent: &Message) {
+    let Some(lifetime) = lifetime(reminder, preferences) else {
+        return;
+    };
+    let mut expiring = EXPIRING.lock().await;
+    expiring.push(Expiring {
+        channel: sent.channel_id,
+        message: sent.id,
+        at: Timestamp::now() + SignedDuration::from_millis(lifetime as i64),
+    });
+    write(&expiring).await;
+}
+
+/// Deletes everything that's due. Failures aren't retried, the message has usually been deleted
+/// by hand already. A dry run leaves them queued for the next real run.
+pub async fn release(http: &Http) {
+    if delivery::dry_run() {
+        return;
+    }
+    let now = Timestamp::now();
+    let mut expiring = EXPIRING.lock().await;
+    if !expiring.iter().any(|e| e.at <= now) {
+        return;
+    }
+    let (due, kept) = std::mem::take(&mut *expiring)
+        .into_iter()
+        .partition::<Vec<_>, _>(|e| e.at <= now);
+    *expiring = kept;
+    write(&expiring).await;
+    drop(expiring);
+    for expired in due {
+        log_error(expired.channel.delete_message(http, expired.message).await);
+    }
+}
+
+/// Checks a lifetime set with `$expire`, long enough to read the reminder and short enough to
+/// still be worth waiting for.
+pub fn validate(lifetime: u64) -> Result<(), CommandError> {
+    match lifetime {
+        MIN_LIFETIME_MS..=MAX_LIFETIME_MS => Ok(()),
+        _ => Err(CommandError::InvalidExpiry),
+    }
+}
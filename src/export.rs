@@ -0,0 +1,78 @@
+//! `$export csv`, a user's reminders as a spreadsheet.
+
+use serenity::all::CreateAttachment;
+
+use crate::{interval, listing, Preferences, Reminder};
+
+const HEADER: [&str; 7] = [
+    "id",
+    "time",
+    "timezone",
+    "message",
+    "recurrence",
+    "tags",
+    "status",
+];
+
+/// Quotes a field when it needs it, and defuses text a spreadsheet would run as a formula.
+fn field(text: &str) -> String {
+    let text = if text.starts_with(['=', '+', '-', '@']) {
+        format!("'{text}")
+    } else {
+        text.to_string()
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn status(reminder: &Reminder) -> &'static str {
+    if reminder.attempts > 0 {
+        "retrying"
+    } else if reminder.paused {
+        "paused"
+    } else if reminder.after.is_some() {
+        "follows another reminder"
+    } else if reminder.condition.is_some() {
+        "conditional"
+    } else {
+        "scheduled"
+    }
+}
+
+/// One row per reminder, with IDs matching `$rs`.
+pub fn csv(reminders: &[Reminder], preferences: &Preferences) -> CreateAttachment {
+    let mut lines = vec![HEADER.join(",")];
+    for (id, reminder) in reminders.iter().enumerate() {
+        let time = &reminder.time;
+        let timezone = time
+            .time_zone()
+            .iana_name()
+            .unwrap_or(preferences.timezone());
+        let recurrence = reminder
+            .interval
+            .as_ref()
+            .map(|modifiers| interval::describe(modifiers, preferences.time_format()))
+            .unwrap_or_default();
+        let tags: Vec<_> = listing::tags(&reminder.message)
+            .iter()
+            .map(|tag| format!("#{tag}"))
+            .collect();
+        let row = [
+            id.to_string(),
+            time.strftime("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+            timezone.to_string(),
+            reminder.message.clone(),
+            recurrence,
+            tags.join(" "),
+            status(reminder).to_string(),
+        ];
+        let row: Vec<_> = row.iter().map(|text| field(text)).collect();
+        lines.push(row.join(","));
+    }
+    // Spreadsheets expect CRLF, and the byte order mark makes Excel read it as UTF-8
+    let text = format!("\u{feff}{}\r\n", lines.join("\r\n"));
+    CreateAttachment::bytes(text.into_bytes(), "reminders.csv")
+}
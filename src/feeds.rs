@@ -0,0 +1,263 @@
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{CreateMessage, Http, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    delivery::{self, DeliveryStatus, Recipient},
+    log_error, save,
+    web::CLIENT,
+    CommandError, Reply,
+};
+
+pub const FEEDS_FILE: &str = "feeds.json";
+
+/// How often watched feeds are fetched.
+pub const INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Feeds each user can watch at once.
+const MAX_WATCHES: usize = 10;
+/// New items sent per feed per poll, so a feed that reshuffles doesn't flood anyone.
+const MAX_NEW_ITEMS: usize = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct Watch {
+    url: String,
+    filter: Option<String>,
+    /// Ids of the items in the feed as of the last fetch
+    seen: Vec<String>,
+}
+
+pub static WATCHES: LazyLock<Mutex<HashMap<UserId, Vec<Watch>>>> = LazyLock::new(Default::default);
+
+pub enum FeedCommand {
+    List,
+    Watch(String, Option<String>),
+    Unwatch(u64),
+}
+
+struct Item {
+    id: String,
+    title: String,
+    link: String,
+}
+
+pub async fn load_feeds() {
+    let Ok(feeds_json) = tokio::fs::read_to_string(FEEDS_FILE).await else {
+        return;
+    };
+    let watches = serde_json::from_str(&feeds_json).unwrap();
+    *WATCHES.lock().await = watches;
+}
+
+/// Decodes the entities and CDATA a feed's text can contain.
+fn text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(cdata) = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|raw| raw.strip_suffix("]]>"))
+    {
+        return cdata.trim().to_string();
+    }
+    raw.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The contents of the first `<tag>` in `xml`, along with the attributes of its opening tag.
+fn element<'a>(xml: &'a str, tag: &str) -> Option<(&'a str, &'a str)> {
+    let mut rest = xml;
+    loop {
+        let start = rest.find(&format!("<{tag}"))?;
+        let after = &rest[start + tag.len() + 1..];
+        // `<link` shouldn't match `<linkage>`
+        if !after.starts_with([' ', '>', '/', '\t', '\n', '\r']) {
+            rest = after;
+            continue;
+        }
+        let open_end = after.find('>')?;
+        let attributes = &after[..open_end];
+        if attributes.ends_with('/') {
+            return Some((attributes, ""));
+        }
+        let body = &after[open_end + 1..];
+        let end = body.find(&format!("</{tag}>"))?;
+        return Some((attributes, &body[..end]));
+    }
+}
+
+/// Every `<tag>` element's contents, in order.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let mut found = vec![];
+    let mut rest = xml;
+    let close = format!("</{tag}>");
+    while let Some((_, body)) = element(rest, tag) {
+        found.push(body);
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        rest = &rest[end + close.len()..];
+    }
+    found
+}
+
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let start = attributes.find(&format!("{name}=\""))? + name.len() + 2;
+    let end = attributes[start..].find('"')?;
+    Some(&attributes[start..start + end])
+}
+
+/// Reads RSS `<item>`s or Atom `<entry>`s, newest first as feeds list them.
+fn parse(xml: &str) -> Option<Vec<Item>> {
+    let (items, atom) = match elements(xml, "item") {
+        items if !items.is_empty() => (items, false),
+        _ => (elements(xml, "entry"), true),
+    };
+    if items.is_empty() && !xml.contains("<rss") && !xml.contains("<feed") {
+        return None;
+    }
+    Some(
+        items
+            .into_iter()
+            .map(|item| {
+                let title = element(item, "title")
+                    .map(|(_, title)| text(title))
+                    .unwrap_or_default();
+                let link = match element(item, "link") {
+                    Some((attributes, _)) if atom => {
+                        attribute(attributes, "href").map(text).unwrap_or_default()
+                    }
+                    Some((_, link)) => text(link),
+                    None => String::new(),
+                };
+                let id = element(item, if atom { "id" } else { "guid" })
+                    .map(|(_, id)| text(id))
+                    .filter(|id| !id.is_empty())
+                    .unwrap_or_else(|| format!("{link}{title}"));
+                Item { id, title, link }
+            })
+            .collect(),
+    )
+}
+
+async fn fetch(url: &str) -> Result<Vec<Item>, String> {
+    let xml = CLIENT
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?
+        .text()
+        .await
+        .map_err(|err| err.to_string())?;
+    parse(&xml).ok_or_else(|| "that isn't an RSS or Atom feed".into())
+}
+
+fn matches(filter: &Option<String>, item: &Item) -> bool {
+    filter
+        .as_ref()
+        .is_none_or(|filter| item.title.to_lowercase().contains(&filter.to_lowercase()))
+}
+
+pub async fn handle(user: UserId, command: FeedCommand) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let response = match command {
+        FeedCommand::List => {
+            let watches = WATCHES.lock().await;
+            let Some(mine) = watches.get(&user).filter(|mine| !mine.is_empty()) else {
+                return Ok("You aren't watching any feeds, add one with `$watch <url>`".into());
+            };
+            let lines: Vec<_> = mine
+                .iter()
+                .enumerate()
+                .map(|(id, watch)| match &watch.filter {
+                    Some(filter) => format!("{id}: <{}> matching '{filter}'", watch.url),
+                    None => format!("{id}: <{}>", watch.url),
+                })
+                .collect();
+            return Ok(format!("Feeds you're watching:\n{}", lines.join("\n")).into());
+        }
+        FeedCommand::Watch(url, filter) => {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(InvalidFeed(url, "it must be an http or https URL".into()));
+            }
+            if WATCHES.lock().await.get(&user).map_or(0, Vec::len) >= MAX_WATCHES {
+                return Err(TooManyWatches(MAX_WATCHES));
+            }
+            // Items already in the feed count as seen, only later ones are sent
+            let items = fetch(&url)
+                .await
+                .map_err(|err| InvalidFeed(url.clone(), err))?;
+            let response = match &filter {
+                Some(filter) => format!("Watching <{url}> for new items matching '{filter}'"),
+                None => format!("Watching <{url}> for new items"),
+            };
+            let watch = Watch {
+                url,
+                filter,
+                seen: items.into_iter().map(|item| item.id).collect(),
+            };
+            WATCHES.lock().await.entry(user).or_default().push(watch);
+            response
+        }
+        FeedCommand::Unwatch(id) => {
+            let mut watches = WATCHES.lock().await;
+            let mine = watches.entry(user).or_default();
+            if mine.len() <= id as usize {
+                return Err(InvalidID(id));
+            }
+            let watch = mine.remove(id as usize);
+            format!("Stopped watching <{}>", watch.url)
+        }
+    };
+    save();
+    Ok(response.into())
+}
+
+/// Sends users the items that appeared in their feeds since the last poll.
+pub async fn poll(http: &Http) {
+    let targets: Vec<_> = WATCHES
+        .lock()
+        .await
+        .iter()
+        .flat_map(|(user, mine)| mine.iter().map(|watch| (*user, watch.url.clone())))
+        .collect();
+    for (user, url) in targets {
+        let items = match fetch(&url).await {
+            Ok(items) => items,
+            Err(err) => {
+                eprintln!("Failed to fetch feed {url}: {err}");
+                continue;
+            }
+        };
+        let mut watches = WATCHES.lock().await;
+        let Some(watch) = watches
+            .get_mut(&user)
+            .and_then(|mine| mine.iter_mut().find(|watch| watch.url == url))
+        else {
+            continue;
+        };
+        let new: Vec<_> = items
+            .iter()
+            .filter(|item| !watch.seen.contains(&item.id) && matches(&watch.filter, item))
+            .take(MAX_NEW_ITEMS)
+            .collect();
+        watch.seen = items.iter().map(|item| item.id.clone()).collect();
+        drop(watches);
+        for item in new {
+            let content = format!("New in <{url}>: {}\n{}", item.title, item.link);
+            let message = CreateMessage::new().content(&content);
+            let result = delivery::send(http, Recipient::User(user), message).await;
+            let status = match result {
+                Ok(_) => DeliveryStatus::Delivered,
+                Err(_) => DeliveryStatus::Failed,
+            };
+            delivery::record(user, &content, &result, status).await;
+            log_error(result);
+        }
+    }
+    save();
+}
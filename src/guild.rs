@@ -0,0 +1,61 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, GuildId, Permissions, UserId};
+use tokio::sync::RwLock;
+
+use crate::Preferences;
+
+pub const GUILD_SETTINGS_FILE: &str = "guilds.json";
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub defaults: Preferences,
+    /// Channels where "remind me to ..." without the prefix gets a button to schedule it
+    pub listening: Vec<ChannelId>,
+}
+
+pub static GUILD_SETTINGS: LazyLock<RwLock<HashMap<GuildId, GuildSettings>>> =
+    LazyLock::new(Default::default);
+
+pub async fn get_guild_settings(guild: GuildId) -> GuildSettings {
+    GUILD_SETTINGS
+        .read()
+        .await
+        .get(&guild)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub async fn set_guild_settings(guild: GuildId, cb: impl FnOnce(&mut GuildSettings)) {
+    let mut map = GUILD_SETTINGS.write().await;
+    cb(map.entry(guild).or_default());
+    crate::save();
+}
+
+pub async fn load_guild_settings() {
+    let Ok(settings_json) = tokio::fs::read_to_string(GUILD_SETTINGS_FILE).await else {
+        return;
+    };
+    let settings = serde_json::from_str(&settings_json).unwrap();
+    *GUILD_SETTINGS.write().await = settings;
+}
+
+pub async fn channel_permissions(
+    ctx: &Context,
+    channel: ChannelId,
+    user: UserId,
+) -> serenity::Result<Permissions> {
+    let Some(channel) = channel.to_channel(ctx).await?.guild() else {
+        return Ok(Permissions::empty());
+    };
+    let guild = channel.guild_id.to_partial_guild(ctx).await?;
+    let member = guild.member(ctx, user).await?;
+    Ok(guild.user_permissions_in(&channel, &member))
+}
+
+pub async fn channel_guild(ctx: &Context, channel: ChannelId) -> Option<GuildId> {
+    let channel = channel.to_channel(ctx).await.ok()?.guild()?;
+    Some(channel.guild_id)
+}
@@ -0,0 +1,77 @@
+//! `$habit`, repeating reminders whose streaks are shown in `$rs`, with a nudge shortly before
+//! the next occurrence when the current one still isn't done and a streak is on the line.
+
+use jiff::{SignedDuration, Timestamp};
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateMessage, Http};
+
+use crate::{
+    delivery::{self, Recipient},
+    format_time, get_preferences, log_error,
+    stats::{self, Streak, STATS},
+    REMINDERS,
+};
+
+/// How long before the next occurrence the nudge goes out, or half the time between them when
+/// that's shorter.
+const NUDGE_LEAD: SignedDuration = SignedDuration::from_hours(1);
+
+pub fn describe(streak: Option<&Streak>) -> String {
+    let Some(streak) = streak else {
+        return "no streak yet".into();
+    };
+    let mut text = format!("{} in a row, best {}", streak.current, streak.best);
+    if streak.misses > 0 {
+        text.push_str(&format!(", {} missed", streak.misses));
+    }
+    text
+}
+
+/// Nudges everyone whose habit streak breaks at the next occurrence unless they mark it done.
+pub async fn process_nudges(http: &Http) {
+    let now = Timestamp::now();
+    let mut due = vec![];
+    {
+        let cache = REMINDERS.lock().await;
+        let stats = STATS.read().await;
+        for (user, reminders) in cache.iter() {
+            let Some(stats) = stats.get(user) else {
+                continue;
+            };
+            for reminder in reminders.iter().filter(|r| r.habit && !r.paused) {
+                let Some(streak) = stats.streaks.get(&reminder.key) else {
+                    continue;
+                };
+                let Some(opened) = streak.opened else {
+                    continue;
+                };
+                if !streak.open || streak.nudged || streak.current == 0 {
+                    continue;
+                }
+                let next = reminder.time.timestamp();
+                let lead = NUDGE_LEAD.min(opened.duration_until(next) / 2);
+                if now.duration_until(next) <= lead {
+                    due.push((*user, reminder.clone(), streak.current));
+                }
+            }
+        }
+    }
+    for (user, reminder, current) in due {
+        stats::record(user, |stats| {
+            stats.streaks.entry(reminder.key).or_default().nudged = true;
+        })
+        .await;
+        let preferences = get_preferences(user, None).await;
+        let button = CreateButton::new(format!("reminder:done:{user}:{}", reminder.key))
+            .label("Done")
+            .style(ButtonStyle::Success);
+        let message = CreateMessage::new()
+            .content(format!(
+                "Your streak of {current} for '{}' ends when it comes round again at {}, \
+                mark it done to keep it going",
+                &reminder.message,
+                format_time(&reminder.time, preferences.time_format())
+            ))
+            .components(vec![CreateActionRow::Buttons(vec![button])]);
+        log_error(delivery::send(http, Recipient::User(user), message).await);
+    }
+}
@@ -0,0 +1,849 @@
+struct Topic {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    commands: &'static [(&'static str, &'static str)],
+    examples: &'static [(&'static str, &'static str)],
+    pitfalls: &'static [&'static str],
+}
+
+const TOPICS: &[Topic] = &[
+    Topic {
+        name: "time",
+        aliases: &["times", "modifiers", "modifier"],
+        commands: &[],
+        examples: &[
+            ("1d", "1 day from now"),
+            ("1w1h5m3s", "1 week, 1 hour, 5 minutes, 1 second from now"),
+            ("3pm", "3:00 PM"),
+            ("3:30pm", "3:30 PM"),
+            ("21:00", "9:00 PM"),
+            ("2001-03-06", "March 6th, 2001"),
+            ("--04", "4th day of the current month"),
+            ("-03-04", "March 4th of the current year"),
+            ("1mo", "1 month"),
+            ("1y", "1 year"),
+            ("tuesday", "Tuesday"),
+            ("1w tuesday", "The next Tuesday in 1 week"),
+            ("1d (2pm, 4pm)", "2pm and 4pm tomorrow"),
+            ("w23 monday 10am", "10:00 AM on Monday of ISO week 23"),
+            ("q3 9am", "9:00 AM on the first day of the next Q3"),
+            ("end of quarter", "The last day of the current quarter"),
+            ("next quarter", "The first day of the next quarter"),
+            (
+                "next month 9am",
+                "9:00 AM on the first day of next month, `$prefs next same` keeps today's day",
+            ),
+            ("next year", "January 1st next year"),
+            ("sunset", "Sunset today where you are, set with `$location`"),
+            ("friday 5pm -30m", "30 minutes before 5pm on Friday"),
+            ("9am -commute", "Your commute offset before 9am, set with `$offset`"),
+        ],
+        pitfalls: &[
+            "Modifiers are applied left to right, so `1d 3pm` is 3pm tomorrow",
+            "A time of day that has already passed today is not moved to tomorrow; add `1d`",
+            "On a Tuesday, `tuesday 9am` could be today or next week, so you're asked which you meant until you pick one for good with `$weekdays smart` or `$weekdays next`",
+            "Dates like `6/3` are read as month/day or day/month, and when both make sense you're asked which you meant",
+            "Times are interpreted in your timezone, set it with `$tz`",
+            "A date and a weekday together must agree, and only one time of day can be given outside `(a, b)`",
+            "Subtractions like `-30m` always apply last, so `-30m friday 5pm` is still 4:30pm on Friday",
+        ],
+    },
+    Topic {
+        name: "remind",
+        aliases: &["r", "remindme", "reminder", "schedule", "emphasis"],
+        commands: &[
+            ("$r|remindme|reminder <modifiers> [if|unless <condition>]; message", "Schedule a reminder"),
+            (
+                "$r after #<id> <delay>; message",
+                "Schedule a reminder a fixed time after another one",
+            ),
+            (
+                "$r nl <phrase>; message",
+                "Read the time as natural language, falling back to the usual syntax",
+            ),
+            (
+                "$emphasis <id> quiet|normal|urgent",
+                "Deliver a reminder silently, or mention you, read it aloud and ping twice more until it's done",
+            ),
+        ],
+        examples: &[
+            ("$r 2h; stretch", "Remind you to stretch in 2 hours"),
+            (
+                "$r \"pick up kids\" in 2h",
+                "Remind you to pick up the kids in 2 hours",
+            ),
+            (
+                "$r 7:30am if rain; take the bus",
+                "Only remind you if it's raining where you are at 7:30am",
+            ),
+            (
+                "$r friday 9am; standup notes",
+                "Remind you on Friday at 9am",
+            ),
+            (
+                "$r 1d (9am, 5pm); water plants",
+                "Schedule two reminders tomorrow",
+            ),
+            (
+                "$r after #3 2h; follow up",
+                "Remind you 2 hours after reminder 3, moving with it if it's skipped",
+            ),
+            (
+                "$r nl the day after tomorrow at quarter past nine; dentist",
+                "Remind you at 9:15am in two days, saying how the time was read",
+            ),
+            (
+                "$r saturday; groceries: milk, eggs, bread",
+                "A checklist you can tick off with buttons when it's delivered",
+            ),
+            (
+                "$r 5pm; 🦷 Dentist | bring insurance card",
+                "A reminder titled Dentist, marked with 🦷 in your list",
+            ),
+        ],
+        pitfalls: &[
+            "The message goes after a `;`, or in quotes before or after the time like `$r 5pm \"pick up kids\"` or `$r \"pick up kids\" in 2h`",
+            "Several commands can be sent in one message, each on its own line starting with `$`",
+            "A date on its own keeps the current time of day, so `$r 2020-01-01; x` is rejected as already passed",
+            "A reminder scheduled after another one is only sent once, unless it's given its own interval with `$si`",
+            "Text before a `|` becomes the reminder's title, and an emoji at the very start is shown next to it in `$rs`",
+            "Conditions are checked when the reminder fires and it's sent anyway if checking fails: `if rain|snow|storm|clear` uses your `$location`, `if between 9am and 5pm`, `if up <url>` and `if online` also work, as does `unless`",
+        ],
+    },
+    Topic {
+        name: "say",
+        aliases: &[],
+        commands: &[
+            (
+            "$say <#channel> <modifiers>; message",
+            "Post a message in a channel at a scheduled time",
+        ),
+        (
+            "$callout <id> <@role>|off",
+            "Ping a role when a scheduled channel message is posted",
+        ),
+        ],
+        examples: &[
+            (
+                "$say #general friday 6pm; Game night starts now!",
+                "Announce game night on Friday",
+            ),
+            (
+                "$say #raid 8:55pm; !tts Raid in 5 minutes",
+                "Have the reminder read aloud in the channel",
+            ),
+            (
+                "$callout 0 @raiders",
+                "Ping the raiders role when message #0 is posted",
+            ),
+        ],
+        pitfalls: &[
+            "You need permission to send messages in the channel",
+            "Voice channels work too, the message goes to their text chat",
+            "`$callout` needs the role to be mentionable by everyone, or the Mention All Roles permission for both you and the bot",
+            "`!tts` anywhere in the message reads it aloud, it needs the Send Text-to-Speech Messages permission for both you and the bot",
+            "Scheduled messages show up in `$rs` and are cancelled with `$cr` like reminders",
+        ],
+    },
+    Topic {
+        name: "announce",
+        aliases: &["an", "announcements", "announcement"],
+        commands: &[
+            (
+                "$announce add <#channel> <modifiers>; <interval>; message",
+                "Set up a recurring channel post (Manage Messages)",
+            ),
+            (
+                "$announce list|cancel <id>",
+                "List or cancel the server's recurring posts",
+            ),
+        ],
+        examples: &[(
+            "$announce add #events friday 7pm; 1w; Event night starts now!",
+            "Post every Friday at 7pm",
+        )],
+        pitfalls: &[
+            "Announcements belong to the server, not to you, and don't show up in `$rs`",
+            "Times use the timezone of whoever sets them up",
+        ],
+    },
+    Topic {
+        name: "list",
+        aliases: &["rs", "reminders", "nextup", "review"],
+        commands: &[
+            ("$rs|reminders", "List reminders"),
+            (
+                "$nextup on|off",
+                "Pin a DM listing your next 5 reminders, kept up to date as they change",
+            ),
+            (
+                "$review on|off",
+                "Get a DM every Sunday at 6pm with last week's reminders and next week's",
+            ),
+            (
+                "$rs sort:<time|created|alpha> group:<day|tag>",
+                "Sort or group the list",
+            ),
+        ],
+        examples: &[
+            ("$rs sort:alpha", "List reminders alphabetically"),
+            ("$rs group:tag", "Group reminders by the #tags in their messages"),
+        ],
+        pitfalls: &[
+            "Reminder IDs are positions in this list, so they change as reminders fire",
+            "IDs stay the same when sorting differently, use them as shown",
+        ],
+    },
+    Topic {
+        name: "cancel",
+        aliases: &["cr", "cancelreminder", "done"],
+        commands: &[
+            ("$cr|cancelreminder <id>", "Cancel a reminder"),
+            (
+                "$done <id>",
+                "Mark a reminder done, like its Done button, counting towards its streak if it repeats",
+            ),
+        ],
+        examples: &[
+            ("$cr 2", "Cancel reminder #2 from `$rs`"),
+            ("$done 2", "Mark reminder #2 done"),
+        ],
+        pitfalls: &[
+            "Check `$rs` first, since IDs shift when reminders fire or are cancelled",
+            "A one-off reminder marked done before it fires won't be sent",
+            "A repeating reminder's streak ends when it fires again before being marked done",
+        ],
+    },
+    Topic {
+        name: "shared",
+        aliases: &["team", "lists"],
+        commands: &[
+            ("$list [name]", "Show your shared lists, or one list"),
+            (
+                "$list create|delete|leave <name>",
+                "Manage shared reminder lists",
+            ),
+            (
+                "$list add|remove <name> @user",
+                "Invite or remove subscribers (owner only)",
+            ),
+            (
+                "$list accept|decline <name>",
+                "Join a list you were invited to, or turn it down",
+            ),
+            (
+                "$r list:<name> <modifiers>; message",
+                "Remind everyone on a shared list",
+            ),
+            (
+                "$list cancel|repeat <name> <id> [modifiers]",
+                "Cancel or repeat a shared reminder you scheduled, the owner can change any",
+            ),
+            (
+                "$list jitter <name> <id> <seconds>|reset",
+                "Spread a shared reminder's DMs over a window (owner only)",
+            ),
+        ],
+        examples: &[
+            ("$list create ops", "Create the ops list"),
+            (
+                "$r list:ops friday 4pm; rotate keys",
+                "Remind the whole ops list",
+            ),
+            ("$list repeat ops 0 1w", "Repeat it every week"),
+        ],
+        pitfalls: &[
+            "Every subscriber gets a DM when a shared reminder fires",
+            "People you add are only subscribed once they accept with `$list accept <name>`",
+            "List names only need to be unique among the lists you're on",
+            "Spread out DMs are lost if the bot restarts before they're sent",
+        ],
+    },
+    Topic {
+        name: "seq",
+        aliases: &["sequence", "sequences", "steps"],
+        commands: &[
+            ("$seq", "Show your sequences and the step each is on"),
+            (
+                "$seq create \"<name>\": \"<step>\" <modifiers>, \"<step>\" +<delay>, ...",
+                "Create a sequence of steps that follow each other",
+            ),
+            (
+                "$seq done <name>",
+                "Mark the current step finished and schedule the next",
+            ),
+            ("$seq cancel <name>", "Stop a sequence"),
+        ],
+        examples: &[
+            (
+                "$seq create \"release\": \"freeze\" friday 9am, \"tag\" +1d, \"announce\" +2d",
+                "Freeze on Friday, tag a day after that's done and announce two days after tagging",
+            ),
+            ("$seq done release", "Finish the freeze and schedule the tag"),
+        ],
+        pitfalls: &[
+            "The next step isn't scheduled until the current one is marked done, its delay counts from then",
+        ],
+    },
+    Topic {
+        name: "watch",
+        aliases: &["watches", "unwatch", "feeds", "rss"],
+        commands: &[
+            ("$watch <feed url> [filter]", "Get a DM when an RSS or Atom feed has new items"),
+            ("$watches", "Show the feeds you're watching"),
+            ("$unwatch <id>", "Stop watching a feed"),
+        ],
+        examples: &[(
+            "$watch https://blog.rust-lang.org/feed.xml release",
+            "Get new Rust blog posts with 'release' in the title",
+        )],
+        pitfalls: &[
+            "Feeds are checked every 15 minutes, items already there when you start watching aren't sent",
+        ],
+    },
+    Topic {
+        name: "when",
+        aliases: &["triggers", "trigger", "voice", "mentions", "keyword"],
+        commands: &[
+            (
+                "$r when @user joins voice; <message>",
+                "Get reminded the next time someone joins a voice channel",
+            ),
+            (
+                "$r when #channel mentions \"<keyword>\"; <message>",
+                "Get reminded whenever a message in a channel contains a keyword",
+            ),
+            ("$triggers", "Show your trigger reminders"),
+            ("$triggers cancel <id>", "Cancel a trigger reminder"),
+        ],
+        examples: &[
+            (
+                "$r when @sam joins voice; ask about the raid",
+                "A DM the next time sam joins voice in this server",
+            ),
+            (
+                "$r when #ops mentions \"deploy\"; check the pipeline",
+                "A DM linking each message in #ops that mentions deploy",
+            ),
+        ],
+        pitfalls: &[
+            "Trigger reminders only work in servers, and only for events in the server you set them in",
+            "Voice triggers fire once and are then removed, keyword triggers stay until you cancel them",
+            "A keyword trigger notifies you at most once every 10 minutes, and never for your own messages",
+        ],
+    },
+    Topic {
+        name: "bookmark",
+        aliases: &["bookmarks"],
+        commands: &[
+            (
+                "$bookmark [note]",
+                "Reply to a message with this to save a link to it",
+            ),
+            ("$bookmark <message link> [note]", "Save a link to a message"),
+            ("$bookmarks", "Show your bookmarks"),
+            ("$bookmarks remind <id> <time>", "Turn a bookmark into a reminder"),
+            ("$bookmarks remove <id>", "Remove a bookmark"),
+        ],
+        examples: &[(
+            "$bookmarks remind 2 friday 6pm",
+            "Get reminded of bookmark #2 on Friday evening",
+        )],
+        pitfalls: &[
+            "A bookmark is removed once it's turned into a reminder",
+            "If the bot has reactions turned on, reacting to a message with ⏰ reminds you about it in an hour",
+        ],
+    },
+    Topic {
+        name: "tasks",
+        aliases: &["push", "todoist", "notion"],
+        commands: &[
+            ("$tasks", "Show your linked task apps"),
+            ("$tasks todoist <token>", "Link Todoist with an API token, in a DM"),
+            (
+                "$tasks notion <token> <database id>",
+                "Link a Notion database with an integration token, in a DM",
+            ),
+            ("$tasks todoist|notion remove", "Unlink a task app"),
+            ("$push <id> todoist|notion", "Add a reminder to a task app as a task"),
+        ],
+        examples: &[("$push 3 todoist", "Add reminder #3 to Todoist")],
+        pitfalls: &[
+            "Tasks added after linking become reminders within a few minutes, existing ones are left alone",
+            "Tasks due on a date without a time are reminded at 9am",
+            "Notion tasks need a title and a date property in the database",
+        ],
+    },
+    Topic {
+        name: "cal",
+        aliases: &["calendar", "google"],
+        commands: &[
+            ("$cal link", "Link your Google Calendar, if the bot has it set up"),
+            ("$cal code <address>", "Finish linking, in a DM"),
+            ("$cal <id> on|off", "Keep a reminder as an event in your calendar"),
+            ("$cal unlink", "Stop syncing with your calendar"),
+        ],
+        examples: &[("$cal 2 on", "Add reminder #2 to your calendar")],
+        pitfalls: &[
+            "Syncing runs every few minutes, so new events and deletions aren't instant",
+            "Deleting the event in your calendar cancels the reminder, a repeating one included",
+        ],
+    },
+    Topic {
+        name: "transfer",
+        aliases: &["handoff"],
+        commands: &[(
+            "$transfer <id> @user",
+            "Hand a reminder over to someone else",
+        )],
+        examples: &[(
+            "$transfer 3 @alex",
+            "Offer reminder #3 to alex, who gets an Accept button in DMs",
+        )],
+        pitfalls: &[
+            "The reminder stays yours until they accept",
+            "Offers expire if the bot restarts before they're answered",
+        ],
+    },
+    Topic {
+        name: "follow",
+        aliases: &["public", "unfollow", "jitter"],
+        commands: &[
+            (
+                "$public <id> on|off",
+                "Let others follow one of your reminders",
+            ),
+            (
+                "$follow @user <id>",
+                "Also get notified when someone's public reminder fires",
+            ),
+            ("$unfollow @user <id>", "Stop following a reminder"),
+            (
+                "$jitter <id> <seconds>|<minutes>m|reset",
+                "Spread the copies sent to followers over a window",
+            ),
+        ],
+        examples: &[
+            ("$public 2 on", "Make your reminder #2 followable"),
+            ("$follow @alex 2", "Follow alex's reminder #2"),
+            ("$jitter 2 5m", "Send followers their copies over 5 minutes"),
+        ],
+        pitfalls: &[
+            "IDs are the organizer's, ask them for the number shown in their `$rs`",
+            "Making a reminder private again removes its followers",
+        ],
+    },
+    Topic {
+        name: "interval",
+        aliases: &[
+            "intervals",
+            "si",
+            "setinterval",
+            "ci",
+            "clearinterval",
+            "repeat",
+            "skip",
+            "simulate",
+            "habit",
+        ],
+        commands: &[
+            (
+                "$si|setinterval <id> <modifiers>",
+                "Preview the next occurrences of an interval, then save it with the button",
+            ),
+            (
+                "$si|setinterval <id> cron <expression>",
+                "Repeat a reminder on a 5-field cron schedule",
+            ),
+            (
+                "$si <id> +<modifiers> | remove <kind>",
+                "Add to or remove from an existing interval",
+            ),
+            ("$ci|clearinterval <id>", "Clear the interval of a reminder"),
+            (
+                "$skip <id>",
+                "Skip the next occurrence of a repeating reminder",
+            ),
+            (
+                "$note <id> <text>",
+                "Add a note to a reminder, shown in `$rs` and when it's delivered",
+            ),
+            (
+                "$habit <id> on|off",
+                "Track a repeating reminder as a habit, with its streak in `$rs` and a nudge before it breaks",
+            ),
+            (
+                "$simulate <id> <count>",
+                "List upcoming occurrences to check an interval",
+            ),
+        ],
+        examples: &[
+            ("$si 0 1d", "Repeat reminder #0 every day"),
+            ("$si 0 1w", "Repeat reminder #0 every week"),
+            (
+                "$si 0 1mo --01 9am",
+                "Repeat on the 1st of every month at 9am",
+            ),
+            ("$si 0 cron 0 9 * * MON-FRI", "Repeat at 9am on weekdays"),
+            (
+                "$si 0 +9am",
+                "Also set the time of day on reminder #0's interval",
+            ),
+            (
+                "$si 0 remove weekday",
+                "Remove the weekday from reminder #0's interval",
+            ),
+            (
+                "$si 0 rrule FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "Repeat on the last weekday of every month",
+            ),
+            (
+                "$skip 0",
+                "Don't send reminder #0 this time, but keep repeating",
+            ),
+        ],
+        pitfalls: &[
+            "The interval is added to the reminder's scheduled time each time it fires",
+            "`$si <id> <modifiers>` only saves once you press Save interval under the preview",
+            "Interval modifiers cannot use the `(a, b)` permutation syntax",
+            "Removable kinds are delay, weekday, time, date, months, years and week",
+            "Repeating reminders come with a Manage button to skip, pause, move or stop them",
+            "Habit streaks count occurrences marked done in a row, the nudge comes an hour before the next one at most",
+            "Cron fields are minute, hour, day of month, month and weekday, in your timezone",
+            "RRULEs (RFC 5545) are supported except for COUNT, BYSECOND, BYWEEKNO and BYYEARDAY",
+            "Monthly repeats from the 31st fall on the last day of shorter months, then go back to the 31st",
+        ],
+    },
+    Topic {
+        name: "holidays",
+        aliases: &["holiday", "sh", "skipholidays"],
+        commands: &[
+            (
+                "$holidays [<country>|off]",
+                "Show or set your holiday calendar",
+            ),
+            (
+                "$holidays add|remove <yyyy-mm-dd>",
+                "Add or remove a custom holiday",
+            ),
+            (
+                "$sh|skipholidays <id> <on|off|roll>",
+                "Skip occurrences of a repeating reminder that fall on holidays, or move them to the next working day",
+            ),
+        ],
+        examples: &[
+            ("$holidays GB", "Use UK bank holidays"),
+            (
+                "$holidays add 2026-12-24",
+                "Treat Christmas Eve as a holiday",
+            ),
+            ("$sh 0 on", "Don't send reminder #0 on holidays"),
+            (
+                "$sh 0 roll",
+                "Send reminder #0 the day after a holiday instead",
+            ),
+        ],
+        pitfalls: &[
+            "With `on` a holiday occurrence moves to the next interval step, `roll` sends it on the next working day and then carries on with the usual schedule",
+            "Available calendars: US, GB, CA, DE, FR, AU",
+        ],
+    },
+    Topic {
+        name: "timezone",
+        aliases: &["tz", "timezones"],
+        commands: &[(
+            "$tz|timezone <timezone>",
+            "Set your timezone (e.g. Europe/London, EST, UTC+5:30)",
+        )],
+        examples: &[
+            (
+                "$tz Europe/London",
+                "Use the UK timezone, including daylight saving",
+            ),
+            ("$tz PT", "Use US Pacific time"),
+            ("$tz UTC+5:30", "Use a fixed offset from UTC"),
+        ],
+        pitfalls: &[
+            "Fixed offsets don't follow daylight saving, prefer a zone name where possible",
+            "Reminders keep the timezone they were scheduled in",
+        ],
+    },
+    Topic {
+        name: "timeformat",
+        aliases: &["tf"],
+        commands: &[("$tf|timeformat <12h|24h>", "Set your preferred time format")],
+        examples: &[("$tf 24h", "Show times like 21:00")],
+        pitfalls: &[],
+    },
+    Topic {
+        name: "prefs",
+        aliases: &["preferences", "settings", "weekdays", "liststyle", "fiscal", "location", "offset", "offsets", "telegram", "matrix", "delivery", "expire", "template", "horizon", "language"],
+        commands: &[
+            (
+                "$prefs",
+                "Show your preferences, with buttons for the timezone, time format, weekdays, list style, natural language and language",
+            ),
+            (
+                "$weekdays smart|next",
+                "Choose whether a weekday naming today can mean today",
+            ),
+            (
+                "$liststyle compact|verbose",
+                "Choose how much detail `$rs` shows",
+            ),
+            (
+                "$fiscal <month>",
+                "Set the month (1-12) your fiscal year starts in, used by `q1`-`q4`",
+            ),
+            (
+                "$location <latitude> <longitude>",
+                "Set where you are for `sunrise` and `sunset`, `$location clear` removes it",
+            ),
+            (
+                "$offset set|remove <name> [duration]",
+                "Name a duration to add or subtract in times, `$offsets` lists them",
+            ),
+            (
+                "$delivery online [max hold]|now",
+                "Hold reminders that fire while you're offline until you come online, 8h at most by default",
+            ),
+            (
+                "$expire <duration>|off",
+                "Delete delivered reminder DMs after a while, from 1 minute up to 30 days",
+            ),
+            (
+                "$expire <id> <duration>|keep|default",
+                "Set how long one reminder's deliveries are kept, overriding the above",
+            ),
+            (
+                "$prefs language en|fr|es|de",
+                "Choose the language durations like \"in 2 days 3 hours\" are written in",
+            ),
+            (
+                "$prefs next first|same",
+                "Choose whether `next month` and `next year` land on the first day or today's day",
+            ),
+            (
+                "$prefs horizon <years>|reset",
+                "Change how far ahead reminders can be scheduled, 10 years unless set",
+            ),
+            (
+                "$prefs natural on|off",
+                "Read every `$r` time as natural language first, like `$r nl`",
+            ),
+            (
+                "$prefs template \"<template>\"|clear",
+                "Lay out delivered reminders with {message}, {time}, {created}, {created_rel} and {tags}",
+            ),
+            (
+                "$telegram link|unlink",
+                "Also get your reminders in Telegram, if the bot has it set up",
+            ),
+            (
+                "$matrix <homeserver> <room id> <access token>",
+                "Also post your reminders to a Matrix room, in DMs only, `$matrix clear` stops it",
+            ),
+        ],
+        examples: &[
+            (
+                "$prefs template \"⏰ {message} (set {created_rel} ago)\"",
+                "⏰ stretch (set 2 hours ago)",
+            ),
+            ("$expire 1h", "Delete reminder DMs an hour after they arrive"),
+            ("$expire 3 keep", "Keep reminder #3's DMs anyway"),
+        ],
+        pitfalls: &[
+            "Templates only apply to your own reminders, shared lists and channel messages are sent as usual",
+            "New users are DMed a short setup for timezone and time format after their first command",
+            "`$expire` only deletes DMs, reminders sent to channels are kept",
+            "Settings that take a value, like the template or horizon, only change through their commands",
+        ],
+    },
+    Topic {
+        name: "dashboard",
+        aliases: &["web"],
+        commands: &[(
+            "$dashboard",
+            "Get the address of a page where you can see and change your reminders on a calendar",
+        )],
+        examples: &[],
+        pitfalls: &[
+            "You sign in there with Discord, and only ever see your own reminders",
+            "Reminders scheduled there don't repeat, set an interval with `$si` afterwards",
+        ],
+    },
+    Topic {
+        name: "server",
+        aliases: &["sv", "guild"],
+        commands: &[
+            (
+                "$server tz <timezone>",
+                "Set the server's default timezone (Manage Server)",
+            ),
+            (
+                "$server tf <12h|24h>",
+                "Set the server's default time format (Manage Server)",
+            ),
+            (
+                "$server listen #channel on|off",
+                "Offer a button to schedule \"remind me to ...\" messages in a channel (Manage Server)",
+            ),
+        ],
+        examples: &[("$server tz Europe/Berlin", "Default members to Berlin time")],
+        pitfalls: &[
+            "Server defaults only apply to members who haven't set their own preference",
+            "Listening only picks up a time at the end, like \"remind me to call mom tomorrow at 5pm\"",
+        ],
+    },
+    Topic {
+        name: "stats",
+        aliases: &["statistics"],
+        commands: &[(
+            "$stats",
+            "Show how many reminders you've scheduled, received, snoozed and done, with streaks",
+        )],
+        examples: &[],
+        pitfalls: &["Counts start from when statistics were introduced"],
+    },
+    Topic {
+        name: "history",
+        aliases: &["deliveries"],
+        commands: &[(
+            "$history",
+            "Show whether your recent reminders were delivered",
+        )],
+        examples: &[],
+        pitfalls: &[
+            "Failed deliveries are retried a few times before giving up",
+            "Reminders stay pending in `$rs` until they fire",
+        ],
+    },
+    Topic {
+        name: "audit",
+        aliases: &["log"],
+        commands: &[
+            ("$audit", "Show recent changes to your reminders"),
+            (
+                "$audit <id>",
+                "Show everything that happened to one reminder",
+            ),
+        ],
+        examples: &[(
+            "$audit 2",
+            "See when reminder #2 was created, edited and fired",
+        )],
+        pitfalls: &["Only the most recent changes are kept"],
+    },
+    Topic {
+        name: "about",
+        aliases: &["version"],
+        commands: &[("$about", "Show the bot version, uptime and source link")],
+        examples: &[],
+        pitfalls: &[],
+    },
+    Topic {
+        name: "import",
+        aliases: &["csv", "export"],
+        commands: &[
+            (
+                "$import csv",
+                "Import reminders from a CSV file attached to the message",
+            ),
+            (
+                "$export csv",
+                "Get your reminders as a CSV file for spreadsheets",
+            ),
+        ],
+        examples: &[(
+            "$import csv",
+            "With a file like `time,message,repeat` / `2026-11-02 09:00,standup,weekly`",
+        )],
+        pitfalls: &[
+            "Nothing is scheduled until you press Import on the summary",
+            "Times without an offset are read in your timezone, or a `timezone` column if the file has one",
+            "Repeats can be `daily`, `weekly`, `monthly`, `yearly`, anything `$si` accepts, a cron expression or an RRULE",
+            "Repeating reminders whose first time has passed start from their next occurrence, one-off ones are skipped",
+        ],
+    },
+    Topic {
+        name: "ping",
+        aliases: &["status", "latency"],
+        commands: &[(
+            "$ping",
+            "Show gateway latency, when the scheduler last ran and how many deliveries are waiting",
+        )],
+        examples: &[],
+        pitfalls: &[
+            "If the scheduler is behind, reminders will be late but still arrive",
+            "Due deliveries count everyone's reminders, not just yours",
+        ],
+    },
+    Topic {
+        name: "help",
+        aliases: &["h"],
+        commands: &[("$h|help [topic]", "Show help, optionally for a topic")],
+        examples: &[("$help interval", "Show help for intervals")],
+        pitfalls: &[],
+    },
+];
+
+pub fn topic_names() -> String {
+    TOPICS
+        .iter()
+        .map(|topic| topic.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn overview() -> String {
+    let mut lines = vec!["Time modifier examples:".to_string()];
+    for topic in TOPICS.iter().filter(|topic| topic.commands.is_empty()) {
+        for (example, description) in topic.examples {
+            lines.push(format!("{example} - {description}"));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push("Commands:".into());
+    // Only the main command of each topic, the rest are listed by `$help <topic>`
+    for topic in TOPICS {
+        if let Some((usage, description)) = topic.commands.first() {
+            lines.push(format!("`{usage}` - {description}"));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Use `$help <topic>` for details on: {}",
+        topic_names()
+    ));
+    lines.join("\n")
+}
+
+pub fn topic(name: &str) -> Option<String> {
+    let name = name.trim().trim_start_matches('$').to_lowercase();
+    let topic = TOPICS
+        .iter()
+        .find(|topic| topic.name == name || topic.aliases.contains(&name.as_str()))?;
+
+    let mut lines = vec![];
+    if !topic.commands.is_empty() {
+        lines.push("Usage:".to_string());
+        for (usage, description) in topic.commands {
+            lines.push(format!("`{usage}` - {description}"));
+        }
+    }
+    if !topic.examples.is_empty() {
+        lines.push("Examples:".into());
+        for (example, description) in topic.examples {
+            lines.push(format!("`{example}` - {description}"));
+        }
+    }
+    if !topic.pitfalls.is_empty() {
+        lines.push("Watch out for:".into());
+        for pitfall in topic.pitfalls {
+            lines.push(format!("- {pitfall}"));
+        }
+    }
+    Some(lines.join("\n"))
+}
@@ -0,0 +1,295 @@
+use jiff::{
+    civil::{date, Date, Weekday},
+    Span, ToSpan, Zoned,
+};
+use serde::{Deserialize, Serialize};
+
+pub const COUNTRIES: &[(&str, &str)] = &[
+    ("US", "United States"),
+    ("GB", "United Kingdom (England and Wales)"),
+    ("CA", "Canada"),
+    ("DE", "Germany"),
+    ("FR", "France"),
+    ("AU", "Australia"),
+];
+
+pub fn country_name(code: &str) -> Option<&'static str> {
+    COUNTRIES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+}
+
+pub fn country_codes() -> String {
+    COUNTRIES
+        .iter()
+        .map(|(code, _)| *code)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How a repeating reminder treats an occurrence that falls on a holiday, set with `$sh`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HolidayMode {
+    Send,
+    /// Moves on to the next occurrence that isn't a holiday
+    Skip,
+    /// Sends it on the next day that isn't a holiday instead
+    Roll,
+}
+
+/// Longest run of holidays an occurrence is rolled over, a full year of custom holidays stops it.
+const MAX_ROLL_DAYS: usize = 366;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HolidayCalendar {
+    pub country: Option<String>,
+    pub custom: Vec<Date>,
+}
+
+impl HolidayCalendar {
+    pub fn is_empty(&self) -> bool {
+        self.country.is_none() && self.custom.is_empty()
+    }
+
+    pub fn is_holiday(&self, day: Date) -> bool {
+        self.holiday(day).is_some()
+    }
+
+    /// The same time on the first day from `time` on that isn't a holiday.
+    pub fn next_working_day(&self, time: &Zoned) -> Result<Zoned, jiff::Error> {
+        let mut time = time.clone();
+        for _ in 0..MAX_ROLL_DAYS {
+            if !self.is_holiday(time.date()) {
+                break;
+            }
+            time = time.checked_add(1.day())?;
+        }
+        Ok(time)
+    }
+
+    pub fn holiday(&self, day: Date) -> Option<&'static str> {
+        if self.custom.contains(&day) {
+            return Some("Custom holiday");
+        }
+        let country = self.country.as_deref()?;
+        // Observed dates can fall into the previous year, e.g. New Year's Day on a Saturday
+        [day.year(), day.year() + 1]
+            .into_iter()
+            .flat_map(|year| national_holidays(country, year))
+            .find(|(date, _)| *date == day)
+            .map(|(_, name)| name)
+    }
+
+    pub fn upcoming(&self, from: Date, count: usize) -> Vec<(Date, &'static str)> {
+        let mut holidays: Vec<_> = self
+            .custom
+            .iter()
+            .map(|day| (*day, "Custom holiday"))
+            .collect();
+        if let Some(country) = &self.country {
+            for year in [from.year(), from.year() + 1] {
+                holidays.extend(national_holidays(country, year));
+            }
+        }
+        holidays.retain(|(day, _)| *day >= from);
+        holidays.sort();
+        holidays.dedup_by_key(|(day, _)| *day);
+        holidays.truncate(count);
+        holidays
+    }
+}
+
+enum Substitute {
+    None,
+    NearestWeekday,
+    NextFreeWeekday,
+}
+
+fn is_weekend(day: Date) -> bool {
+    matches!(day.weekday(), Weekday::Saturday | Weekday::Sunday)
+}
+
+fn add_days(day: Date, days: i64) -> Date {
+    day.checked_add(Span::new().days(days)).unwrap()
+}
+
+fn nth(year: i16, month: i8, nth: i8, weekday: Weekday) -> Date {
+    date(year, month, 1)
+        .nth_weekday_of_month(nth, weekday)
+        .unwrap()
+}
+
+fn easter(year: i16) -> Date {
+    let y = year as i32;
+    let (a, b, c) = (y % 19, y / 100, y % 100);
+    let (d, e) = (b / 4, b % 4);
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let (i, k) = (c / 4, c % 4);
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    date(year, month as i8, day as i8)
+}
+
+fn observe(
+    fixed: &[(i8, i8, &'static str)],
+    year: i16,
+    rule: Substitute,
+) -> Vec<(Date, &'static str)> {
+    let mut observed: Vec<(Date, &'static str)> = vec![];
+    for &(month, day, name) in fixed {
+        let actual = date(year, month, day);
+        let mut substitute = actual;
+        match rule {
+            Substitute::None => {}
+            Substitute::NearestWeekday => match actual.weekday() {
+                Weekday::Saturday => substitute = add_days(actual, -1),
+                Weekday::Sunday => substitute = add_days(actual, 1),
+                _ => {}
+            },
+            Substitute::NextFreeWeekday => {
+                while is_weekend(substitute) || observed.iter().any(|(d, _)| *d == substitute) {
+                    substitute = add_days(substitute, 1);
+                }
+            }
+        }
+        observed.push((actual, name));
+        if substitute != actual {
+            observed.push((substitute, name));
+        }
+    }
+    observed
+}
+
+pub fn national_holidays(country: &str, year: i16) -> Vec<(Date, &'static str)> {
+    let easter = easter(year);
+    let good_friday = (add_days(easter, -2), "Good Friday");
+    let easter_monday = (add_days(easter, 1), "Easter Monday");
+    let ascension = (add_days(easter, 39), "Ascension Day");
+    let whit_monday = (add_days(easter, 50), "Whit Monday");
+
+    let mut holidays = match country {
+        "US" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (6, 19, "Juneteenth"),
+                    (7, 4, "Independence Day"),
+                    (11, 11, "Veterans Day"),
+                    (12, 25, "Christmas Day"),
+                ],
+                year,
+                Substitute::NearestWeekday,
+            );
+            holidays.extend([
+                (
+                    nth(year, 1, 3, Weekday::Monday),
+                    "Martin Luther King Jr. Day",
+                ),
+                (nth(year, 2, 3, Weekday::Monday), "Presidents' Day"),
+                (nth(year, 5, -1, Weekday::Monday), "Memorial Day"),
+                (nth(year, 9, 1, Weekday::Monday), "Labor Day"),
+                (nth(year, 10, 2, Weekday::Monday), "Columbus Day"),
+                (nth(year, 11, 4, Weekday::Thursday), "Thanksgiving"),
+            ]);
+            holidays
+        }
+        "GB" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (12, 25, "Christmas Day"),
+                    (12, 26, "Boxing Day"),
+                ],
+                year,
+                Substitute::NextFreeWeekday,
+            );
+            holidays.extend([
+                good_friday,
+                easter_monday,
+                (nth(year, 5, 1, Weekday::Monday), "Early May bank holiday"),
+                (nth(year, 5, -1, Weekday::Monday), "Spring bank holiday"),
+                (nth(year, 8, -1, Weekday::Monday), "Summer bank holiday"),
+            ]);
+            holidays
+        }
+        "CA" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (7, 1, "Canada Day"),
+                    (12, 25, "Christmas Day"),
+                    (12, 26, "Boxing Day"),
+                ],
+                year,
+                Substitute::NextFreeWeekday,
+            );
+            let mut victoria_day = date(year, 5, 24);
+            while victoria_day.weekday() != Weekday::Monday {
+                victoria_day = add_days(victoria_day, -1);
+            }
+            holidays.extend([
+                good_friday,
+                (victoria_day, "Victoria Day"),
+                (nth(year, 9, 1, Weekday::Monday), "Labour Day"),
+                (nth(year, 10, 2, Weekday::Monday), "Thanksgiving"),
+            ]);
+            holidays
+        }
+        "DE" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (5, 1, "Labour Day"),
+                    (10, 3, "German Unity Day"),
+                    (12, 25, "Christmas Day"),
+                    (12, 26, "Second Day of Christmas"),
+                ],
+                year,
+                Substitute::None,
+            );
+            holidays.extend([good_friday, easter_monday, ascension, whit_monday]);
+            holidays
+        }
+        "FR" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (5, 1, "Labour Day"),
+                    (5, 8, "Victory in Europe Day"),
+                    (7, 14, "Bastille Day"),
+                    (8, 15, "Assumption Day"),
+                    (11, 1, "All Saints' Day"),
+                    (11, 11, "Armistice Day"),
+                    (12, 25, "Christmas Day"),
+                ],
+                year,
+                Substitute::None,
+            );
+            holidays.extend([easter_monday, ascension, whit_monday]);
+            holidays
+        }
+        "AU" => {
+            let mut holidays = observe(
+                &[
+                    (1, 1, "New Year's Day"),
+                    (1, 26, "Australia Day"),
+                    (12, 25, "Christmas Day"),
+                    (12, 26, "Boxing Day"),
+                ],
+                year,
+                Substitute::NextFreeWeekday,
+            );
+            holidays.extend([good_friday, easter_monday, (date(year, 4, 25), "Anzac Day")]);
+            holidays
+        }
+        _ => vec![],
+    };
+    holidays.sort();
+    holidays
+}
@@ -0,0 +1,123 @@
+//! Durations written out for people, like "in 2 days 3 hours", in the user's language.
+
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Languages replies can write durations in, set with `$prefs language`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Fr,
+    Es,
+    De,
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Language::En => "English",
+            Language::Fr => "Français",
+            Language::Es => "Español",
+            Language::De => "Deutsch",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Language {
+    /// The code it's saved and chosen by, as in `$prefs language fr`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Fr => "fr",
+            Language::Es => "es",
+            Language::De => "de",
+        }
+    }
+}
+
+/// Only the largest units are written, "2 days 3 hours" rather than down to the second.
+const UNITS_SHOWN: usize = 2;
+
+enum Unit {
+    Day,
+    Hour,
+    Minute,
+}
+
+/// A number of a unit. German uses the dative after "in" and "vor", so those get their own forms.
+fn unit(count: u64, unit: Unit, language: Language, dative: bool) -> String {
+    let (singular, plural) = match (language, unit) {
+        (Language::En, Unit::Day) => ("day", "days"),
+        (Language::En, Unit::Hour) => ("hour", "hours"),
+        (Language::En, Unit::Minute) => ("minute", "minutes"),
+        (Language::Fr, Unit::Day) => ("jour", "jours"),
+        (Language::Fr, Unit::Hour) => ("heure", "heures"),
+        (Language::Fr, Unit::Minute) => ("minute", "minutes"),
+        (Language::Es, Unit::Day) => ("día", "días"),
+        (Language::Es, Unit::Hour) => ("hora", "horas"),
+        (Language::Es, Unit::Minute) => ("minuto", "minutos"),
+        (Language::De, Unit::Day) if dative => ("Tag", "Tagen"),
+        (Language::De, Unit::Day) => ("Tag", "Tage"),
+        (Language::De, Unit::Hour) => ("Stunde", "Stunden"),
+        (Language::De, Unit::Minute) => ("Minute", "Minuten"),
+    };
+    let name = if count == 1 { singular } else { plural };
+    format!("{count} {name}")
+}
+
+fn units(duration: Duration, language: Language, dative: bool) -> String {
+    let secs = duration.as_secs();
+    let parts: Vec<_> = [
+        (secs / 86400, Unit::Day),
+        (secs % 86400 / 3600, Unit::Hour),
+        (secs % 3600 / 60, Unit::Minute),
+    ]
+    .into_iter()
+    .skip_while(|(count, _)| *count == 0)
+    .take(UNITS_SHOWN)
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, name)| unit(count, name, language, dative))
+    .collect();
+    if !parts.is_empty() {
+        return parts.join(" ");
+    }
+    match language {
+        Language::En => "less than a minute",
+        Language::Fr => "moins d'une minute",
+        Language::Es => "menos de un minuto",
+        Language::De if dative => "weniger als einer Minute",
+        Language::De => "weniger als eine Minute",
+    }
+    .to_string()
+}
+
+/// A length of time on its own, like "2 days 3 hours".
+pub fn duration(duration: Duration, language: Language) -> String {
+    units(duration, language, false)
+}
+
+/// Time until something, like "in 2 days 3 hours".
+pub fn until(duration: Duration, language: Language) -> String {
+    let units = units(duration, language, true);
+    match language {
+        Language::En => format!("in {units}"),
+        Language::Fr => format!("dans {units}"),
+        Language::Es => format!("en {units}"),
+        Language::De => format!("in {units}"),
+    }
+}
+
+/// Time since something, like "2 days ago".
+pub fn since(duration: Duration, language: Language) -> String {
+    let units = units(duration, language, true);
+    match language {
+        Language::En => format!("{units} ago"),
+        Language::Fr => format!("il y a {units}"),
+        Language::Es => format!("hace {units}"),
+        Language::De => format!("vor {units}"),
+    }
+}
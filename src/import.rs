@@ -0,0 +1,360 @@
+//! `$import csv`, bringing in reminders exported from other bots. The file is checked and shown
+//! as a summary first, nothing is scheduled until its owner confirms.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use jiff::{
+    civil::{Date, DateTime},
+    tz::TimeZone,
+    Timestamp, Zoned,
+};
+use serenity::all::{Attachment, ButtonStyle, CreateActionRow, CreateButton, UserId};
+use tokio::sync::Mutex;
+use untwine::prelude::ParserContext;
+
+use crate::{
+    audit::{self, Action},
+    command::{self, Command},
+    cron::CronSchedule,
+    format_time, interval, rrule, save, stats, timezone, validate_message, validate_times,
+    CommandError, Preferences, Reminder, Reply, TimeModifier, REMINDERS,
+};
+
+pub const MAX_IMPORT_BYTES: u32 = 256 * 1024;
+pub const MAX_IMPORT_ROWS: usize = 500;
+/// Rows listed in the summary, for both the reminders to import and the ones skipped.
+const ROWS_SHOWN: usize = 5;
+/// Rolling a repeating reminder forward past its first time gives up after this many steps.
+const MAX_CATCH_UP: usize = 10_000;
+
+const TIME_HEADERS: [&str; 6] = ["time", "when", "due", "datetime", "date_time", "remind_at"];
+const MESSAGE_HEADERS: [&str; 6] = [
+    "message",
+    "text",
+    "content",
+    "reminder",
+    "title",
+    "description",
+];
+const RECURRENCE_HEADERS: [&str; 6] = [
+    "recurrence",
+    "repeat",
+    "repeats",
+    "interval",
+    "every",
+    "rrule",
+];
+const TIMEZONE_HEADERS: [&str; 4] = ["timezone", "tz", "time_zone", "zone"];
+
+/// Reminders read from a file, waiting for their owner to confirm. Only the latest file per user
+/// is kept.
+static PENDING: LazyLock<Mutex<HashMap<UserId, Vec<Reminder>>>> = LazyLock::new(Default::default);
+
+/// Splits CSV text into records, with quoted fields able to hold commas, newlines and `""`.
+fn records(text: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, '\r') => {}
+            (false, c) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|record| record.iter().any(|field| !field.trim().is_empty()));
+    records
+}
+
+struct Columns {
+    time: Option<usize>,
+    date: Option<usize>,
+    message: usize,
+    recurrence: Option<usize>,
+    timezone: Option<usize>,
+}
+
+impl Columns {
+    fn find(header: &[String]) -> Option<Columns> {
+        let names: Vec<_> = header
+            .iter()
+            .map(|name| name.trim().to_lowercase().replace(' ', "_"))
+            .collect();
+        let find = |options: &[&str]| names.iter().position(|name| options.contains(&&**name));
+        let columns = Columns {
+            time: find(&TIME_HEADERS),
+            date: find(&["date"]),
+            message: find(&MESSAGE_HEADERS)?,
+            recurrence: find(&RECURRENCE_HEADERS),
+            timezone: find(&TIMEZONE_HEADERS),
+        };
+        (columns.time.is_some() || columns.date.is_some()).then_some(columns)
+    }
+}
+
+/// Reads a time the way exports tend to write them: unix seconds or milliseconds, RFC 3339 with an
+/// offset, or a plain date and time in `zone`. Dates on their own are taken at 9am.
+fn parse_time(text: &str, zone: &TimeZone) -> Option<Zoned> {
+    if let Ok(number) = text.parse::<i64>() {
+        // Seconds won't reach this for thousands of years
+        let timestamp = if number > 100_000_000_000 {
+            Timestamp::from_millisecond(number)
+        } else {
+            Timestamp::from_second(number)
+        };
+        return Some(timestamp.ok()?.to_zoned(zone.clone()));
+    }
+    if let Ok(zoned) = text.parse::<Zoned>() {
+        return Some(zoned);
+    }
+    if let Ok(timestamp) = text.parse::<Timestamp>() {
+        return Some(timestamp.to_zoned(zone.clone()));
+    }
+    if !text.contains(':') {
+        let date = text.parse::<Date>().ok()?;
+        return date.at(9, 0, 0, 0).to_zoned(zone.clone()).ok();
+    }
+    let datetime = text.parse::<DateTime>().ok()?;
+    datetime.to_zoned(zone.clone()).ok()
+}
+
+/// Reads a recurrence with the same syntax as `$si`, along with the words other bots use.
+fn parse_recurrence(
+    text: &str,
+    preferences: &Preferences,
+) -> Result<Option<Vec<TimeModifier>>, String> {
+    let text = text.trim();
+    let lower = text.to_lowercase();
+    let lower = lower.strip_prefix("every ").unwrap_or(&lower);
+    let spelled = match lower {
+        "" | "none" | "never" | "once" => return Ok(None),
+        "hourly" | "hour" => "1h".to_string(),
+        "daily" | "day" => "1d".to_string(),
+        "weekly" | "week" => "1w".to_string(),
+        "monthly" | "month" => "1mo".to_string(),
+        "yearly" | "annually" | "year" => "1y".to_string(),
+        _ if lower.starts_with("freq=") => format!("rrule {text}"),
+        _ if text.split_whitespace().count() == 5
+            && text
+                .chars()
+                .all(|c| c.is_ascii_digit() || " */,-".contains(c)) =>
+        {
+            format!("cron {text}")
+        }
+        _ => lower.to_string(),
+    };
+    let input = format!("si 0 {spelled}");
+    let mut parser_context = ParserContext::new(&input, preferences.time_context());
+    let modifiers = match parser_context.result(command::command(&parser_context)) {
+        Ok(Command::SetInterval(_, modifiers)) => modifiers,
+        Ok(Command::SetCronInterval(_, expression)) => {
+            let schedule = CronSchedule::parse(&expression).map_err(|e| e.to_string())?;
+            vec![TimeModifier::Cron(schedule)]
+        }
+        Ok(Command::SetRRuleInterval(_, rule)) => {
+            let rule = rrule::RRule::parse(&rule).map_err(|e| e.to_string())?;
+            vec![TimeModifier::RRule(rule)]
+        }
+        _ => return Err(format!("couldn't read the recurrence `{text}`")),
+    };
+    Ok(Some(modifiers))
+}
+
+fn field(row: &[String], column: Option<usize>) -> &str {
+    column
+        .and_then(|column| row.get(column))
+        .map_or("", |field| field.trim())
+}
+
+fn reminder(
+    row: &[String],
+    columns: &Columns,
+    preferences: &Preferences,
+) -> Result<Reminder, String> {
+    let zone = match field(row, columns.timezone) {
+        "" => preferences.time_zone(),
+        name => timezone::lookup(name).ok_or_else(|| format!("unknown timezone `{name}`"))?,
+    };
+    let time = match (field(row, columns.date), field(row, columns.time)) {
+        ("", time) | (time, "") => time.to_string(),
+        (date, time) => format!("{date} {time}"),
+    };
+    if time.is_empty() {
+        return Err("no time given".into());
+    }
+    let mut time = parse_time(&time, &zone)
+        .ok_or_else(|| format!("couldn't read the time `{time}`"))?
+        .with_time_zone(zone);
+    let message = validate_message(field(row, Some(columns.message))).map_err(|e| e.to_string())?;
+    let interval = parse_recurrence(field(row, columns.recurrence), preferences)?;
+    let mut reminder = Reminder {
+        interval,
+        key: 0,
+        ..Reminder::new(time.clone(), message)
+    };
+    // Repeating reminders pick up from their next occurrence, one-offs in the past are rejected
+    let now = Zoned::now();
+    for _ in 0..MAX_CATCH_UP {
+        if time >= now {
+            break;
+        }
+        match reminder.next_occurrence(&preferences.holidays) {
+            Ok(Some(next)) => {
+                reminder.advance(next.clone(), &preferences.holidays);
+                time = next;
+            }
+            _ => break,
+        }
+    }
+    validate_times(&[time], preferences).map_err(|e| e.to_string())?;
+    Ok(reminder)
+}
+
+fn describe(reminder: &Reminder, preferences: &Preferences) -> String {
+    let mut line = format!(
+        "{} - {}",
+        format_time(&reminder.time, preferences.time_format()),
+        reminder.message
+    );
+    if let Some(modifiers) = &reminder.interval {
+        line.push_str(&format!(
+            " (repeats {})",
+            interval::describe(modifiers, preferences.time_format())
+        ));
+    }
+    line
+}
+
+/// Reads an attached CSV file and replies with what would be imported, with buttons to go ahead.
+pub async fn preview(
+    user: UserId,
+    attachment: Option<&Attachment>,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    let attachment = attachment.ok_or(CommandError::ImportNeedsFile)?;
+    if attachment.size > MAX_IMPORT_BYTES {
+        return Err(CommandError::ImportTooLarge);
+    }
+    let bytes = attachment.download().await?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut records = records(text.trim_start_matches('\u{feff}'));
+    if records.is_empty() {
+        return Err(CommandError::ImportMissingColumns);
+    }
+    let header = records.remove(0);
+    let columns = Columns::find(&header).ok_or(CommandError::ImportMissingColumns)?;
+    if records.len() > MAX_IMPORT_ROWS {
+        return Err(CommandError::ImportTooLarge);
+    }
+
+    let mut reminders = vec![];
+    let mut skipped = vec![];
+    for (index, row) in records.iter().enumerate() {
+        match reminder(row, &columns, preferences) {
+            Ok(reminder) => reminders.push(reminder),
+            // The header is row 1
+            Err(e) => skipped.push(format!("Row {}: {e}", index + 2)),
+        }
+    }
+    reminders.sort_by(|a, b| a.time.cmp(&b.time));
+
+    let repeating = reminders.iter().filter(|r| r.interval.is_some()).count();
+    let mut lines = vec![format!(
+        "{} has {} reminders ready to import ({repeating} repeating) and {} rows that will be skipped",
+        attachment.filename,
+        reminders.len(),
+        skipped.len()
+    )];
+    lines.extend(
+        reminders
+            .iter()
+            .take(ROWS_SHOWN)
+            .map(|reminder| describe(reminder, preferences)),
+    );
+    if reminders.len() > ROWS_SHOWN {
+        lines.push(format!("...and {} more", reminders.len() - ROWS_SHOWN));
+    }
+    if !skipped.is_empty() {
+        lines.push(String::new());
+        lines.extend(skipped.iter().take(ROWS_SHOWN).cloned());
+        if skipped.len() > ROWS_SHOWN {
+            lines.push(format!("...and {} more", skipped.len() - ROWS_SHOWN));
+        }
+    }
+    if columns.timezone.is_none() {
+        lines.push(format!(
+            "\nTimes without an offset were read in {}",
+            preferences.timezone()
+        ));
+    }
+    let content = lines.join("\n");
+    if reminders.is_empty() {
+        return Ok(Reply::warning(content));
+    }
+
+    let buttons = vec![
+        CreateButton::new(format!("import:{user}:confirm"))
+            .label(format!("Import {}", reminders.len()))
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("import:{user}:cancel"))
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ];
+    PENDING.lock().await.insert(user, reminders);
+    let reply = if skipped.is_empty() {
+        Reply::from(content)
+    } else {
+        Reply::warning(content)
+    };
+    Ok(Reply {
+        components: vec![CreateActionRow::Buttons(buttons)],
+        ..reply
+    })
+}
+
+/// Schedules the reminders from the user's latest preview.
+pub async fn confirm(user: UserId) -> Result<String, &'static str> {
+    let reminders = PENDING
+        .lock()
+        .await
+        .remove(&user)
+        .ok_or("This import has already been handled")?;
+    let count = reminders.len();
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    for reminder in reminders {
+        let reminder = Reminder {
+            key: audit::next_key(),
+            ..reminder
+        };
+        audit::record(user, &reminder, Action::Created, "$import").await;
+        list.push(reminder);
+    }
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+    drop(cache);
+    stats::record(user, |stats| stats.scheduled += count as u64).await;
+    save();
+    Ok(format!("Imported {count} reminders, see them with `$rs`"))
+}
+
+pub async fn cancel(user: UserId) -> &'static str {
+    PENDING.lock().await.remove(&user);
+    "Import cancelled, nothing was scheduled"
+}
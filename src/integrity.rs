@@ -0,0 +1,116 @@
+use std::sync::LazyLock;
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::all::Http;
+use tokio::sync::Mutex;
+
+use crate::{crash, holidays::HolidayCalendar, log_error, UserReminder};
+
+pub const BROKEN_FILE: &str = "broken.json";
+
+/// A saved record that couldn't be loaded, kept as it was so it can be fixed by hand.
+#[derive(Serialize, Deserialize)]
+struct Quarantined {
+    found: Zoned,
+    reason: String,
+    record: Value,
+}
+
+/// Reasons for everything quarantined since startup, for the admin notice once connected.
+static FOUND: LazyLock<Mutex<Vec<String>>> = LazyLock::new(Default::default);
+
+/// Why a reminder can't run, checking that its interval can be applied since a bad one would
+/// otherwise fail every time it's rescheduled or listed.
+fn problem(reminder: &UserReminder) -> Option<String> {
+    let reminder = &reminder.reminder;
+    match reminder.next_occurrence(&HolidayCalendar::default()) {
+        Ok(_) => None,
+        Err(e) => Some(format!("interval can't be applied: {e}")),
+    }
+}
+
+/// Loads saved reminders one at a time, moving any that don't parse or can't be rescheduled into
+/// `broken.json` instead of failing on them later.
+pub async fn check(contents: &str) -> Vec<UserReminder> {
+    let records: Vec<Value> = match serde_json::from_str(contents) {
+        Ok(records) => records,
+        Err(e) => {
+            quarantine(vec![(
+                format!("save file unreadable: {e}"),
+                contents.into(),
+            )])
+            .await;
+            return vec![];
+        }
+    };
+    let mut valid = vec![];
+    let mut broken = vec![];
+    for record in records {
+        match serde_json::from_value::<UserReminder>(record.clone()) {
+            Ok(reminder) => match problem(&reminder) {
+                None => valid.push(reminder),
+                Some(reason) => broken.push((reason, record)),
+            },
+            Err(e) => broken.push((format!("invalid record: {e}"), record)),
+        }
+    }
+    quarantine(broken).await;
+    valid
+}
+
+async fn quarantine(records: Vec<(String, Value)>) {
+    if records.is_empty() {
+        return;
+    }
+    let mut quarantined: Vec<Quarantined> = match tokio::fs::read_to_string(BROKEN_FILE).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+        Err(_) => vec![],
+    };
+    let mut found = FOUND.lock().await;
+    for (reason, record) in records {
+        eprintln!("Quarantined a saved reminder: {reason}");
+        found.push(reason.clone());
+        quarantined.push(Quarantined {
+            found: Zoned::now(),
+            reason,
+            record,
+        });
+    }
+    let json = serde_json::to_string(&quarantined).unwrap();
+    tokio::fs::write(BROKEN_FILE, json).await.unwrap();
+}
+
+/// Forgets records quarantined before `cutoff`, nobody is coming back to fix them by then.
+pub async fn purge(cutoff: &Zoned) {
+    let Ok(json) = tokio::fs::read_to_string(BROKEN_FILE).await else {
+        return;
+    };
+    let Ok(mut quarantined) = serde_json::from_str::<Vec<Quarantined>>(&json) else {
+        return;
+    };
+    let before = quarantined.len();
+    quarantined.retain(|record| record.found >= *cutoff);
+    if quarantined.len() == before {
+        return;
+    }
+    let json = serde_json::to_string(&quarantined).unwrap();
+    log_error(tokio::fs::write(BROKEN_FILE, json).await);
+}
+
+/// Tells the admins about anything quarantined while loading.
+pub async fn report(http: &Http) {
+    let found = std::mem::take(&mut *FOUND.lock().await);
+    if found.is_empty() {
+        return;
+    }
+    let mut content = format!(
+        "**reedbot startup check**\n{} saved reminders couldn't be loaded and were moved to `{BROKEN_FILE}`:",
+        found.len()
+    );
+    for reason in found.iter().take(10) {
+        content.push_str(&format!("\n- {reason}"));
+    }
+    crash::alert(http, &content).await;
+}
@@ -0,0 +1,457 @@
+use jiff::{ToSpan, Zoned};
+use serenity::all::{
+    ActionRowComponent, ButtonStyle, ComponentInteraction, ComponentInteractionDataKind, Context,
+    CreateActionRow, CreateButton, CreateInputText, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateModal, InputTextStyle, ModalInteraction, UserId,
+};
+
+use crate::{
+    ambiguity,
+    audit::{self, Action},
+    checklist, completion, format_time, get_preferences,
+    humanize::Language,
+    import,
+    listing::ListStyle,
+    log_error, manage, natural, onboarding, preferences_reply, preview, save,
+    sequence::{self, SequenceCommand},
+    set_preferences, stats, timezone, transfer, undo, Reminder, Reply, TimeFormat, WeekdayMode,
+    REMINDERS,
+};
+
+/// How long the snooze button puts a reminder off for.
+const SNOOZE_MINUTES: i64 = 10;
+
+/// Buttons under a delivered reminder. Their IDs hold the owner and the reminder's key rather
+/// than pointing at state in memory, so they keep working after a restart.
+pub fn reminder_buttons(user: UserId, key: u64, step: bool, recurring: bool) -> CreateActionRow {
+    let mut buttons = vec![CreateButton::new(format!("reminder:snooze:{user}:{key}"))
+        .label(format!("Snooze {SNOOZE_MINUTES}m"))
+        .style(ButtonStyle::Secondary)];
+    // Sequence steps have their own Done, which also moves the sequence along
+    let done = if step {
+        format!("seq:done:{user}:{key}")
+    } else {
+        format!("reminder:done:{user}:{key}")
+    };
+    buttons.push(
+        CreateButton::new(done)
+            .label("Done")
+            .style(ButtonStyle::Success),
+    );
+    if recurring {
+        buttons.push(
+            CreateButton::new(format!("reminder:manage:{user}:{key}"))
+                .label("Manage")
+                .style(ButtonStyle::Secondary),
+        );
+    }
+    CreateActionRow::Buttons(buttons)
+}
+
+/// Schedules another copy of a delivered reminder, found through the audit log since one-off
+/// reminders are gone from the list once they fire.
+async fn snooze(user: UserId, key: u64) -> Result<String, &'static str> {
+    let entry = audit::entries(user, Some(key), 1)
+        .await
+        .pop()
+        .ok_or("This reminder is no longer available")?;
+    let preferences = get_preferences(user, None).await;
+    let time = Zoned::now()
+        .with_time_zone(preferences.time_zone())
+        .checked_add(SNOOZE_MINUTES.minutes())
+        .map_err(|_| "Couldn't snooze this reminder")?;
+    let reminder = Reminder::new(time.clone(), entry.message);
+    audit::record(user, &reminder, Action::Created, "snooze").await;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    list.push(reminder);
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+    drop(cache);
+    stats::record(user, |stats| stats.snoozed += 1).await;
+    save();
+    Ok(format!(
+        "Snoozed until {}",
+        format_time(&time, preferences.time_format())
+    ))
+}
+
+/// Marks a sequence step done from its reminder, if it's still the current step.
+async fn finish_step(user: UserId, key: u64) -> Result<String, String> {
+    let name = sequence::current_step(user, key)
+        .await
+        .ok_or("This step was already marked done")?;
+    let preferences = get_preferences(user, None).await;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    sequence::handle(user, SequenceCommand::Done(name), list, &preferences)
+        .await
+        .map(|reply| reply.content)
+        .map_err(|e| e.to_string())
+}
+
+/// Parses the owner and key from a button ID, only for the owner themselves.
+fn owned(user: UserId, owner: &str, key: &str) -> Option<u64> {
+    if owner.parse::<UserId>().ok()? != user {
+        return None;
+    }
+    key.parse().ok()
+}
+
+fn timezone_modal(id: &str) -> CreateModal {
+    let input = CreateInputText::new(InputTextStyle::Short, "Timezone", "timezone")
+        .placeholder("Europe/London, EST, UTC+5:30");
+    CreateModal::new(id, "Change timezone").components(vec![CreateActionRow::InputText(input)])
+}
+
+fn error_response(message: impl Into<String>) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        Reply::error(message)
+            .into_interaction_message()
+            .ephemeral(true),
+    )
+}
+
+pub async fn handle_component(ctx: &Context, component: ComponentInteraction) {
+    let user = component.user.id;
+    let parts: Vec<_> = component.data.custom_id.split(':').collect();
+
+    let response = match parts.as_slice() {
+        ["prefs", "timezone"] => CreateInteractionResponse::Modal(timezone_modal("prefs:timezone")),
+        ["onboard", owner, "other"] => {
+            if owner.parse::<UserId>().ok() != Some(user) {
+                return;
+            }
+            CreateInteractionResponse::Modal(timezone_modal("onboard:timezone"))
+        }
+        ["onboard", owner, action] => {
+            if owner.parse::<UserId>().ok() != Some(user) {
+                return;
+            }
+            let value = match &component.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => {
+                    values.first().map(String::as_str)
+                }
+                _ => None,
+            };
+            match onboarding::advance(user, action, value).await {
+                Ok(reply) => {
+                    CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+                }
+                Err(e) => error_response(e),
+            }
+        }
+        ["prefs", "timeformat", format] => {
+            let time_format = match *format {
+                "12h" => TimeFormat::H12,
+                "24h" => TimeFormat::H24,
+                _ => return,
+            };
+            set_preferences(user, |prefs| prefs.time_format = Some(time_format)).await;
+            let reply = preferences_reply(user, component.guild_id).await;
+            CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+        }
+        ["prefs", "weekdays", mode] => {
+            let weekday_mode = match *mode {
+                "smart" => WeekdayMode::Smart,
+                "next" => WeekdayMode::Next,
+                _ => return,
+            };
+            set_preferences(user, |prefs| prefs.weekday_mode = Some(weekday_mode)).await;
+            let reply = preferences_reply(user, component.guild_id).await;
+            CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+        }
+        ["prefs", "liststyle", style] => {
+            let list_style = match *style {
+                "compact" => ListStyle::Compact,
+                "verbose" => ListStyle::Verbose,
+                _ => return,
+            };
+            set_preferences(user, |prefs| prefs.list_style = Some(list_style)).await;
+            let reply = preferences_reply(user, component.guild_id).await;
+            CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+        }
+        ["prefs", "natural", state @ ("on" | "off")] => {
+            set_preferences(user, |prefs| prefs.natural = *state == "on").await;
+            let reply = preferences_reply(user, component.guild_id).await;
+            CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+        }
+        ["prefs", "language"] => {
+            let ComponentInteractionDataKind::StringSelect { values } = &component.data.kind else {
+                return;
+            };
+            let language = match values.first().map(String::as_str) {
+                Some("en") => Language::En,
+                Some("fr") => Language::Fr,
+                Some("es") => Language::Es,
+                Some("de") => Language::De,
+                _ => return,
+            };
+            set_preferences(user, |prefs| prefs.language = Some(language)).await;
+            let reply = preferences_reply(user, component.guild_id).await;
+            CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+        }
+        ["transfer", action @ ("accept" | "decline"), id] => {
+            let Ok(id) = id.parse() else {
+                return;
+            };
+            let content = transfer::resolve(ctx, id, user, *action == "accept").await;
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            )
+        }
+        // Offers from before they were kept on disk, which could be answered forever
+        ["transfer", "accept" | "decline", _, _, _] => CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content("This transfer offer has expired")
+                .components(vec![]),
+        ),
+        ["reminder", "snooze", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            match snooze(user, key).await {
+                Ok(notice) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("{}\n{notice}", component.message.content))
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["reminder", "done", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            match completion::from_button(user, key).await {
+                Ok(notice) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("{}\n✅ {notice}", component.message.content))
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["reminder", "manage", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            match manage::menu(user, key).await {
+                Ok(reply) => CreateInteractionResponse::Message(
+                    reply.into_interaction_message().ephemeral(true),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["manage", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            let action = match &component.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => {
+                    values.first().cloned().unwrap_or_default()
+                }
+                _ => return,
+            };
+            if action == "time" {
+                CreateInteractionResponse::Modal(manage::time_modal(key))
+            } else {
+                match manage::apply(user, key, &action).await {
+                    Ok(content) => CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .content(content)
+                            .components(vec![]),
+                    ),
+                    Err(e) => error_response(e),
+                }
+            }
+        }
+        ["undo", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            match undo::undo(user, key).await {
+                Ok(notice) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("~~{}~~\n{notice}", component.message.content))
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["check", owner, key, index] => {
+            let (Some(key), Ok(index)) = (owned(user, owner, key), index.parse()) else {
+                return;
+            };
+            let content = &component.message.content;
+            match checklist::toggle(user, key, index, content).await {
+                Some((content, checklist_rows)) => {
+                    let step = sequence::current_step(user, key).await.is_some();
+                    let recurring = REMINDERS.lock().await.get(&user).is_some_and(|list| {
+                        list.iter().any(|r| r.key == key && r.interval.is_some())
+                    });
+                    let mut rows = vec![reminder_buttons(user, key, step, recurring)];
+                    rows.extend(checklist_rows);
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .content(content)
+                            .components(rows),
+                    )
+                }
+                None => error_response("This checklist is no longer available"),
+            }
+        }
+        ["nl", "schedule", owner, channel, message] => {
+            let (Ok(owner), Ok(channel), Ok(message)) =
+                (owner.parse::<UserId>(), channel.parse(), message.parse())
+            else {
+                return;
+            };
+            if owner != user {
+                return;
+            }
+            match natural::schedule(ctx, user, channel, message).await {
+                Ok(content) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["pick", owner, index] => {
+            let (Ok(owner), Ok(index)) = (owner.parse::<UserId>(), index.parse()) else {
+                return;
+            };
+            if owner != user {
+                return;
+            }
+            match ambiguity::pick(ctx, user, index).await {
+                Ok(content) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["import", owner, action] => {
+            if owner.parse::<UserId>().ok() != Some(user) {
+                return;
+            }
+            let result = match *action {
+                "confirm" => import::confirm(user).await,
+                _ => Ok(import::cancel(user).await.to_string()),
+            };
+            match result {
+                Ok(content) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .embeds(vec![])
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["interval", owner, preview, action] => {
+            if owner.parse::<UserId>().ok() != Some(user) {
+                return;
+            }
+            let preview = preview.parse().unwrap_or_default();
+            let result = match *action {
+                "apply" => preview::apply(user, preview).await,
+                _ => Ok(preview::cancel(user, preview).await.to_string()),
+            };
+            match result {
+                Ok(content) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        ["nl", "dismiss", owner] => {
+            if owner.parse::<UserId>().ok() != Some(user) {
+                return;
+            }
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content("Okay, I won't schedule it")
+                    .components(vec![]),
+            )
+        }
+        ["seq", "done", owner, key] => {
+            let Some(key) = owned(user, owner, key) else {
+                return;
+            };
+            match finish_step(user, key).await {
+                Ok(notice) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("{}\n{notice}", component.message.content))
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+        _ => return,
+    };
+
+    log_error(component.create_response(ctx, response).await);
+}
+
+fn input_value<'a>(modal: &'a ModalInteraction, id: &str) -> Option<&'a str> {
+    modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == id => input.value.as_deref(),
+            _ => None,
+        })
+}
+
+pub async fn handle_modal(ctx: &Context, modal: ModalInteraction) {
+    let user = modal.user.id;
+
+    let response = match modal.data.custom_id.as_str() {
+        "prefs:timezone" => {
+            let input = input_value(&modal, "timezone").unwrap_or_default();
+            match timezone::resolve(input) {
+                Ok(timezone) => {
+                    set_preferences(user, |prefs| prefs.timezone = Some(timezone)).await;
+                    let reply = preferences_reply(user, modal.guild_id).await;
+                    CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+                }
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "onboard:timezone" => {
+            let input = input_value(&modal, "timezone");
+            match onboarding::advance(user, "timezone", input).await {
+                Ok(reply) => {
+                    CreateInteractionResponse::UpdateMessage(reply.into_interaction_message())
+                }
+                Err(e) => error_response(e),
+            }
+        }
+        id => {
+            let Some(key) = id.strip_prefix("manage:").and_then(|key| key.parse().ok()) else {
+                return;
+            };
+            let input = input_value(&modal, "time").unwrap_or_default();
+            match manage::retime(user, key, input).await {
+                Ok(content) => CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+                Err(e) => error_response(e),
+            }
+        }
+    };
+
+    log_error(modal.create_response(ctx, response).await);
+}
@@ -0,0 +1,221 @@
+use std::fmt::Display;
+
+use jiff::civil::Weekday;
+
+use crate::{Period, PeriodDay, TimeFormat, TimeModifier};
+
+pub enum IntervalEdit {
+    Add(Vec<TimeModifier>),
+    Remove(ModifierKind),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ModifierKind {
+    Delay,
+    Weekday,
+    Time,
+    Date,
+    Months,
+    Years,
+    Week,
+    Quarter,
+    Sun,
+}
+
+impl ModifierKind {
+    pub fn matches(&self, modifier: &TimeModifier) -> bool {
+        matches!(
+            (self, modifier),
+            (ModifierKind::Delay, TimeModifier::Delay(_))
+                | (ModifierKind::Weekday, TimeModifier::Weekday(_))
+                | (ModifierKind::Time, TimeModifier::TimeOfDay { .. })
+                | (ModifierKind::Date, TimeModifier::Date { .. })
+                | (ModifierKind::Months, TimeModifier::Months(_))
+                | (
+                    ModifierKind::Months,
+                    TimeModifier::Next {
+                        period: Period::Month,
+                        ..
+                    }
+                )
+                | (ModifierKind::Years, TimeModifier::Years(_))
+                | (
+                    ModifierKind::Years,
+                    TimeModifier::Next {
+                        period: Period::Year,
+                        ..
+                    }
+                )
+                | (ModifierKind::Week, TimeModifier::IsoWeek { .. })
+                | (ModifierKind::Quarter, TimeModifier::Quarter { .. })
+                | (ModifierKind::Sun, TimeModifier::Sun { .. })
+        )
+    }
+}
+
+impl Display for ModifierKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ModifierKind::Delay => "delay",
+            ModifierKind::Weekday => "weekday",
+            ModifierKind::Time => "time of day",
+            ModifierKind::Date => "date",
+            ModifierKind::Months => "months",
+            ModifierKind::Years => "years",
+            ModifierKind::Week => "week",
+            ModifierKind::Quarter => "quarter",
+            ModifierKind::Sun => "sun",
+        };
+        write!(f, "{name}")
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+pub fn month_name(month: i8) -> &'static str {
+    MONTHS.get(month as usize - 1).copied().unwrap_or("January")
+}
+
+fn plural(count: u64, unit: &str) -> String {
+    match count {
+        1 => format!("1 {unit}"),
+        _ => format!("{count} {unit}s"),
+    }
+}
+
+/// "every 1 day" reads better as "every day"
+fn every(amount: String) -> String {
+    match amount.strip_prefix("1 ") {
+        Some(unit) if !unit.contains(' ') => format!("every {unit}"),
+        _ => format!("every {amount}"),
+    }
+}
+
+fn ordinal(n: i8) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+pub fn weekday_name(weekday: i8) -> &'static str {
+    match Weekday::from_monday_zero_offset(weekday) {
+        Ok(Weekday::Monday) => "monday",
+        Ok(Weekday::Tuesday) => "tuesday",
+        Ok(Weekday::Wednesday) => "wednesday",
+        Ok(Weekday::Thursday) => "thursday",
+        Ok(Weekday::Friday) => "friday",
+        Ok(Weekday::Saturday) => "saturday",
+        _ => "sunday",
+    }
+}
+
+fn describe_delay(ms: u64) -> String {
+    let units = [
+        ("week", 7 * 24 * 60 * 60 * 1000),
+        ("day", 24 * 60 * 60 * 1000),
+        ("hour", 60 * 60 * 1000),
+        ("minute", 60 * 1000),
+        ("second", 1000),
+    ];
+    let mut rest = ms;
+    let mut parts = vec![];
+    for (unit, size) in units {
+        if rest >= size {
+            parts.push((rest / size, unit));
+            rest %= size;
+        }
+    }
+    parts
+        .iter()
+        .map(|(count, unit)| plural(*count, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn describe_time_of_day(hour: u64, minute: u64, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::H24 => format!("{hour}:{minute:02}"),
+        TimeFormat::H12 => {
+            let suffix = if hour < 12 { "am" } else { "pm" };
+            let hour = match hour % 12 {
+                0 => 12,
+                hour => hour,
+            };
+            match minute {
+                0 => format!("{hour}{suffix}"),
+                minute => format!("{hour}:{minute:02}{suffix}"),
+            }
+        }
+    }
+}
+
+fn describe_date(year: Option<i16>, month: Option<i8>, day: i8) -> String {
+    let month = month.and_then(|month| MONTHS.get(month as usize - 1));
+    match (year, month) {
+        (Some(year), Some(month)) => format!("on {month} {}, {year}", ordinal(day)),
+        (None, Some(month)) => format!("on {month} {}", ordinal(day)),
+        _ => format!("on the {}", ordinal(day)),
+    }
+}
+
+/// Describes an interval in words, e.g. "every monday at 9am".
+pub fn describe(interval: &[TimeModifier], format: TimeFormat) -> String {
+    let mut parts = vec![];
+    for (i, modifier) in interval.iter().enumerate() {
+        let first = i == 0;
+        let part = match modifier {
+            TimeModifier::Delay(ms) if *ms < 0 => {
+                format!("minus {}", describe_delay(ms.unsigned_abs()))
+            }
+            TimeModifier::Delay(ms) if first => every(describe_delay(*ms as u64)),
+            TimeModifier::Delay(ms) => format!("plus {}", describe_delay(*ms as u64)),
+            TimeModifier::Months(months) if first => every(plural(*months, "month")),
+            TimeModifier::Months(months) => format!("plus {}", plural(*months, "month")),
+            TimeModifier::Years(years) if first => every(plural(*years, "year")),
+            TimeModifier::Years(years) => format!("plus {}", plural(*years, "year")),
+            TimeModifier::Next {
+                period,
+                day: PeriodDay::First,
+            } if first => format!("on the first day of every {period}"),
+            TimeModifier::Next { period, .. } if first => format!("every {period}"),
+            TimeModifier::Next {
+                period,
+                day: PeriodDay::First,
+            } => format!("then the first day of the next {period}"),
+            TimeModifier::Next { period, .. } => format!("then the next {period}"),
+            TimeModifier::Weekday(weekday) if first => format!("every {}", weekday_name(*weekday)),
+            TimeModifier::Weekday(weekday) => format!("then the next {}", weekday_name(*weekday)),
+            TimeModifier::TimeOfDay { hour, minute } => {
+                format!("at {}", describe_time_of_day(*hour, *minute, format))
+            }
+            TimeModifier::Date { year, month, day } => describe_date(*year, *month, *day),
+            TimeModifier::IsoWeek { week, weekday } => {
+                format!("on {} of week {week}", weekday_name(*weekday))
+            }
+            TimeModifier::Quarter { position, .. } => position.to_string(),
+            TimeModifier::Sun { event, .. } => format!("at {event}"),
+            TimeModifier::Cron(schedule) => format!("on cron schedule `{schedule}`"),
+            TimeModifier::RRule(rule) => format!("following `RRULE:{rule}`"),
+        };
+        parts.push(part);
+    }
+    parts.join(" ")
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs::File,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// Held by whichever instance is sending reminders. The OS releases it when that process exits,
+/// so a standby instance sharing the working directory takes over on its next attempt.
+const LOCK_FILE: &str = "leader.lock";
+
+static LEADER: AtomicBool = AtomicBool::new(false);
+static LOCK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Whether this instance holds the lock. Standby instances stay connected but don't deliver,
+/// answer commands or write save files.
+pub fn is_leader() -> bool {
+    LEADER.load(Ordering::Relaxed)
+}
+
+/// Tries to take the lock, returning true only when this instance has just become the leader.
+pub fn elect() -> bool {
+    if is_leader() {
+        return false;
+    }
+    let Ok(file) = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(LOCK_FILE)
+    else {
+        return false;
+    };
+    if file.try_lock().is_err() {
+        return false;
+    }
+    *LOCK.lock().unwrap() = Some(file);
+    LEADER.store(true, Ordering::Relaxed);
+    true
+}
@@ -0,0 +1,3384 @@
+use ambiguity::Resolution;
+use announcement::{load_announcements, process_announcements, ANNOUNCEMENTS, ANNOUNCEMENTS_FILE};
+use audit::{load_audit, Action, AUDIT, AUDIT_FILE};
+use bookmarks::{load_bookmarks, MessageLink, BOOKMARKS, BOOKMARKS_FILE};
+use calendar::{load_calendars, CALENDARS, CALENDAR_FILE};
+use checklist::{load_checklists, CHECKLISTS, CHECKLISTS_FILE};
+use command::{
+    AdminCommand, AnnouncementCommand, Command, CommandName, HolidayCommand, ServerCommand,
+    TimeContext,
+};
+use condition::{Check, Guard, Verdict};
+use config::{config, load_config};
+use cron::CronSchedule;
+use delivery::{load_history, DeliveryStatus, Recipient, HISTORY, HISTORY_FILE};
+use dependency::Dependency;
+use emphasis::Emphasis;
+use feeds::{load_feeds, FEEDS_FILE, WATCHES};
+use guild::{load_guild_settings, GUILD_SETTINGS, GUILD_SETTINGS_FILE};
+use holidays::{HolidayCalendar, HolidayMode};
+use humanize::Language;
+use interval::IntervalEdit;
+use jiff::{
+    civil::{Date, ISOWeekDate, Weekday},
+    tz::TimeZone,
+    SignedDuration, Span, Timestamp, Zoned,
+};
+use listing::ListStyle;
+use matrix::{load_rooms, MATRIX_FILE, ROOMS};
+use presence::{load_held, HELD, HELD_FILE};
+use quarter::QuarterPosition;
+use rrule::RRule;
+use sequence::{load_sequences, SEQUENCES, SEQUENCES_FILE};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{
+        ChannelId, Colour, Context, CreateActionRow, CreateAllowedMentions, CreateAttachment,
+        CreateButton, CreateEmbed, CreateInteractionResponseMessage, CreateMessage,
+        CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EventHandler, GuildId,
+        Http, Interaction, Message, MessageId, OnlineStatus, Presence, Reaction, RoleId, UserId,
+        VoiceState,
+    },
+    async_trait, Client,
+};
+use shared::{load_shared_lists, process_shared_lists, SHARED_LISTS, SHARED_LISTS_FILE};
+use solar::{Location, SunEvent};
+use stats::{load_stats, STATS, STATS_FILE};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use tasks::{load_tasks, ProviderKind, ACCOUNTS, TASKS_FILE};
+use telegram::{load_links, LINKS, TELEGRAM_FILE};
+use thiserror::Error;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{Mutex, RwLock},
+};
+use trigger::{load_triggers, TRIGGERS, TRIGGERS_FILE};
+use untwine::prelude::ParserContext;
+use usage::{Outcome, USAGE, USAGE_FILE};
+
+mod ambiguity;
+mod announcement;
+mod audit;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod bookmarks;
+mod calendar;
+mod callout;
+mod checklist;
+mod command;
+mod completion;
+mod condition;
+mod config;
+mod content;
+mod crash;
+mod cron;
+mod dashboard;
+mod dedup;
+mod delivery;
+mod dependency;
+mod diagnostics;
+mod emphasis;
+mod expiry;
+mod export;
+mod feeds;
+mod guild;
+mod habit;
+mod help;
+mod holidays;
+mod humanize;
+mod import;
+mod integrity;
+mod interaction;
+mod interval;
+mod leader;
+mod listing;
+mod maintenance;
+mod manage;
+mod matrix;
+mod natural;
+mod onboarding;
+mod outbox;
+mod pinned;
+mod presence;
+mod preview;
+mod quarter;
+mod ratelimit;
+mod reaction;
+mod review;
+mod rrule;
+mod secret;
+mod sequence;
+mod shared;
+mod solar;
+mod stats;
+mod storage;
+pub mod syntax;
+mod tasks;
+mod telegram;
+mod template;
+mod timezone;
+mod transfer;
+mod trigger;
+mod undo;
+mod usage;
+mod web;
+
+/// A calendar period `next month` and `next year` jump into.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Month,
+    Year,
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::Month => write!(f, "month"),
+            Period::Year => write!(f, "year"),
+        }
+    }
+}
+
+/// Which day of the next month or year `next month` and `next year` land on.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodDay {
+    #[default]
+    First,
+    /// The same day as today, clamped to the end of shorter months
+    Same,
+}
+
+impl Display for PeriodDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeriodDay::First => write!(f, "first"),
+            PeriodDay::Same => write!(f, "same"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum TimeModifier {
+    /// Milliseconds, negative to go back
+    Delay(i64),
+    Weekday(i8),
+    TimeOfDay {
+        hour: u64,
+        minute: u64,
+    },
+    Date {
+        year: Option<i16>,
+        month: Option<i8>,
+        day: i8,
+    },
+    Months(u64),
+    Years(u64),
+    /// `next month` or `next year`, keeping the time of day
+    Next {
+        period: Period,
+        day: PeriodDay,
+    },
+    /// A day in an ISO 8601 week, moving to next year's once this year's day has passed
+    IsoWeek {
+        week: i8,
+        weekday: i8,
+    },
+    /// A quarter boundary of a fiscal year starting in month `fiscal_start`
+    Quarter {
+        position: QuarterPosition,
+        fiscal_start: i8,
+    },
+    /// Sunrise or sunset on the same day, or the next day it happens near the poles
+    Sun {
+        event: SunEvent,
+        location: Location,
+    },
+    Cron(CronSchedule),
+    RRule(RRule),
+}
+
+impl TimeModifier {
+    fn modify(&self, datetime: Zoned) -> Result<Zoned, jiff::Error> {
+        datetime.weekday();
+
+        match self {
+            TimeModifier::Delay(ms) => datetime.checked_add(SignedDuration::from_millis(*ms)),
+            TimeModifier::TimeOfDay { hour, minute } => datetime
+                .date()
+                .at(*hour as i8, *minute as i8, 0, 0)
+                .to_zoned(datetime.time_zone().clone()),
+            TimeModifier::Date { year, month, day } => {
+                let year = year.unwrap_or(datetime.year());
+                let month = month.unwrap_or(datetime.month());
+                jiff::civil::Date::new(year, month, *day)?
+                    .at(datetime.hour(), datetime.minute(), datetime.second(), 0)
+                    .to_zoned(datetime.time_zone().clone())
+            }
+            TimeModifier::Weekday(weekday) => {
+                datetime.nth_weekday(1, Weekday::from_monday_zero_offset(*weekday)?)
+            }
+            // Both clamp to the end of shorter months, e.g. January 31st plus a month is February 28th
+            TimeModifier::Months(months) => {
+                datetime.checked_add(Span::new().try_months(*months as i64)?)
+            }
+            TimeModifier::Years(years) => {
+                datetime.checked_add(Span::new().try_years(*years as i64)?)
+            }
+            TimeModifier::Next { period, day } => {
+                let span = match period {
+                    Period::Month => Span::new().months(1),
+                    Period::Year => Span::new().years(1),
+                };
+                let date = match (period, day) {
+                    (_, PeriodDay::Same) => return datetime.checked_add(span),
+                    (Period::Month, PeriodDay::First) => datetime.date().first_of_month(),
+                    (Period::Year, PeriodDay::First) => datetime.date().first_of_year(),
+                };
+                date.checked_add(span)?
+                    .to_datetime(datetime.time())
+                    .to_zoned(datetime.time_zone().clone())
+            }
+            TimeModifier::IsoWeek { week, weekday } => {
+                let weekday = Weekday::from_monday_zero_offset(*weekday)?;
+                let mut year = datetime.date().iso_week_date().year();
+                loop {
+                    let date = ISOWeekDate::new(year, *week, weekday)?.date();
+                    if date >= datetime.date() {
+                        return date
+                            .to_datetime(datetime.time())
+                            .to_zoned(datetime.time_zone().clone());
+                    }
+                    year += 1;
+                }
+            }
+            TimeModifier::Quarter {
+                position,
+                fiscal_start,
+            } => quarter::resolve(datetime.date(), *position, *fiscal_start)?
+                .to_datetime(datetime.time())
+                .to_zoned(datetime.time_zone().clone()),
+            TimeModifier::Sun { event, location } => {
+                Ok(solar::next(datetime.date(), *event, *location)?
+                    .to_zoned(datetime.time_zone().clone()))
+            }
+            TimeModifier::Cron(schedule) => schedule.next_after(&datetime),
+            TimeModifier::RRule(rule) => rule.next_after(&datetime),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Attachment {
+    filename: String,
+    url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct MessageSource {
+    channel: ChannelId,
+    message: MessageId,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Reminder {
+    time: Zoned,
+    message: String,
+    interval: Option<Vec<TimeModifier>>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+    #[serde(default)]
+    source: Option<MessageSource>,
+    #[serde(default)]
+    skip_holidays: bool,
+    /// With `skip_holidays`, holiday occurrences are sent on the next working day instead
+    #[serde(default)]
+    roll_holidays: bool,
+    /// The occurrence a rolled over one stands in for, where the schedule carries on from
+    #[serde(default)]
+    rolled_from: Option<Zoned>,
+    #[serde(default)]
+    channel: Option<ChannelId>,
+    #[serde(default)]
+    public: bool,
+    #[serde(default)]
+    followers: Vec<UserId>,
+    #[serde(default)]
+    attempts: u32,
+    #[serde(default)]
+    key: u64,
+    /// Day of the month that monthly and yearly repeats return to after being clamped.
+    #[serde(default)]
+    anchor_day: Option<i8>,
+    /// Set by `$r after`, keeps the reminder a fixed time after another one
+    #[serde(default)]
+    after: Option<Dependency>,
+    /// Checked when the reminder fires, it's skipped if this isn't met
+    #[serde(default)]
+    condition: Option<Guard>,
+    /// Added with `$note` after scheduling, shown under the message when it's delivered
+    #[serde(default)]
+    notes: Vec<String>,
+    /// Set by `$jitter`, seconds to spread copies for followers over. The config's
+    /// `delivery_jitter_secs` is used when unset.
+    #[serde(default)]
+    jitter: Option<u64>,
+    /// Set on retry copies, the occurrence they're retrying
+    #[serde(default)]
+    occurrence: Option<Occurrence>,
+    /// Set from the Manage button, paused reminders keep moving along their schedule unsent
+    #[serde(default)]
+    paused: bool,
+    /// Set by `$expire`, milliseconds its deliveries are kept for, 0 to keep them. Falls back to
+    /// the user's `expire_after` when unset.
+    #[serde(default)]
+    expire: Option<u64>,
+    /// Set by `$habit`, shows the reminder's streak in `$rs` and nudges before it breaks
+    #[serde(default)]
+    habit: bool,
+    /// Set by `$emphasis`
+    #[serde(default)]
+    emphasis: Emphasis,
+    /// Set with `!tts` in a `$say` message, read aloud in the channel
+    #[serde(default)]
+    tts: bool,
+    /// Role pinged when a `$say` reminder is posted, set with `$callout`
+    #[serde(default)]
+    callout: Option<RoleId>,
+}
+
+/// The occurrence a copy of a reminder stands in for. Copies have keys of their own, so this is
+/// the only link back to the reminder they came from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+enum Occurrence {
+    Of {
+        key: u64,
+        time: Timestamp,
+    },
+    /// Saved before copies had their own keys, when they shared the original's
+    Time(Timestamp),
+}
+
+/// Whether an interval only moves by whole months or years, so the day of the month should stay
+/// put instead of drifting to the 28th after passing through February.
+fn recovers_day(interval: &[TimeModifier]) -> bool {
+    interval
+        .iter()
+        .any(|m| matches!(m, TimeModifier::Months(_) | TimeModifier::Years(_)))
+        && interval.iter().all(|m| {
+            matches!(
+                m,
+                TimeModifier::Months(_) | TimeModifier::Years(_) | TimeModifier::TimeOfDay { .. }
+            )
+        })
+}
+
+/// Upper bound on consecutive holiday occurrences skipped, in case every step lands on one.
+const MAX_HOLIDAY_SKIPS: usize = 64;
+
+impl Reminder {
+    /// A one-off reminder with a fresh key and everything else left at its default, for struct
+    /// update syntax to set the rest.
+    fn new(time: Zoned, message: String) -> Reminder {
+        Reminder {
+            time,
+            message,
+            interval: None,
+            attachments: vec![],
+            source: None,
+            skip_holidays: false,
+            roll_holidays: false,
+            rolled_from: None,
+            channel: None,
+            public: false,
+            followers: vec![],
+            attempts: 0,
+            key: audit::next_key(),
+            anchor_day: None,
+            after: None,
+            condition: None,
+            notes: vec![],
+            jitter: None,
+            occurrence: None,
+            paused: false,
+            expire: None,
+            habit: false,
+            emphasis: Emphasis::Normal,
+            tts: false,
+            callout: None,
+        }
+    }
+
+    /// When the occurrence being sent was due, which a retry copy keeps.
+    fn occurrence(&self) -> Timestamp {
+        match self.occurrence {
+            Some(Occurrence::Of { time, .. } | Occurrence::Time(time)) => time,
+            None => self.time.timestamp(),
+        }
+    }
+
+    /// The key of the reminder the occurrence being sent belongs to, the original's for a copy.
+    fn origin(&self) -> u64 {
+        match self.occurrence {
+            Some(Occurrence::Of { key, .. }) => key,
+            _ => self.key,
+        }
+    }
+
+    /// Marks this as a copy of the occurrence `original` is sending.
+    fn stand_in_for(self, original: &Reminder) -> Reminder {
+        Reminder {
+            key: audit::next_key(),
+            occurrence: Some(Occurrence::Of {
+                key: original.origin(),
+                time: original.occurrence(),
+            }),
+            ..self
+        }
+    }
+
+    /// The notes as quoted lines to go under the message, empty without notes.
+    fn notes_text(&self) -> String {
+        self.notes
+            .iter()
+            .map(|note| format!("\n> {note}"))
+            .collect()
+    }
+
+    fn status(&self) -> DeliveryStatus {
+        match self.attempts {
+            0 => DeliveryStatus::Pending,
+            _ => DeliveryStatus::Retrying,
+        }
+    }
+
+    fn next_occurrence(&self, holidays: &HolidayCalendar) -> Result<Option<Zoned>, jiff::Error> {
+        Ok(self.next_times(holidays)?.map(|(_, time)| time))
+    }
+
+    /// The next occurrence on the reminder's schedule, and when it's sent once holidays are
+    /// skipped or rolled over.
+    fn next_times(
+        &self,
+        holidays: &HolidayCalendar,
+    ) -> Result<Option<(Zoned, Zoned)>, jiff::Error> {
+        let Some(interval) = &self.interval else {
+            return Ok(None);
+        };
+        let mut time = self
+            .rolled_from
+            .clone()
+            .unwrap_or_else(|| self.time.clone());
+        let mut sent = time.clone();
+        for _ in 0..MAX_HOLIDAY_SKIPS {
+            for modifier in interval {
+                time = modifier.modify(time)?;
+            }
+            if recovers_day(interval) {
+                let day = self.anchor().min(time.days_in_month());
+                time = time.with().day(day).build()?;
+            }
+            sent = time.clone();
+            if self.skip_holidays && self.roll_holidays {
+                sent = holidays.next_working_day(&time)?;
+            }
+            // A rolled over occurrence can catch up with the ones after it, which are passed over
+            if self.rolled_from.is_some() && sent <= self.time {
+                continue;
+            }
+            if !self.skip_holidays || self.roll_holidays || !holidays.is_holiday(time.date()) {
+                break;
+            }
+        }
+        let ended = interval
+            .iter()
+            .any(|modifier| matches!(modifier, TimeModifier::RRule(rule) if rule.ended(&time)));
+        // An interval that goes backwards or nowhere would fire forever, so it stops instead
+        Ok((!ended && sent > self.time).then_some((time, sent)))
+    }
+
+    fn holiday_mode(&self) -> HolidayMode {
+        match (self.skip_holidays, self.roll_holidays) {
+            (false, _) => HolidayMode::Send,
+            (true, false) => HolidayMode::Skip,
+            (true, true) => HolidayMode::Roll,
+        }
+    }
+
+    fn anchor(&self) -> i8 {
+        self.anchor_day.unwrap_or(self.time.day())
+    }
+
+    /// Moves to a later occurrence, remembering which day of the month the reminder started on
+    /// and, for one rolled over a holiday, the day it was due.
+    fn advance(&mut self, time: Zoned, holidays: &HolidayCalendar) {
+        self.rolled_from = match self.next_times(holidays) {
+            Ok(Some((due, sent))) if sent == time && due != sent => Some(due),
+            _ => None,
+        };
+        self.anchor_day = Some(self.anchor());
+        self.time = time;
+    }
+
+    /// Fast-forwards through the next `count` occurrences, starting with the scheduled one.
+    fn occurrences(
+        &self,
+        holidays: &HolidayCalendar,
+        count: usize,
+    ) -> Result<Vec<Zoned>, jiff::Error> {
+        let mut reminder = self.clone();
+        let mut times = vec![reminder.time.clone()];
+        while times.len() < count {
+            let Some(next) = reminder.next_occurrence(holidays)? else {
+                break;
+            };
+            times.push(next.clone());
+            reminder.advance(next, holidays);
+        }
+        Ok(times)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+enum TimeFormat {
+    #[serde(rename = "12h")]
+    #[default]
+    H12,
+    #[serde(rename = "24h")]
+    H24,
+}
+
+impl Display for TimeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeFormat::H12 => write!(f, "12h"),
+            TimeFormat::H24 => write!(f, "24h"),
+        }
+    }
+}
+
+/// How a weekday naming today is read, e.g. `tuesday 9am` said on a Tuesday.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum WeekdayMode {
+    /// Today if a time of day after the weekday is still ahead, otherwise next week
+    #[default]
+    Smart,
+    /// Always next week
+    Next,
+}
+
+impl Display for WeekdayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeekdayMode::Smart => write!(f, "smart"),
+            WeekdayMode::Next => write!(f, "next"),
+        }
+    }
+}
+
+const DEFAULT_TIMEZONE: &str = "America/New_York";
+/// How many years ahead reminders can be scheduled unless changed with `$prefs horizon`.
+const DEFAULT_HORIZON_YEARS: i16 = 10;
+const MAX_HORIZON_YEARS: u64 = 100;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Preferences {
+    timezone: Option<String>,
+    time_format: Option<TimeFormat>,
+    #[serde(default)]
+    timezone_notice_shown: bool,
+    #[serde(default)]
+    holidays: HolidayCalendar,
+    #[serde(default)]
+    weekday_mode: Option<WeekdayMode>,
+    #[serde(default)]
+    list_style: Option<ListStyle>,
+    /// Month the fiscal year starts in, 1 for January
+    #[serde(default)]
+    fiscal_year_start: Option<i8>,
+    /// Used for sunrise and sunset
+    #[serde(default)]
+    location: Option<Location>,
+    /// Named durations set with `$offset`, in milliseconds
+    #[serde(default)]
+    offsets: BTreeMap<String, u64>,
+    /// Set by `$delivery online`, the longest a reminder is held while offline in milliseconds
+    #[serde(default)]
+    hold_offline: Option<u64>,
+    /// Set by `$prefs template`, lays out delivered reminders
+    #[serde(default)]
+    template: Option<String>,
+    /// Set by `$prefs natural`, reads `$r` times as natural language before the strict syntax
+    #[serde(default)]
+    natural: bool,
+    /// Set by `$prefs next`, which day `next month` and `next year` land on
+    #[serde(default)]
+    period_day: Option<PeriodDay>,
+    /// Set by `$prefs horizon`, how many years ahead reminders can be scheduled
+    #[serde(default)]
+    horizon: Option<i16>,
+    /// Set by `$prefs language`, what durations are written in
+    #[serde(default)]
+    language: Option<Language>,
+    /// Where the user is in the first-run setup, None once it's finished or skipped
+    #[serde(default)]
+    onboarding: Option<onboarding::Step>,
+    /// Set by `$expire`, milliseconds delivered reminders are kept for before being deleted
+    #[serde(default)]
+    expire_after: Option<u64>,
+    /// Set by `$nextup`, the pinned DM listing upcoming reminders
+    #[serde(default)]
+    next_up: Option<pinned::NextUp>,
+    /// Set by `$review`, a look back and ahead DMed on Sunday evenings
+    #[serde(default)]
+    weekly_review: bool,
+    /// The Sunday the last review went out on
+    #[serde(default)]
+    last_review: Option<Date>,
+}
+
+impl Preferences {
+    fn or(self, fallback: Preferences) -> Preferences {
+        Preferences {
+            timezone: self.timezone.or(fallback.timezone),
+            time_format: self.time_format.or(fallback.time_format),
+            timezone_notice_shown: self.timezone_notice_shown,
+            holidays: self.holidays,
+            weekday_mode: self.weekday_mode.or(fallback.weekday_mode),
+            list_style: self.list_style.or(fallback.list_style),
+            fiscal_year_start: self.fiscal_year_start.or(fallback.fiscal_year_start),
+            location: self.location.or(fallback.location),
+            offsets: self.offsets,
+            hold_offline: self.hold_offline,
+            template: self.template,
+            natural: self.natural,
+            horizon: self.horizon,
+            period_day: self.period_day.or(fallback.period_day),
+            language: self.language.or(fallback.language),
+            onboarding: self.onboarding,
+            expire_after: self.expire_after,
+            next_up: self.next_up,
+            weekly_review: self.weekly_review,
+            last_review: self.last_review,
+        }
+    }
+
+    fn timezone(&self) -> &str {
+        self.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE)
+    }
+
+    fn time_format(&self) -> TimeFormat {
+        self.time_format.unwrap_or_default()
+    }
+
+    fn time_zone(&self) -> TimeZone {
+        timezone::lookup(self.timezone()).unwrap_or_else(TimeZone::system)
+    }
+
+    fn weekday_mode(&self) -> WeekdayMode {
+        self.weekday_mode.unwrap_or_default()
+    }
+
+    fn list_style(&self) -> ListStyle {
+        self.list_style.unwrap_or_default()
+    }
+
+    fn language(&self) -> Language {
+        self.language.unwrap_or_default()
+    }
+
+    fn horizon(&self) -> i16 {
+        self.horizon.unwrap_or(DEFAULT_HORIZON_YEARS)
+    }
+
+    fn fiscal_year_start(&self) -> i8 {
+        self.fiscal_year_start.unwrap_or(1)
+    }
+
+    fn time_context(&self) -> TimeContext {
+        TimeContext {
+            time_zone: self.time_zone(),
+            weekday_mode: self.weekday_mode(),
+            fiscal_start: self.fiscal_year_start(),
+            period_day: self.period_day.unwrap_or_default(),
+            location: self.location,
+            offsets: self.offsets.clone(),
+        }
+    }
+}
+
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+type ReminderCache = Mutex<HashMap<UserId, Vec<Reminder>>>;
+static REMINDERS: LazyLock<ReminderCache> = LazyLock::new(Default::default);
+static PREFERENCES: LazyLock<RwLock<HashMap<UserId, Preferences>>> =
+    LazyLock::new(Default::default);
+
+async fn get_preferences(user: UserId, guild: Option<GuildId>) -> Preferences {
+    let preferences = PREFERENCES
+        .read()
+        .await
+        .get(&user)
+        .cloned()
+        .unwrap_or_default();
+    match guild {
+        Some(guild) => preferences.or(guild::get_guild_settings(guild).await.defaults),
+        None => preferences,
+    }
+}
+
+async fn set_preferences(user: UserId, cb: impl FnOnce(&mut Preferences)) {
+    let mut map = PREFERENCES.write().await;
+    cb(map.entry(user).or_default());
+    save();
+}
+
+async fn preferences_reply(user: UserId, guild: Option<GuildId>) -> Reply {
+    let personal = PREFERENCES
+        .read()
+        .await
+        .get(&user)
+        .cloned()
+        .unwrap_or_default();
+    let preferences = get_preferences(user, guild).await;
+    let source = |personal: bool, inherited: bool| match (personal, inherited) {
+        (true, _) => "your setting",
+        (false, true) => "server default",
+        (false, false) => "default",
+    };
+
+    let content = [
+        "Your preferences:".to_string(),
+        format!(
+            "Timezone: {} ({})",
+            preferences.timezone(),
+            source(personal.timezone.is_some(), preferences.timezone.is_some())
+        ),
+        format!(
+            "Time format: {} ({})",
+            preferences.time_format(),
+            source(
+                personal.time_format.is_some(),
+                preferences.time_format.is_some()
+            )
+        ),
+        format!(
+            "Weekdays: {} ({})",
+            preferences.weekday_mode(),
+            source(
+                personal.weekday_mode.is_some(),
+                preferences.weekday_mode.is_some()
+            )
+        ),
+        format!(
+            "List style: {} ({})",
+            preferences.list_style(),
+            source(
+                personal.list_style.is_some(),
+                preferences.list_style.is_some()
+            )
+        ),
+        format!(
+            "Fiscal year starts: {} ({})",
+            interval::month_name(preferences.fiscal_year_start()),
+            source(
+                personal.fiscal_year_start.is_some(),
+                preferences.fiscal_year_start.is_some()
+            )
+        ),
+        format!(
+            "Location: {} ({})",
+            preferences
+                .location
+                .map_or("not set".to_string(), |location| location.to_string()),
+            source(personal.location.is_some(), preferences.location.is_some())
+        ),
+        match preferences.hold_offline {
+            Some(max_hold) => format!(
+                "Delivery: when you're online, for up to {}",
+                humanize::duration(Duration::from_millis(max_hold), preferences.language())
+            ),
+            None => "Delivery: immediately".to_string(),
+        },
+        match preferences.expire_after {
+            Some(lifetime) => format!(
+                "Delivered reminders: deleted after {}",
+                humanize::duration(Duration::from_millis(lifetime), preferences.language())
+            ),
+            None => "Delivered reminders: kept".to_string(),
+        },
+        format!(
+            "Weekly review: {}",
+            if preferences.weekly_review {
+                "on"
+            } else {
+                "off"
+            }
+        ),
+        format!(
+            "Next up message: {}",
+            if preferences.next_up.is_some() {
+                "on"
+            } else {
+                "off"
+            }
+        ),
+        match &preferences.template {
+            Some(template) => format!("Template: `{template}`"),
+            None => "Template: default".to_string(),
+        },
+        format!(
+            "Natural language times: {}",
+            if preferences.natural { "on" } else { "off" }
+        ),
+        format!(
+            "Language: {} ({})",
+            preferences.language(),
+            source(personal.language.is_some(), preferences.language.is_some())
+        ),
+        format!(
+            "Next month/year lands on: {} day ({})",
+            preferences.period_day.unwrap_or_default(),
+            source(
+                personal.period_day.is_some(),
+                preferences.period_day.is_some()
+            )
+        ),
+        format!(
+            "Horizon: {} years ({})",
+            preferences.horizon(),
+            source(personal.horizon.is_some(), false)
+        ),
+    ]
+    .join("\n");
+
+    let other_format = match preferences.time_format() {
+        TimeFormat::H12 => TimeFormat::H24,
+        TimeFormat::H24 => TimeFormat::H12,
+    };
+    let (other_weekdays, weekdays_label) = match preferences.weekday_mode() {
+        WeekdayMode::Smart => (WeekdayMode::Next, "Weekdays always mean next week"),
+        WeekdayMode::Next => (WeekdayMode::Smart, "Weekdays can mean today"),
+    };
+    let other_style = match preferences.list_style() {
+        ListStyle::Compact => ListStyle::Verbose,
+        ListStyle::Verbose => ListStyle::Compact,
+    };
+    let (natural, natural_label) = match preferences.natural {
+        true => ("off", "Turn off natural language"),
+        false => ("on", "Turn on natural language"),
+    };
+    let buttons = vec![
+        CreateButton::new("prefs:timezone").label("Change timezone"),
+        CreateButton::new(format!("prefs:timeformat:{other_format}"))
+            .label(format!("Use {other_format} time")),
+        CreateButton::new(format!("prefs:weekdays:{other_weekdays}")).label(weekdays_label),
+        CreateButton::new(format!("prefs:liststyle:{other_style}"))
+            .label(format!("Use {other_style} lists")),
+        CreateButton::new(format!("prefs:natural:{natural}")).label(natural_label),
+    ];
+    // Only the settings with a few fixed choices get components, the rest take arguments
+    let options = [Language::En, Language::Fr, Language::Es, Language::De]
+        .into_iter()
+        .map(|language| {
+            CreateSelectMenuOption::new(language.to_string(), language.code())
+                .default_selection(language == preferences.language())
+        })
+        .collect();
+    let languages =
+        CreateSelectMenu::new("prefs:language", CreateSelectMenuKind::String { options });
+
+    Reply {
+        components: vec![
+            CreateActionRow::Buttons(buttons),
+            CreateActionRow::SelectMenu(languages),
+        ],
+        ..content.into()
+    }
+}
+
+/// How a reply reads, so every frontend can style it the same way.
+#[derive(Clone, Copy, PartialEq)]
+enum ReplyKind {
+    Success,
+    /// Done, but with something the user should know
+    Warning,
+    Error,
+}
+
+pub struct Reply {
+    content: String,
+    components: Vec<CreateActionRow>,
+    kind: ReplyKind,
+    embed: Option<CreateEmbed>,
+    files: Vec<CreateAttachment>,
+}
+
+impl Reply {
+    fn warning(content: impl Into<String>) -> Reply {
+        Reply {
+            kind: ReplyKind::Warning,
+            ..content.into().into()
+        }
+    }
+
+    fn error(content: impl Into<String>) -> Reply {
+        Reply {
+            kind: ReplyKind::Error,
+            ..content.into().into()
+        }
+    }
+
+    /// The content with a marker for its kind, for places that can only show text.
+    fn text(&self) -> String {
+        match self.kind {
+            ReplyKind::Success => self.content.clone(),
+            ReplyKind::Warning => format!("⚠️ {}", self.content),
+            ReplyKind::Error => format!("❌ {}", self.content),
+        }
+    }
+
+    /// The content and embed to send. Errors are shown as a red embed unless they carry their own.
+    fn render(self) -> (String, Option<CreateEmbed>, Vec<CreateActionRow>) {
+        let colour = match self.kind {
+            ReplyKind::Success => None,
+            ReplyKind::Warning => Some(Colour::GOLD),
+            ReplyKind::Error => Some(Colour::RED),
+        };
+        let mut text = self.text();
+        // Several commands' replies combined, or a digest put in front, can outgrow Discord's limit
+        if let Some((end, _)) = text.char_indices().nth(MAX_REPLY_LENGTH - TRUNCATED.len()) {
+            text.truncate(end);
+            text.push_str(TRUNCATED);
+        }
+        let (content, embed) = match (self.kind, self.embed) {
+            (ReplyKind::Error, None) => (String::new(), Some(CreateEmbed::new().description(text))),
+            (_, embed) => (text, embed),
+        };
+        let embed = match colour {
+            Some(colour) => embed.map(|embed| embed.colour(colour)),
+            None => embed,
+        };
+        (content, embed, self.components)
+    }
+
+    fn combine(mut replies: Vec<Reply>) -> Reply {
+        if replies.len() == 1 {
+            return replies.remove(0);
+        }
+        // Each part keeps its own marker, the whole is neither a success nor a failure
+        let content = replies
+            .iter()
+            .enumerate()
+            .map(|(i, reply)| format!("{}. {}", i + 1, reply.text()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut components = vec![];
+        let mut files = vec![];
+        for reply in replies {
+            components.extend(reply.components);
+            files.extend(reply.files);
+        }
+        components.truncate(5);
+        Reply {
+            components,
+            files,
+            ..content.into()
+        }
+    }
+
+    fn into_message(mut self) -> CreateMessage {
+        let files = std::mem::take(&mut self.files);
+        let (content, embed, components) = self.render();
+        CreateMessage::new()
+            .content(content)
+            .embeds(embed.into_iter().collect())
+            .components(components)
+            .add_files(files)
+    }
+
+    fn into_interaction_message(mut self) -> CreateInteractionResponseMessage {
+        let files = std::mem::take(&mut self.files);
+        let (content, embed, components) = self.render();
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .embeds(embed.into_iter().collect())
+            .components(components)
+            .add_files(files)
+    }
+}
+
+impl From<String> for Reply {
+    fn from(content: String) -> Self {
+        Reply {
+            content,
+            components: vec![],
+            kind: ReplyKind::Success,
+            embed: None,
+            files: vec![],
+        }
+    }
+}
+
+impl From<&str> for Reply {
+    fn from(content: &str) -> Self {
+        content.to_string().into()
+    }
+}
+
+#[derive(Error, Debug)]
+enum CommandError {
+    #[error("Invalid reminder ID: {0}")]
+    InvalidID(u64),
+    #[error("Reminder #{0} doesn't repeat, cancel it with `$cr {0}` instead")]
+    NotRecurring(u64),
+    #[error("Time parsing error: {0}")]
+    Jiff(#[from] jiff::Error),
+    #[error("{0}")]
+    Timezone(#[from] timezone::TimezoneError),
+    #[error("{0}")]
+    Cron(#[from] cron::CronError),
+    #[error("{0}")]
+    RRule(#[from] rrule::RRuleError),
+    #[error("{0}, keeping the current config")]
+    Config(#[from] config::ConfigError),
+    #[error("This command can only be used in a server")]
+    NotInGuild,
+    #[error("You need the Manage Server permission to use this command")]
+    MissingPermissions,
+    #[error("You need the Manage Messages permission to use this command")]
+    MissingModeratorPermissions,
+    #[error("This command is only available to the bot operator")]
+    NotOperator,
+    #[error("No help for '{0}'. Topics: {topics}", topics = help::topic_names())]
+    UnknownHelpTopic(String),
+    #[error("Reminder message can't be empty")]
+    EmptyMessage,
+    #[error("Reminder message is too long ({0} characters, the maximum is {MAX_MESSAGE_LENGTH})")]
+    MessageTooLong(usize),
+    #[error("Unknown holiday calendar '{0}'. Available: {codes}", codes = holidays::country_codes())]
+    UnknownCountry(String),
+    #[error("Cron and RRULE intervals can't be edited, set a new one with `$si {0} ...`")]
+    NotEditable(u64),
+    #[error("Reminder #{0}'s interval has no {1} to remove")]
+    NoSuchModifier(u64, interval::ModifierKind),
+    #[error("That isn't a valid channel, mention it like #general")]
+    InvalidChannel,
+    #[error("You can't send messages in <#{0}>, or I can't see it")]
+    CannotPostIn(ChannelId),
+    #[error("`!tts` only works for messages posted in a channel with `$say`")]
+    TtsNeedsChannel,
+    #[error(
+        "Both you and I need the Send Text-to-Speech Messages permission in <#{0}> for `!tts`"
+    )]
+    CannotTts(ChannelId),
+    #[error("Only messages posted in a channel with `$say` can ping a role")]
+    CalloutNeedsChannel,
+    #[error("That isn't a role in the channel's server, mention it like @raiders")]
+    InvalidRole,
+    #[error("'{0}' can't be mentioned by everyone, so both you and I need the Mention All Roles permission there")]
+    CannotCallOut(String),
+    #[error("That isn't someone you can transfer a reminder to")]
+    InvalidRecipient,
+    #[error("Scheduled channel messages can't be transferred")]
+    NotTransferable,
+    #[error("I couldn't DM <@{0}>, they may have DMs from server members turned off")]
+    CannotMessage(UserId),
+    #[error("You aren't on a shared list called {0}")]
+    UnknownList(String),
+    #[error("You're already on a shared list called {0}")]
+    ListExists(String),
+    #[error("You haven't been invited to a shared list called {0}")]
+    NotInvited(String),
+    #[error("Only whoever scheduled that reminder or the owner of {0} can change it")]
+    NotListReminderCreator(String),
+    #[error("Only the owner of {0} can do that")]
+    NotListOwner(String),
+    #[error("You don't have an offset called {0}")]
+    UnknownOffset(String),
+    #[error("You don't have a sequence called {0}")]
+    UnknownSequence(String),
+    #[error("You already have a sequence called {0}")]
+    SequenceExists(String),
+    #[error("The first step of a sequence needs exactly one time")]
+    SequenceStart,
+    #[error("You own {0}, delete it with `$list delete {0}` instead")]
+    OwnerCannotLeave(String),
+    #[error("Reminder #{0} isn't public")]
+    NotPublic(u64),
+    #[error("You can't follow your own reminder")]
+    FollowingSelf,
+    #[error("{0} has already passed, pick a time in the future")]
+    InPast(String),
+    #[error("{0} is more than {1} years away, use `$prefs horizon <years>` to allow it")]
+    BeyondHorizon(String, i16),
+    #[error("The horizon must be between 1 and {MAX_HORIZON_YEARS} years")]
+    InvalidHorizon,
+    #[error("{0} isn't a month, use 1 to 12")]
+    InvalidMonth(u64),
+    #[error("{0}, {1} isn't a location, latitude must be between -90 and 90 and longitude between -180 and 180")]
+    InvalidLocation(f64, f64),
+    #[error("The Telegram bridge isn't set up on this bot")]
+    TelegramDisabled,
+    #[error("The dashboard isn't set up on this bot")]
+    DashboardDisabled,
+    #[error("Digests are turned off on this bot")]
+    DigestsDisabled,
+    #[error("Matrix isn't set up on this bot")]
+    MatrixDisabled,
+    #[error(
+        "Set up Matrix in a DM with me so your access token stays private, and delete that message"
+    )]
+    MatrixInGuild,
+    #[error("The homeserver must be an https:// URL")]
+    InvalidHomeserver,
+    #[error("Couldn't post to that Matrix room: {0}")]
+    Matrix(String),
+    #[error("Google Calendar isn't set up on this bot")]
+    CalendarDisabled,
+    #[error("Send the code in a DM with me so it stays private, and delete that message")]
+    CalendarInGuild,
+    #[error("Link your Google Calendar first with `$cal link`")]
+    CalendarNotLinked,
+    #[error("Google Calendar error: {0}")]
+    Calendar(String),
+    #[error("Task apps aren't set up on this bot")]
+    TasksDisabled,
+    #[error("Link task apps in a DM with me so your token stays private, and delete that message")]
+    TasksInGuild,
+    #[error("You haven't linked {0}, see `$help tasks`")]
+    TasksNotLinked(ProviderKind),
+    #[error("{0} error: {1}")]
+    Tasks(ProviderKind, String),
+    #[error("Couldn't watch <{0}>, {1}")]
+    InvalidFeed(String, String),
+    #[error("Set your location with `$location <latitude> <longitude>` to use weather conditions")]
+    ConditionNeedsLocation,
+    #[error("Presence tracking isn't enabled on this bot")]
+    PresenceDisabled,
+    #[error("The page to check must be an http or https URL")]
+    InvalidConditionUrl,
+    #[error("You can have up to {0} trigger reminders, cancel one with `$triggers cancel <id>`")]
+    TooManyTriggers(usize),
+    #[error("Reply to a message or give its link to bookmark it")]
+    BookmarkNeedsMessage,
+    #[error("You can have up to {0} bookmarks, remove one with `$bookmarks remove <id>`")]
+    TooManyBookmarks(usize),
+    #[error("Reminders can have up to {0} notes")]
+    TooManyNotes(usize),
+    #[error(
+        "Copies can be spread over at most {} seconds",
+        delivery::MAX_JITTER_SECS
+    )]
+    InvalidJitter,
+    #[error("Deliveries can be deleted after anywhere from 1 minute to 30 days")]
+    InvalidExpiry,
+    #[error("Reminder #{0} is already marked done until it's sent again")]
+    AlreadyDone(u64),
+    #[error("Attach a CSV file with time and message columns to `$import csv`")]
+    ImportNeedsFile,
+    #[error(
+        "Imports can be up to {} KiB and {} rows",
+        import::MAX_IMPORT_BYTES / 1024,
+        import::MAX_IMPORT_ROWS
+    )]
+    ImportTooLarge,
+    #[error("The file needs a header row with a time (or date) column and a message column")]
+    ImportMissingColumns,
+    #[error("{0}")]
+    InvalidTemplate(String),
+    #[error("Templates can be up to {max} characters, that one is {0}", max = template::MAX_TEMPLATE_LENGTH)]
+    TemplateTooLong(usize),
+    #[error("You can watch up to {0} feeds, remove one with `$unwatch <id>`")]
+    TooManyWatches(usize),
+    #[error("Discord error: {0}")]
+    Serenity(Box<serenity::Error>),
+}
+
+impl From<serenity::Error> for CommandError {
+    fn from(err: serenity::Error) -> Self {
+        CommandError::Serenity(Box::new(err))
+    }
+}
+
+/// Upper bound on occurrences listed by `$simulate`, to stay within one message.
+const MAX_SIMULATED: u64 = 25;
+/// Deliveries shown by `$history`, most recent first.
+const HISTORY_LENGTH: usize = 10;
+/// Entries shown by `$audit`, most recent first.
+const AUDIT_LENGTH: usize = 15;
+const DELIVERY_PREFIX: &str = "Reminder: ";
+const MAX_REPLY_LENGTH: usize = 2000;
+const TRUNCATED: &str = "\n... (reply cut short)";
+const MAX_MESSAGE_LENGTH: usize = 2000 - DELIVERY_PREFIX.len();
+const MAX_NOTES: usize = 10;
+/// `$ping` warns when the scheduler hasn't finished a pass for this long, it runs every second.
+const SCHEDULER_LAG: Duration = Duration::from_secs(10);
+
+fn validate_message(message: &str) -> Result<String, CommandError> {
+    let message: String = message
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect();
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(CommandError::EmptyMessage);
+    }
+    let length = message.chars().count();
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(CommandError::MessageTooLong(length));
+    }
+    Ok(message.to_string())
+}
+
+/// Absolute dates keep the current clock time, so they can easily land in the past and fire at once.
+/// Times within the last minute are let through, "now" has moved on a little since parsing.
+fn validate_times(times: &[Zoned], preferences: &Preferences) -> Result<(), CommandError> {
+    let format = preferences.time_format();
+    let cutoff = Zoned::now().saturating_sub(Span::new().minutes(1));
+    if let Some(time) = times.iter().find(|time| **time < cutoff) {
+        return Err(CommandError::InPast(format_time(time, format)));
+    }
+    // Far off times are almost always typos, and would pile up at the end of the list
+    let horizon = preferences.horizon();
+    let limit = Zoned::now().saturating_add(Span::new().years(horizon));
+    match times.iter().find(|time| **time > limit) {
+        Some(time) => Err(CommandError::BeyondHorizon(
+            format_time(time, format),
+            horizon,
+        )),
+        None => Ok(()),
+    }
+}
+
+async fn handle_command(
+    ctx: &Context,
+    msg: &Message,
+    command: Command,
+) -> Result<Reply, CommandError> {
+    let user = msg.author.id;
+    use CommandError::*;
+    usage::record(Outcome::Parsed(command.kind())).await;
+    if command.requires_admin() {
+        msg.guild_id.ok_or(NotInGuild)?;
+        let permissions = guild::channel_permissions(ctx, msg.channel_id, user).await?;
+        if !permissions.manage_guild() {
+            return Err(MissingPermissions);
+        }
+    }
+
+    if command.requires_moderator() {
+        msg.guild_id.ok_or(NotInGuild)?;
+        let permissions = guild::channel_permissions(ctx, msg.channel_id, user).await?;
+        if !permissions.manage_messages() {
+            return Err(MissingModeratorPermissions);
+        }
+    }
+
+    if command.requires_operator() && !config().await.admins.contains(&user) {
+        return Err(NotOperator);
+    }
+
+    let preferences = get_preferences(user, msg.guild_id).await;
+    let mut cache = REMINDERS.lock().await;
+    match command {
+        Command::ScheduleReminder(times, condition, message, channel) => {
+            // Checking channel permissions can hit the API, so it happens without the lock
+            drop(cache);
+            let (message, tts) = emphasis::take_tts(&message);
+            let message = validate_message(&message)?;
+            match condition.as_ref().map(|guard| &guard.check) {
+                Some(Check::Weather(_)) if preferences.location.is_none() => {
+                    return Err(ConditionNeedsLocation);
+                }
+                Some(Check::Online(_)) if !config().await.features.presence => {
+                    return Err(PresenceDisabled);
+                }
+                Some(Check::Url(url))
+                    if !url.0.starts_with("https://") && !url.0.starts_with("http://") =>
+                {
+                    return Err(InvalidConditionUrl);
+                }
+                _ => {}
+            }
+            validate_times(&times, &preferences)?;
+            let channel = match channel {
+                Some(0) => return Err(InvalidChannel),
+                channel => channel.map(ChannelId::new),
+            };
+            if let Some(channel) = channel {
+                let permissions = guild::channel_permissions(ctx, channel, user)
+                    .await
+                    .unwrap_or_default();
+                if !permissions.view_channel() || !permissions.send_messages() {
+                    return Err(CannotPostIn(channel));
+                }
+                if tts {
+                    let bot = ctx.cache.current_user().id;
+                    let own = guild::channel_permissions(ctx, channel, bot)
+                        .await
+                        .unwrap_or_default();
+                    if !permissions.send_tts_messages() || !own.send_tts_messages() {
+                        return Err(CannotTts(channel));
+                    }
+                }
+            } else if tts {
+                return Err(TtsNeedsChannel);
+            }
+            let attachments: Vec<_> = msg
+                .attachments
+                .iter()
+                .map(|a| Attachment {
+                    filename: a.filename.clone(),
+                    url: a.url.clone(),
+                })
+                .collect();
+            let source = (!attachments.is_empty()).then_some(MessageSource {
+                channel: msg.channel_id,
+                message: msg.id,
+            });
+            let mut cache = REMINDERS.lock().await;
+            let list = cache.entry(user).or_default();
+
+            let mut lines = vec![];
+            let mut keys = vec![];
+
+            for time in times {
+                let reminder = Reminder {
+                    attachments: attachments.clone(),
+                    source: source.clone(),
+                    channel,
+                    condition: condition.clone(),
+                    tts,
+                    ..Reminder::new(time.clone(), message.clone())
+                };
+                let source = if channel.is_some() { "$say" } else { "$r" };
+                audit::record(user, &reminder, Action::Created, source).await;
+                keys.push(reminder.key);
+
+                list.push(reminder);
+                list.sort_by(|a, b| a.time.cmp(&b.time));
+                stats::record(user, |stats| stats.scheduled += 1).await;
+
+                let id = list
+                    .iter()
+                    .enumerate()
+                    .find(|(_, e)| e.time == time && e.message == message)
+                    .map(|(i, _)| i)
+                    .expect("Reminder was not inserted");
+
+                let kind = match channel {
+                    Some(channel) => format!("message in <#{channel}>"),
+                    None => "reminder".into(),
+                };
+                let until = time.duration_since(&Zoned::now()).unsigned_abs();
+                lines.push(format!(
+                    "Scheduled {kind} for {}, {} (#{id})",
+                    format_time(&time, preferences.time_format()),
+                    humanize::until(until, preferences.language())
+                ));
+            }
+
+            let reply = if preferences.timezone.is_none() && !preferences.timezone_notice_shown {
+                lines.push(format!(
+                    "\nNote: you haven't set a timezone, so {} was assumed. \
+                    Use `$tz <timezone>` (e.g. `$tz Europe/London`, `$tz PT` or `$tz UTC+2`) to set yours.",
+                    preferences.timezone()
+                ));
+                set_preferences(user, |prefs| prefs.timezone_notice_shown = true).await;
+                Reply::warning(lines.join("\n"))
+            } else {
+                lines.join("\n").into()
+            };
+
+            save();
+            Ok(Reply {
+                components: undo::button(user, keys).await.into_iter().collect(),
+                ..reply
+            })
+        }
+        Command::ScheduleAfter(id, offset, message) => {
+            let message = validate_message(&message)?;
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reference = list.get(id as usize).ok_or(InvalidID(id))?;
+            let after = Dependency {
+                key: reference.key,
+                offset,
+            };
+            let time = after.time_after(&reference.time);
+            validate_times(std::slice::from_ref(&time), &preferences)?;
+            let response = format!(
+                "Scheduled reminder for {}, {} after '{}'. It moves along if that one is skipped",
+                format_time(&time, preferences.time_format()),
+                humanize::duration(Duration::from_millis(offset), preferences.language()),
+                &reference.message
+            );
+            let reminder = Reminder {
+                after: Some(after),
+                ..Reminder::new(time, message)
+            };
+            audit::record(user, &reminder, Action::Created, "$r after").await;
+            list.push(reminder);
+            list.sort_by(|a, b| a.time.cmp(&b.time));
+            stats::record(user, |stats| stats.scheduled += 1).await;
+            save();
+            Ok(response.into())
+        }
+        Command::ScheduleShared(name, times, message) => {
+            shared::schedule(user, name, times, message, &preferences).await
+        }
+        Command::SharedList(command) => {
+            shared::handle(&ctx.http, user, command, &preferences).await
+        }
+        Command::Telegram(command) => telegram::handle(user, command).await,
+        Command::Matrix(command) => matrix::handle(user, command, msg.guild_id.is_none()).await,
+        Command::Dashboard => dashboard::link().await,
+        Command::Feed(command) => feeds::handle(user, command).await,
+        Command::ScheduleTrigger(trigger, message) => {
+            let guild = msg.guild_id.ok_or(NotInGuild)?;
+            trigger::schedule(user, guild, trigger, message).await
+        }
+        Command::Trigger(command) => trigger::handle(user, command).await,
+        Command::Tasks(command) => {
+            // Task apps can be slow to answer, which shouldn't hold up everyone else's reminders
+            drop(cache);
+            tasks::handle(user, command, msg.guild_id.is_none()).await
+        }
+        Command::Calendar(command) => {
+            // Linking waits on Google, which shouldn't hold up everyone else's reminders
+            drop(cache);
+            calendar::handle(user, command, msg.guild_id.is_none()).await
+        }
+        Command::Bookmark(command) => {
+            let list = cache.entry(user).or_default();
+            let replied = MessageLink::replied_to(msg);
+            bookmarks::handle(user, command, replied, list, &preferences).await
+        }
+        Command::Sequence(command) => {
+            let list = cache.entry(user).or_default();
+            sequence::handle(user, command, list, &preferences).await
+        }
+        Command::CancelReminder(id) => {
+            let list = cache.get_mut(&user);
+            if let Some(list) = list.filter(|l| l.len() > id as usize) {
+                let reminder = list.remove(id as usize);
+                audit::record(user, &reminder, Action::Cancelled, "$cr").await;
+                stats::record(user, |stats| stats.cancelled += 1).await;
+                save();
+                Ok(format!("Removed reminder '{}'", reminder.message).into())
+            } else {
+                Err(InvalidID(id))
+            }
+        }
+        Command::SetInterval(id, time_modifiers) => {
+            let reminder = cache
+                .get(&user)
+                .and_then(|list| list.get(id as usize))
+                .ok_or(InvalidID(id))?;
+            preview::interval(user, id, reminder, time_modifiers, &preferences).await
+        }
+        Command::EditInterval(id, edit) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            let mut interval = reminder.interval.clone().unwrap_or_default();
+            if interval
+                .iter()
+                .any(|m| matches!(m, TimeModifier::Cron(_) | TimeModifier::RRule(_)))
+            {
+                return Err(NotEditable(id));
+            }
+            match edit {
+                IntervalEdit::Add(modifiers) => interval.extend(modifiers),
+                IntervalEdit::Remove(_) if interval.is_empty() => return Err(NotRecurring(id)),
+                IntervalEdit::Remove(kind) => {
+                    let before = interval.len();
+                    interval.retain(|m| !kind.matches(m));
+                    if interval.len() == before {
+                        return Err(NoSuchModifier(id, kind));
+                    }
+                }
+            }
+            reminder.interval = (!interval.is_empty()).then_some(interval);
+            audit::record(user, reminder, Action::Edited, "$si").await;
+            save();
+            Ok(match &reminder.interval {
+                Some(interval) => format!(
+                    "Reminder '{}' (#{id}) now repeats {}",
+                    &reminder.message,
+                    interval::describe(interval, preferences.time_format())
+                ),
+                None => format!("Reminder '{}' (#{id}) no longer repeats", &reminder.message),
+            }
+            .into())
+        }
+        Command::SetRRuleInterval(id, rule) => {
+            let rule = rrule::RRule::parse(&rule)?;
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            let response = format!(
+                "Set interval for reminder '{}' (#{id}) to RRULE:{rule}",
+                &reminder.message
+            );
+            reminder.interval = Some(vec![TimeModifier::RRule(rule)]);
+            audit::record(user, reminder, Action::Edited, "$si").await;
+            save();
+            Ok(response.into())
+        }
+        Command::SetCronInterval(id, expression) => {
+            let schedule = CronSchedule::parse(&expression)?;
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.interval = Some(vec![TimeModifier::Cron(schedule)]);
+            let next = reminder.next_occurrence(&preferences.holidays)?;
+            audit::record(user, reminder, Action::Edited, "$si").await;
+            save();
+            let mut response = format!(
+                "Set cron interval for reminder '{}' (#{id})",
+                &reminder.message
+            );
+            if let Some(next) = next {
+                response.push_str(&format!(
+                    ", after this it repeats at {}",
+                    format_time(&next, preferences.time_format())
+                ));
+            }
+            Ok(response.into())
+        }
+        Command::AddNote(id, note) => {
+            let note = validate_message(&note)?;
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            if reminder.notes.len() >= MAX_NOTES {
+                return Err(TooManyNotes(MAX_NOTES));
+            }
+            let length = reminder.message.chars().count()
+                + reminder.notes_text().chars().count()
+                + note.chars().count()
+                + 3;
+            if length > MAX_MESSAGE_LENGTH {
+                return Err(MessageTooLong(length));
+            }
+            reminder.notes.push(note);
+            audit::record(user, reminder, Action::Edited, "$note").await;
+            save();
+            Ok(format!(
+                "Added a note to '{}', it has {} now",
+                &reminder.message,
+                reminder.notes.len()
+            )
+            .into())
+        }
+        Command::SetJitter(id, jitter) => {
+            let window = delivery::describe_jitter(jitter)?;
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.jitter = jitter;
+            audit::record(user, reminder, Action::Edited, "$jitter").await;
+            save();
+            Ok(format!(
+                "Copies of '{}' for its {} followers are now {window}",
+                &reminder.message,
+                reminder.followers.len()
+            )
+            .into())
+        }
+        Command::SetExpiry(id, lifetime) => {
+            if let Some(lifetime) = lifetime.filter(|lifetime| *lifetime > 0) {
+                expiry::validate(lifetime)?;
+            }
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.expire = lifetime;
+            audit::record(user, reminder, Action::Edited, "$expire").await;
+            save();
+            Ok(match lifetime {
+                None => format!(
+                    "Deliveries of '{}' now follow your default, see `$prefs`",
+                    &reminder.message
+                ),
+                Some(0) => format!("Deliveries of '{}' will be kept", &reminder.message),
+                Some(lifetime) => format!(
+                    "Deliveries of '{}' will be deleted {} after they're sent",
+                    &reminder.message,
+                    humanize::duration(Duration::from_millis(lifetime), preferences.language())
+                ),
+            }
+            .into())
+        }
+        Command::SetDefaultExpiry(lifetime) => {
+            if let Some(lifetime) = lifetime {
+                expiry::validate(lifetime)?;
+            }
+            set_preferences(user, |prefs| prefs.expire_after = lifetime).await;
+            Ok(match lifetime {
+                Some(lifetime) => format!(
+                    "Delivered reminders will be deleted {} after they're sent, `$expire <id> keep` keeps one",
+                    humanize::duration(Duration::from_millis(lifetime), preferences.language())
+                ),
+                None => "Delivered reminders will be kept".to_string(),
+            }
+            .into())
+        }
+        Command::Done(id) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get(id as usize).ok_or(InvalidID(id))?;
+            let (key, recurring) = (reminder.key, reminder.interval.is_some());
+            // A one-off done before it fires has nothing left to remind about
+            let message = if recurring {
+                reminder.message.clone()
+            } else {
+                list.remove(id as usize).message
+            };
+            let response = completion::complete(user, key, &message, recurring)
+                .await
+                .map_err(|_| AlreadyDone(id))?;
+            save();
+            Ok(response.into())
+        }
+        Command::SetHabit(id, enabled) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            if enabled && reminder.interval.is_none() {
+                return Err(NotRecurring(id));
+            }
+            reminder.habit = enabled;
+            audit::record(user, reminder, Action::Edited, "$habit").await;
+            save();
+            Ok(if enabled {
+                let stats = stats::get_stats(user).await;
+                format!(
+                    "'{}' (#{id}) is now a habit, mark it done each time to build a streak ({})",
+                    &reminder.message,
+                    habit::describe(stats.streaks.get(&reminder.key))
+                )
+            } else {
+                format!("'{}' (#{id}) is no longer a habit", &reminder.message)
+            }
+            .into())
+        }
+        Command::SetEmphasis(id, emphasis) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.emphasis = emphasis;
+            audit::record(user, reminder, Action::Edited, "$emphasis").await;
+            save();
+            Ok(match emphasis {
+                Emphasis::Quiet => format!("'{}' (#{id}) will be delivered silently", &reminder.message),
+                Emphasis::Normal => format!("'{}' (#{id}) will be delivered as usual", &reminder.message),
+                Emphasis::Urgent => format!(
+                    "'{}' (#{id}) will mention you, be read aloud and ping again until it's marked done",
+                    &reminder.message
+                ),
+            }
+            .into())
+        }
+        Command::SetCallout(id, role) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            let Some(role) = role else {
+                reminder.callout = None;
+                audit::record(user, reminder, Action::Edited, "$callout").await;
+                save();
+                return Ok(format!("'{}' (#{id}) won't ping a role", &reminder.message).into());
+            };
+            let channel = reminder.channel.ok_or(CalloutNeedsChannel)?;
+            if role == 0 {
+                return Err(InvalidRole);
+            }
+            let role = RoleId::new(role);
+            let name = callout::validate(ctx, user, channel, role).await?;
+            reminder.callout = Some(role);
+            audit::record(user, reminder, Action::Edited, "$callout").await;
+            save();
+            Ok(format!(
+                "'{}' (#{id}) will ping @{name} when it's posted in <#{channel}>",
+                &reminder.message
+            )
+            .into())
+        }
+        Command::SetNextUp(enabled) => {
+            let list = cache.get(&user).map(Vec::as_slice).unwrap_or_default();
+            pinned::set(&ctx.http, user, enabled, list, &preferences).await
+        }
+        Command::SetReview(enabled) => {
+            if enabled && !config().await.features.digests {
+                return Err(DigestsDisabled);
+            }
+            set_preferences(user, |prefs| prefs.weekly_review = enabled).await;
+            Ok(match enabled {
+                true => format!(
+                    "You'll get a review of your week every Sunday at {}:00 ({})",
+                    review::REVIEW_HOUR,
+                    preferences.timezone()
+                ),
+                false => "Weekly reviews turned off".to_string(),
+            }
+            .into())
+        }
+        Command::ImportCsv => import::preview(user, msg.attachments.first(), &preferences).await,
+        Command::ExportCsv => {
+            let list = cache.get(&user).map(Vec::as_slice).unwrap_or_default();
+            if list.is_empty() {
+                return Ok("No reminders".into());
+            }
+            Ok(Reply {
+                files: vec![export::csv(list, &preferences)],
+                ..format!("Exported {} reminders", list.len()).into()
+            })
+        }
+        Command::SkipOccurrence(id) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            let next = reminder
+                .next_occurrence(&preferences.holidays)?
+                .ok_or(NotRecurring(id))?;
+            let response = format!(
+                "Skipped '{}' on {}, next reminder is {}",
+                &reminder.message,
+                format_time(&reminder.time, preferences.time_format()),
+                format_time(&next, preferences.time_format())
+            );
+            reminder.advance(next, &preferences.holidays);
+            audit::record(user, reminder, Action::Edited, "$skip").await;
+            let key = reminder.key;
+            dependency::shift(list, key);
+            save();
+            Ok(response.into())
+        }
+        Command::Simulate(id, count) => {
+            let reminder = cache
+                .get(&user)
+                .and_then(|list| list.get(id as usize))
+                .ok_or(InvalidID(id))?;
+            if reminder.interval.is_none() {
+                return Err(NotRecurring(id));
+            }
+            let count = count.clamp(1, MAX_SIMULATED) as usize;
+            let times = reminder.occurrences(&preferences.holidays, count)?;
+            let mut lines = vec![format!(
+                "Next {} occurrences of '{}':",
+                times.len(),
+                &reminder.message
+            )];
+            for (n, time) in times.iter().enumerate() {
+                lines.push(format!(
+                    "{}. {}",
+                    n + 1,
+                    format_time(time, preferences.time_format())
+                ));
+            }
+            if times.len() < count {
+                lines.push("The reminder stops repeating after this".into());
+            }
+            Ok(lines.join("\n").into())
+        }
+        Command::SkipHolidays(id, mode) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.skip_holidays = mode != HolidayMode::Send;
+            reminder.roll_holidays = mode == HolidayMode::Roll;
+            audit::record(user, reminder, Action::Edited, "$sh").await;
+            save();
+            let mut response = match mode {
+                HolidayMode::Send => {
+                    format!(
+                        "Reminder '{}' (#{id}) will no longer skip holidays",
+                        &reminder.message
+                    )
+                }
+                HolidayMode::Skip => {
+                    format!(
+                        "Reminder '{}' (#{id}) will skip holidays",
+                        &reminder.message
+                    )
+                }
+                HolidayMode::Roll => format!(
+                    "Reminder '{}' (#{id}) will move to the next working day on holidays",
+                    &reminder.message
+                ),
+            };
+            if mode != HolidayMode::Send && preferences.holidays.is_empty() {
+                response.push_str(
+                    "\nNote: you don't have a holiday calendar yet, set one with `$holidays <country>`",
+                );
+            }
+            Ok(response.into())
+        }
+        Command::Holidays(command) => {
+            let calendar = &preferences.holidays;
+            let response = match command {
+                HolidayCommand::Show => {
+                    let country = match &calendar.country {
+                        Some(code) => format!(
+                            "{} ({code})",
+                            holidays::country_name(code).unwrap_or("Unknown")
+                        ),
+                        None => "none".into(),
+                    };
+                    let mut lines = vec![
+                        format!("Holiday calendar: {country}"),
+                        format!("Custom holidays: {}", calendar.custom.len()),
+                    ];
+                    let today = Zoned::now().with_time_zone(preferences.time_zone()).date();
+                    let upcoming = calendar.upcoming(today, 5);
+                    if !upcoming.is_empty() {
+                        lines.push("Upcoming:".into());
+                        for (day, name) in upcoming {
+                            lines.push(format!("{} - {name}", day.strftime("%A, %B %d, %Y")));
+                        }
+                    }
+                    return Ok(lines.join("\n").into());
+                }
+                HolidayCommand::SetCountry(country) => {
+                    let code = country.trim().to_uppercase();
+                    let name = holidays::country_name(&code).ok_or(UnknownCountry(country))?;
+                    set_preferences(user, |prefs| prefs.holidays.country = Some(code)).await;
+                    format!("Holiday calendar set to {name}")
+                }
+                HolidayCommand::Clear => {
+                    set_preferences(user, |prefs| prefs.holidays.country = None).await;
+                    "Holiday calendar cleared".into()
+                }
+                HolidayCommand::Add(day) => {
+                    set_preferences(user, |prefs| {
+                        if !prefs.holidays.custom.contains(&day) {
+                            prefs.holidays.custom.push(day);
+                            prefs.holidays.custom.sort();
+                        }
+                    })
+                    .await;
+                    format!("Added {day} as a holiday")
+                }
+                HolidayCommand::Remove(day) => {
+                    set_preferences(user, |prefs| prefs.holidays.custom.retain(|d| *d != day))
+                        .await;
+                    format!("Removed {day} from your holidays")
+                }
+            };
+            Ok(response.into())
+        }
+        Command::Transfer(id, recipient) => {
+            let reminder = cache
+                .get(&user)
+                .and_then(|list| list.get(id as usize))
+                .ok_or(InvalidID(id))?;
+            if reminder.channel.is_some() {
+                return Err(NotTransferable);
+            }
+            let (key, message, time) = (
+                reminder.key,
+                reminder.message.clone(),
+                reminder.time.clone(),
+            );
+            drop(cache);
+            let recipient = match recipient {
+                0 => return Err(InvalidRecipient),
+                recipient => UserId::new(recipient),
+            };
+            let target = recipient.to_user(ctx).await.map_err(|_| InvalidRecipient)?;
+            if recipient == user || target.bot {
+                return Err(InvalidRecipient);
+            }
+
+            let format = get_preferences(recipient, None).await.time_format();
+            let content = format!(
+                "<@{user}> wants to hand you their reminder '{message}' for {}",
+                format_time(&time, format)
+            );
+            let offer_message = CreateMessage::new()
+                .content(content)
+                .components(vec![transfer::offer(user, recipient, key).await]);
+            let sent = delivery::send(&ctx.http, Recipient::User(recipient), offer_message).await;
+            if sent.is_err() {
+                // Nobody can see the buttons, so the offer would only linger until it expires
+                transfer::withdraw(user, key).await;
+                return Err(CannotMessage(recipient));
+            }
+            Ok(format!("Asked <@{recipient}> to accept reminder '{message}' (#{id})").into())
+        }
+        Command::SetPublic(id, public) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.public = public;
+            audit::record(user, reminder, Action::Edited, "$public").await;
+            let mut response = if public {
+                format!(
+                    "Reminder '{}' is now public, others can follow it with `$follow <@{user}> {id}`",
+                    &reminder.message
+                )
+            } else {
+                format!("Reminder '{}' is no longer public", &reminder.message)
+            };
+            if !public && !reminder.followers.is_empty() {
+                response.push_str(&format!(
+                    " and its {} followers were removed",
+                    reminder.followers.len()
+                ));
+                reminder.followers.clear();
+            }
+            save();
+            Ok(response.into())
+        }
+        Command::Follow(organizer, id) | Command::Unfollow(organizer, id) => {
+            let follow = matches!(command, Command::Follow(..));
+            let organizer = match organizer {
+                0 => return Err(InvalidRecipient),
+                organizer => UserId::new(organizer),
+            };
+            if organizer == user {
+                return Err(FollowingSelf);
+            }
+            let reminder = cache
+                .get_mut(&organizer)
+                .and_then(|list| list.get_mut(id as usize))
+                .ok_or(InvalidID(id))?;
+            if !reminder.public {
+                return Err(NotPublic(id));
+            }
+            reminder.followers.retain(|f| *f != user);
+            if follow {
+                reminder.followers.push(user);
+            }
+            save();
+            Ok(format!(
+                "{} '{}' by <@{organizer}>",
+                if follow { "Following" } else { "Unfollowed" },
+                &reminder.message
+            )
+            .into())
+        }
+        Command::ClearInterval(id) => {
+            let list = cache.get_mut(&user).ok_or(InvalidID(id))?;
+            let reminder = list.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.interval = None;
+            audit::record(user, reminder, Action::Edited, "$ci").await;
+            save();
+            Ok(format!(
+                "Cleared interval for reminder '{}' (#{id})",
+                &reminder.message
+            )
+            .into())
+        }
+        Command::Audit(id) => {
+            let key = match id {
+                Some(id) => Some(
+                    cache
+                        .get(&user)
+                        .and_then(|list| list.get(id as usize))
+                        .ok_or(InvalidID(id))?
+                        .key,
+                ),
+                None => None,
+            };
+            let format = preferences.time_format();
+            let lines: Vec<_> = audit::entries(user, key, AUDIT_LENGTH)
+                .await
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{} - '{}' {} by {}",
+                        format_time(&entry.time, format),
+                        &entry.message,
+                        entry.action,
+                        &entry.source
+                    )
+                })
+                .collect();
+            if lines.is_empty() {
+                return Ok("Nothing has been recorded yet".into());
+            }
+            Ok(lines.join("\n").into())
+        }
+        Command::History => {
+            let format = preferences.time_format();
+            let lines: Vec<_> = delivery::recent(user, HISTORY_LENGTH)
+                .await
+                .iter()
+                .map(|delivery| {
+                    let mut line = format!(
+                        "{} - {}: {}",
+                        format_time(&delivery.attempted, format),
+                        &delivery.message,
+                        delivery.status
+                    );
+                    if let Some(error) = &delivery.error {
+                        line.push_str(&format!(" ({error})"));
+                    }
+                    line
+                })
+                .collect();
+            if lines.is_empty() {
+                return Ok("No reminders have been delivered yet".into());
+            }
+            Ok(lines.join("\n").into())
+        }
+        Command::ListReminders(options) => {
+            let reminders = cache.get(&user).map(Vec::as_slice).unwrap_or_default();
+            let stats = stats::get_stats(user).await;
+            let lines = listing::list(reminders, &options, &preferences, &stats.streaks)?;
+            if lines.is_empty() {
+                return Ok("No reminders".into());
+            }
+            Ok(lines.join("\n").into())
+        }
+        Command::SetTimezone(timezone) => {
+            let timezone = timezone::resolve(&timezone)?;
+            let response = format!("Timezone set to {timezone}");
+            set_preferences(user, |prefs| prefs.timezone = Some(timezone)).await;
+            Ok(response.into())
+        }
+        Command::SetTimeFormat(time_format) => {
+            set_preferences(user, |prefs| prefs.time_format = Some(time_format)).await;
+            Ok("Time format set".into())
+        }
+        Command::SetFiscalYearStart(month) => {
+            let month = i8::try_from(month)
+                .ok()
+                .filter(|month| (1..=12).contains(month))
+                .ok_or(InvalidMonth(month))?;
+            set_preferences(user, |prefs| prefs.fiscal_year_start = Some(month)).await;
+            Ok(format!(
+                "Your fiscal year now starts in {}, so Q1 begins on {} 1st",
+                interval::month_name(month),
+                interval::month_name(month)
+            )
+            .into())
+        }
+        Command::SetOffset(name, ms) => {
+            let duration = format_duration(Duration::from_millis(ms));
+            let response = format!(
+                "Offset {name} set to {duration}, use it like `9am -{name}` or `2h +{name}`"
+            );
+            set_preferences(user, |prefs| {
+                prefs.offsets.insert(name, ms);
+            })
+            .await;
+            Ok(response.into())
+        }
+        Command::RemoveOffset(name) => {
+            let mut removed = false;
+            set_preferences(user, |prefs| {
+                removed = prefs.offsets.remove(&name).is_some()
+            })
+            .await;
+            if !removed {
+                return Err(UnknownOffset(name));
+            }
+            Ok(format!("Removed offset {name}").into())
+        }
+        Command::ListOffsets => {
+            if preferences.offsets.is_empty() {
+                return Ok(
+                    "You don't have any offsets, add one with `$offset set <name> <duration>`"
+                        .into(),
+                );
+            }
+            let lines: Vec<_> = preferences
+                .offsets
+                .iter()
+                .map(|(name, ms)| {
+                    format!("{name}: {}", format_duration(Duration::from_millis(*ms)))
+                })
+                .collect();
+            Ok(format!("Your offsets:\n{}", lines.join("\n")).into())
+        }
+        Command::SetLocation(latitude, longitude) => {
+            let location =
+                Location::new(latitude, longitude).ok_or(InvalidLocation(latitude, longitude))?;
+            set_preferences(user, |prefs| prefs.location = Some(location)).await;
+            Ok(format!("Location set to {location}, `sunrise` and `sunset` will use it").into())
+        }
+        Command::SetDelivery(max_hold) => {
+            if max_hold.is_some() && !config().await.features.presence {
+                return Err(PresenceDisabled);
+            }
+            set_preferences(user, |prefs| prefs.hold_offline = max_hold).await;
+            Ok(match max_hold {
+                Some(max_hold) => format!(
+                    "Reminders that fire while you're offline will wait until you're online, for up to {}",
+                    humanize::duration(Duration::from_millis(max_hold), preferences.language())
+                ),
+                None => "Reminders will be sent as soon as they fire".to_string(),
+            }
+            .into())
+        }
+        Command::ClearLocation => {
+            set_preferences(user, |prefs| prefs.location = None).await;
+            Ok("Location cleared".into())
+        }
+        Command::SetListStyle(style) => {
+            set_preferences(user, |prefs| prefs.list_style = Some(style)).await;
+            Ok(format!("`$rs` will now use the {style} style").into())
+        }
+        Command::SetWeekdayMode(mode) => {
+            set_preferences(user, |prefs| prefs.weekday_mode = Some(mode)).await;
+            Ok(match mode {
+                WeekdayMode::Smart => {
+                    "A weekday naming today now means today when a later time of day is still ahead"
+                }
+                WeekdayMode::Next => "A weekday naming today now always means next week",
+            }
+            .into())
+        }
+        Command::ShowPreferences => Ok(preferences_reply(user, msg.guild_id).await),
+        Command::SetNatural(natural) => {
+            set_preferences(user, |prefs| prefs.natural = natural).await;
+            Ok(match natural {
+                true => "`$r` times are now read as natural language first, like `$r the day after tomorrow at noon; x`",
+                false => "`$r` times now use the strict syntax, `$r nl` still reads natural language",
+            }
+            .into())
+        }
+        Command::SetHorizon(years) => {
+            let horizon = match years {
+                Some(years @ 1..=MAX_HORIZON_YEARS) => Some(years as i16),
+                Some(_) => return Err(InvalidHorizon),
+                None => None,
+            };
+            set_preferences(user, |prefs| prefs.horizon = horizon).await;
+            let years = horizon.unwrap_or(DEFAULT_HORIZON_YEARS);
+            Ok(format!("Reminders can now be scheduled up to {years} years ahead").into())
+        }
+        Command::SetPeriodDay(day) => {
+            set_preferences(user, |prefs| prefs.period_day = Some(day)).await;
+            Ok(match day {
+                PeriodDay::First => "`next month` and `next year` now land on the first day",
+                PeriodDay::Same => "`next month` and `next year` now land on the same day as today",
+            }
+            .into())
+        }
+        Command::SetLanguage(language) => {
+            set_preferences(user, |prefs| prefs.language = Some(language)).await;
+            let example = humanize::until(Duration::from_secs(2 * 86400 + 3 * 3600), language);
+            Ok(format!("Durations will be written in {language}, like \"{example}\"").into())
+        }
+        Command::SetTemplate(template) => {
+            if let Some(template) = &template {
+                template::validate(template)?;
+            }
+            let response = match &template {
+                Some(_) => "Reminders will be delivered with your template",
+                None => "Reminders will be delivered as usual",
+            };
+            set_preferences(user, |prefs| prefs.template = template).await;
+            Ok(response.into())
+        }
+        Command::Server(command) => {
+            let guild = msg.guild_id.ok_or(NotInGuild)?;
+            match command {
+                ServerCommand::SetTimezone(timezone) => {
+                    let timezone = timezone::resolve(&timezone)?;
+                    let response = format!("Server default timezone set to {timezone}");
+                    guild::set_guild_settings(guild, |settings| {
+                        settings.defaults.timezone = Some(timezone)
+                    })
+                    .await;
+                    Ok(response.into())
+                }
+                ServerCommand::SetTimeFormat(time_format) => {
+                    guild::set_guild_settings(guild, |settings| {
+                        settings.defaults.time_format = Some(time_format)
+                    })
+                    .await;
+                    Ok("Server default time format set".into())
+                }
+                ServerCommand::Listen(channel, enabled) => {
+                    let channel = match channel {
+                        0 => return Err(InvalidChannel),
+                        channel => ChannelId::new(channel),
+                    };
+                    if guild::channel_guild(ctx, channel).await != Some(guild) {
+                        return Err(InvalidChannel);
+                    }
+                    guild::set_guild_settings(guild, |settings| {
+                        settings.listening.retain(|listening| *listening != channel);
+                        if enabled {
+                            settings.listening.push(channel);
+                        }
+                    })
+                    .await;
+                    Ok(match enabled {
+                        true => format!(
+                            "I'll offer to schedule \"remind me to ...\" messages in <#{channel}>"
+                        ),
+                        false => format!("I'll stop listening for reminders in <#{channel}>"),
+                    }
+                    .into())
+                }
+            }
+        }
+        Command::Announce(command) => {
+            let guild = msg.guild_id.ok_or(NotInGuild)?;
+            let mut announcements = ANNOUNCEMENTS.lock().await;
+            let list = announcements.entry(guild).or_default();
+            match command {
+                AnnouncementCommand::Add(channel, times, interval, message) => {
+                    let message = validate_message(&message)?;
+                    validate_times(&times, &preferences)?;
+                    let channel = match channel {
+                        0 => return Err(InvalidChannel),
+                        channel => ChannelId::new(channel),
+                    };
+                    if guild::channel_guild(ctx, channel).await != Some(guild) {
+                        return Err(CannotPostIn(channel));
+                    }
+                    let description = interval::describe(&interval, preferences.time_format());
+                    let mut lines = vec![];
+                    for time in times {
+                        list.push(Reminder {
+                            interval: Some(interval.clone()),
+                            channel: Some(channel),
+                            ..Reminder::new(time.clone(), message.clone())
+                        });
+                        list.sort_by(|a, b| a.time.cmp(&b.time));
+                        let id = list
+                            .iter()
+                            .position(|a| a.time == time && a.message == message)
+                            .expect("Announcement was not inserted");
+                        lines.push(format!(
+                            "Scheduled announcement in <#{channel}> for {}, repeating {description} (#{id})",
+                            format_time(&time, preferences.time_format())
+                        ));
+                    }
+                    save();
+                    Ok(lines.join("\n").into())
+                }
+                AnnouncementCommand::List => {
+                    let mut lines = vec![];
+                    for (id, announcement) in list.iter().enumerate() {
+                        let mut line = format!(
+                            "{id}: <#{}> {} - {}",
+                            announcement.channel.map(ChannelId::get).unwrap_or_default(),
+                            format_time(&announcement.time, preferences.time_format()),
+                            &announcement.message
+                        );
+                        if let Some(interval) = &announcement.interval {
+                            line.push_str(&format!(
+                                " (Repeats {})",
+                                interval::describe(interval, preferences.time_format())
+                            ));
+                        }
+                        lines.push(line);
+                    }
+                    if lines.is_empty() {
+                        return Ok("No announcements".into());
+                    }
+                    Ok(lines.join("\n").into())
+                }
+                AnnouncementCommand::Cancel(id) => {
+                    if list.len() <= id as usize {
+                        return Err(InvalidID(id));
+                    }
+                    let announcement = list.remove(id as usize);
+                    save();
+                    Ok(format!("Removed announcement '{}'", announcement.message).into())
+                }
+            }
+        }
+        Command::Admin(AdminCommand::Tasks) => Ok(diagnostics::report().into()),
+        Command::Admin(AdminCommand::Stats) => Ok(format!(
+            "{}\n\n{}",
+            storage::report().await,
+            usage::report(&cache).await
+        )
+        .into()),
+        Command::Admin(AdminCommand::Reload) => {
+            let changes = config::reload().await?;
+            if changes.is_empty() {
+                return Ok("Config reloaded, nothing changed".into());
+            }
+            Ok(format!("Config reloaded:\n{}", changes.join("\n")).into())
+        }
+        Command::Admin(AdminCommand::Report) => {
+            let preferences = PREFERENCES.read().await;
+            let mut users: Vec<_> = cache.keys().chain(preferences.keys()).collect();
+            users.sort();
+            users.dedup();
+            let on_default = users
+                .iter()
+                .filter(|user| preferences.get(user).is_none_or(|p| p.timezone.is_none()))
+                .count();
+            let reminders: usize = cache.values().map(Vec::len).sum();
+            Ok(format!(
+                "Users: {}\nUsers without a timezone (assumed {DEFAULT_TIMEZONE}): {on_default}\nReminders: {reminders}",
+                users.len()
+            ).into())
+        }
+        Command::Stats => {
+            let stats = stats::get_stats(user).await;
+            let active = cache.get(&user).map(Vec::len).unwrap_or(0);
+            let recurring = cache
+                .get(&user)
+                .into_iter()
+                .flatten()
+                .filter(|r| r.interval.is_some())
+                .count();
+            let mut lines = vec![
+                format!("Reminders scheduled: {}", stats.scheduled),
+                format!("Delivered: {}", stats.delivered),
+                format!("Completed: {}", stats.completed),
+                format!("Cancelled: {}", stats.cancelled),
+                format!("Snoozed: {}", stats.snoozed),
+                format!("Active: {active} ({recurring} recurring)"),
+            ];
+            let streaks: Vec<_> = cache
+                .get(&user)
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .filter_map(|(id, reminder)| {
+                    let streak = stats.streaks.get(&reminder.key)?;
+                    (streak.best > 0).then(|| {
+                        format!(
+                            "#{id} '{}': {} in a row, best {}",
+                            &reminder.message, streak.current, streak.best
+                        )
+                    })
+                })
+                .collect();
+            if !streaks.is_empty() {
+                lines.push(format!("Streaks:\n{}", streaks.join("\n")));
+            }
+            Ok(lines.join("\n").into())
+        }
+        Command::Ping => {
+            let latency = match diagnostics::latency(ctx.shard_id).await {
+                Some(latency) => format!("{}ms", latency.as_millis()),
+                None => "not measured yet".into(),
+            };
+            let since_tick = diagnostics::since_finished("scheduler");
+            let behind = since_tick.is_some_and(|since| since > SCHEDULER_LAG);
+            let scheduler = match since_tick {
+                _ if !leader::is_leader() => {
+                    "on standby, another instance is delivering reminders".into()
+                }
+                None => "hasn't run yet".into(),
+                Some(since) if behind => format!(
+                    "last ran {:.1}s ago, reminders may be late",
+                    since.as_secs_f64()
+                ),
+                Some(since) => format!("last ran {:.1}s ago", since.as_secs_f64()),
+            };
+            let now = Zoned::now();
+            let due = cache.values().flatten().filter(|r| r.time <= now).count();
+            let retrying = cache.values().flatten().filter(|r| r.attempts > 0).count();
+            let content = [
+                format!("Gateway latency: {latency}"),
+                format!("Scheduler: {scheduler}"),
+                format!(
+                    "Pending deliveries: {due} due, {retrying} retrying, {} spread out",
+                    delivery::spread_waiting().await
+                ),
+            ]
+            .join("\n");
+            Ok(if behind {
+                Reply::warning(content)
+            } else {
+                content.into()
+            })
+        }
+        Command::About => {
+            let reminders: usize = cache.values().map(Vec::len).sum();
+            let recurring = cache
+                .values()
+                .flatten()
+                .filter(|r| r.interval.is_some())
+                .count();
+            Ok([
+                format!(
+                    "reedbot v{} ({})",
+                    env!("CARGO_PKG_VERSION"),
+                    env!("GIT_COMMIT")
+                ),
+                format!("Uptime: {}", format_duration(STARTED_AT.elapsed())),
+                format!(
+                    "Reminders: {reminders} ({recurring} recurring) for {} users",
+                    cache.len()
+                ),
+                format!("Source: <{}>", env!("CARGO_PKG_REPOSITORY")),
+            ]
+            .join("\n")
+            .into())
+        }
+        Command::Help(None) => Ok(help::overview().into()),
+        Command::Help(Some(topic)) => help::topic(&topic)
+            .map(Reply::from)
+            .ok_or(UnknownHelpTopic(topic)),
+    }
+}
+
+const SAVE_FILE: &str = "reminders.json";
+const PREFERENCES_FILE: &str = "preferences.json";
+const LEGACY_TIMEZONE_FILE: &str = "timezones.json";
+
+#[derive(Serialize, Deserialize)]
+struct UserReminder {
+    user: UserId,
+    reminder: Reminder,
+}
+
+async fn load_reminders() {
+    if !tokio::fs::try_exists(SAVE_FILE).await.unwrap() {
+        return;
+    }
+    let contents = tokio::fs::read_to_string(SAVE_FILE).await.unwrap();
+    let reminders = integrity::check(&contents).await;
+    let mut cache = REMINDERS.lock().await;
+    cache.clear();
+
+    for reminder in reminders {
+        cache
+            .entry(reminder.user)
+            .or_default()
+            .push(reminder.reminder);
+    }
+
+    for (_, list) in cache.iter_mut() {
+        list.sort_by(|a, b| a.time.cmp(&b.time));
+    }
+    audit::assign_keys(cache.values_mut().flatten());
+}
+
+async fn load_preferences() {
+    let Ok(preferences_json) = tokio::fs::read_to_string(PREFERENCES_FILE).await else {
+        return;
+    };
+    let preferences = serde_json::from_str(&preferences_json).unwrap();
+    *PREFERENCES.write().await = preferences;
+}
+
+async fn recover_legacy_timezones() {
+    let Ok(timezones_json) = tokio::fs::read_to_string(LEGACY_TIMEZONE_FILE).await else {
+        return;
+    };
+    let timezones: HashMap<UserId, String> = serde_json::from_str(&timezones_json).unwrap();
+    for (user, timezone) in timezones {
+        set_preferences(user, |prefs| prefs.timezone = Some(timezone)).await;
+    }
+    let _ = tokio::fs::remove_file(LEGACY_TIMEZONE_FILE).await;
+}
+
+async fn load() {
+    load_config().await;
+    load_reminders().await;
+    load_preferences().await;
+    outbox::recover().await;
+    load_guild_settings().await;
+    load_announcements().await;
+    load_shared_lists().await;
+    load_sequences().await;
+    load_stats().await;
+    usage::load_usage().await;
+    load_history().await;
+    dedup::load_sent().await;
+    expiry::load_expiring().await;
+    transfer::load_offers().await;
+    load_audit().await;
+    load_links().await;
+    load_rooms().await;
+    load_calendars().await;
+    load_tasks().await;
+    load_feeds().await;
+    load_held().await;
+    load_triggers().await;
+    load_bookmarks().await;
+    load_checklists().await;
+    recover_legacy_timezones().await;
+}
+
+pub fn save() {
+    tokio::spawn(diagnostics::track("persistence", flush()));
+}
+
+/// Held while a state file is replaced, so two saves never write the same temporary file.
+static WRITING: LazyLock<Mutex<()>> = LazyLock::new(Default::default);
+
+async fn write_state(file: &str, json: serde_json::Result<String>) {
+    let result = match json {
+        Ok(json) => replace_file(file, json.as_bytes())
+            .await
+            .map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(err) = result {
+        eprintln!("Failed to save {file}: {err}");
+    }
+}
+
+/// Writes `<file>.tmp` and renames it over `file`, so a crash mid-write leaves the last complete
+/// save in place instead of a truncated one.
+async fn replace_file(file: &str, contents: &[u8]) -> std::io::Result<()> {
+    let _writing = WRITING.lock().await;
+    let temporary = format!("{file}.tmp");
+    let mut handle = tokio::fs::File::create(&temporary).await?;
+    handle.write_all(contents).await?;
+    handle.sync_all().await?;
+    tokio::fs::rename(&temporary, file).await
+}
+
+fn reminders_json(cache: &HashMap<UserId, Vec<Reminder>>) -> serde_json::Result<String> {
+    let mut all_reminders = vec![];
+
+    for (&user, reminders) in cache.iter() {
+        all_reminders.extend(reminders.iter().map(|r| UserReminder {
+            user,
+            reminder: r.clone(),
+        }));
+    }
+
+    serde_json::to_string(&all_reminders)
+}
+
+/// Writes all state to disk, `save` does this in the background. Failures are logged rather
+/// than panicking, since this also runs from the panic hook.
+pub async fn flush() {
+    if !leader::is_leader() {
+        return;
+    }
+    let cache = REMINDERS.lock().await;
+    let reminders_json = reminders_json(&cache);
+    drop(cache);
+    write_state(SAVE_FILE, reminders_json).await;
+
+    let preferences_json = serde_json::to_string(&*PREFERENCES.read().await);
+    write_state(PREFERENCES_FILE, preferences_json).await;
+
+    let guild_settings_json = serde_json::to_string(&*GUILD_SETTINGS.read().await);
+    write_state(GUILD_SETTINGS_FILE, guild_settings_json).await;
+
+    let shared_lists_json = serde_json::to_string(&*SHARED_LISTS.lock().await);
+    write_state(SHARED_LISTS_FILE, shared_lists_json).await;
+
+    let sequences_json = serde_json::to_string(&*SEQUENCES.lock().await);
+    write_state(SEQUENCES_FILE, sequences_json).await;
+
+    let announcements_json = serde_json::to_string(&*ANNOUNCEMENTS.lock().await);
+    write_state(ANNOUNCEMENTS_FILE, announcements_json).await;
+
+    let stats_json = serde_json::to_string(&*STATS.read().await);
+    write_state(STATS_FILE, stats_json).await;
+
+    let usage_json = serde_json::to_string(&*USAGE.lock().await);
+    write_state(USAGE_FILE, usage_json).await;
+
+    let history_json = serde_json::to_string(&*HISTORY.lock().await);
+    write_state(HISTORY_FILE, history_json).await;
+
+    let audit_json = serde_json::to_string(&*AUDIT.lock().await);
+    write_state(AUDIT_FILE, audit_json).await;
+
+    let links_json = serde_json::to_string(&*LINKS.lock().await);
+    write_state(TELEGRAM_FILE, links_json).await;
+
+    let rooms_json = serde_json::to_string(&*ROOMS.lock().await);
+    write_state(MATRIX_FILE, rooms_json).await;
+
+    let calendars_json = serde_json::to_string(&*CALENDARS.lock().await);
+    write_state(CALENDAR_FILE, calendars_json).await;
+
+    let tasks_json = serde_json::to_string(&*ACCOUNTS.lock().await);
+    write_state(TASKS_FILE, tasks_json).await;
+
+    let feeds_json = serde_json::to_string(&*WATCHES.lock().await);
+    write_state(FEEDS_FILE, feeds_json).await;
+
+    let held_json = serde_json::to_string(&*HELD.lock().await);
+    write_state(HELD_FILE, held_json).await;
+
+    let triggers_json = serde_json::to_string(&*TRIGGERS.lock().await);
+    write_state(TRIGGERS_FILE, triggers_json).await;
+
+    let bookmarks_json = serde_json::to_string(&*BOOKMARKS.lock().await);
+    write_state(BOOKMARKS_FILE, bookmarks_json).await;
+
+    let checklists_json = serde_json::to_string(&*CHECKLISTS.lock().await);
+    write_state(CHECKLISTS_FILE, checklists_json).await;
+}
+
+fn log_error<T>(result: Result<T, impl Display>) {
+    if let Err(err) = result {
+        eprintln!("Failed to send reminder message: {err}");
+    }
+}
+
+async fn reschedule(list: &mut Vec<Reminder>, reminder: &Reminder, holidays: &HolidayCalendar) {
+    let time = match reminder.next_occurrence(holidays) {
+        Ok(Some(time)) => time,
+        Ok(None) => return,
+        Err(_) => {
+            eprintln!("Failed to reschedule reminder {}", &reminder.message);
+            return;
+        }
+    };
+
+    let mut next = reminder.clone();
+    next.advance(time, holidays);
+    list.push(next);
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+}
+
+async fn delivery_message(http: &Http, reminder: &Reminder) -> CreateMessage {
+    let parsed = content::parse(&reminder.message);
+    if let Some(title) = parsed.title {
+        let title = match parsed.emoji {
+            Some(emoji) => format!("{emoji} {title}"),
+            None => title.to_string(),
+        };
+        let embed = CreateEmbed::new().title(title).description(format!(
+            "{}{}",
+            parsed.body,
+            reminder.notes_text()
+        ));
+        let content = match reminder.channel {
+            Some(_) => String::new(),
+            None => DELIVERY_PREFIX.trim_end().to_string(),
+        };
+        return with_attachments(http, reminder, content).await.embed(embed);
+    }
+    if let (None, Some(checklist)) = (reminder.channel, checklist::parse(&reminder.message)) {
+        let content = format!(
+            "{DELIVERY_PREFIX}{}{}\n{}",
+            checklist.name,
+            reminder.notes_text(),
+            checklist::lines(&checklist.items, &[])
+        );
+        return with_attachments(http, reminder, content).await;
+    }
+    // Channel messages are announcements, so they're posted as written
+    let content = match reminder.channel {
+        Some(_) => format!("{}{}", &reminder.message, reminder.notes_text()),
+        None => format!(
+            "{DELIVERY_PREFIX}{}{}",
+            &reminder.message,
+            reminder.notes_text()
+        ),
+    };
+    with_attachments(http, reminder, content).await
+}
+
+/// The message `user` gets for their own reminder, laid out with their template if they set one.
+async fn owner_message(http: &Http, user: UserId, reminder: &Reminder) -> CreateMessage {
+    let preferences = get_preferences(user, None).await;
+    let Some(template) = &preferences.template else {
+        return delivery_message(http, reminder).await;
+    };
+    let mut content = template::render(template, user, reminder, &preferences).await;
+    content.push_str(&reminder.notes_text());
+    if let Some(checklist) = checklist::parse(&reminder.message) {
+        content = format!("{content}\n{}", checklist::lines(&checklist.items, &[]));
+    }
+    // Long messages can outgrow Discord's limit once templated
+    if content.chars().count() > 2000 {
+        return delivery_message(http, reminder).await;
+    }
+    with_attachments(http, reminder, content).await
+}
+
+async fn with_attachments(http: &Http, reminder: &Reminder, mut content: String) -> CreateMessage {
+    if reminder.attachments.is_empty() {
+        return CreateMessage::new().content(content);
+    }
+
+    // Attachment URLs are signed and expire, so prefer fresh ones from the original message
+    let fresh = match &reminder.source {
+        Some(source) => source.channel.message(http, source.message).await.ok(),
+        None => None,
+    };
+    let mut files = vec![];
+    for attachment in &reminder.attachments {
+        let url = fresh
+            .iter()
+            .flat_map(|msg| &msg.attachments)
+            .find(|a| a.filename == attachment.filename)
+            .map_or(attachment.url.as_str(), |a| a.url.as_str());
+        match CreateAttachment::url(http, url).await {
+            Ok(file) => files.push(file),
+            Err(_) => content.push_str(&format!("\n{url}")),
+        }
+    }
+    CreateMessage::new().content(content).add_files(files)
+}
+
+async fn process_reminders(http: &Http) {
+    let mut cache = REMINDERS.lock().await;
+    let now = Zoned::now();
+    let mut due = vec![];
+    for (user, reminders) in cache.iter_mut() {
+        while reminders.first().is_some_and(|f| f.time < now) {
+            let first = reminders.remove(0);
+            outbox::begin(*user, &first).await;
+            let holidays = get_preferences(*user, None).await.holidays;
+            reschedule(reminders, &first, &holidays).await;
+            if first.paused {
+                audit::record(*user, &first, Action::Paused, "scheduler").await;
+                outbox::settle(&first).await;
+                continue;
+            }
+            due.push((*user, first));
+        }
+    }
+    drop(cache);
+
+    // Conditions can wait on outside services, so they're checked without the reminders locked.
+    // The due reminders are in the outbox meanwhile.
+    let mut ready = vec![];
+    for (user, first) in due {
+        let first = match &first.condition {
+            None => first,
+            Some(guard) => {
+                let preferences = get_preferences(user, None).await;
+                let context = condition::Context {
+                    user,
+                    location: preferences.location,
+                    time_zone: preferences.time_zone(),
+                };
+                match condition::evaluate(guard, &context).await {
+                    Verdict::Skip => {
+                        audit::record(user, &first, Action::Skipped, "scheduler").await;
+                        outbox::settle(&first).await;
+                        continue;
+                    }
+                    Verdict::Deliver(note) => Reminder {
+                        message: format!("{} {note}", first.message),
+                        ..first
+                    },
+                }
+            }
+        };
+        ready.push((user, first));
+    }
+
+    let mut cache = REMINDERS.lock().await;
+    for (user, first) in ready {
+        let recipient = match first.channel {
+            Some(channel) => Recipient::Channel(channel),
+            None => Recipient::User(user),
+        };
+        // An occurrence replayed from the outbox after a crash stops here, followers included
+        let occurrence = dedup::key(&first, recipient);
+        if dedup::sent(&occurrence).await {
+            outbox::settle(&first).await;
+            continue;
+        }
+        if !first.followers.is_empty() {
+            let followed = Reminder {
+                message: format!("{} (from <@{user}>)", first.message),
+                ..first.clone()
+            };
+            let message = delivery_message(http, &followed).await;
+            delivery::fan_out(http, &first.followers, message, &followed, None).await;
+        }
+        if first.channel.is_none() && presence::online(user) == Some(false) {
+            if let Some(max_hold) = get_preferences(user, None).await.hold_offline {
+                presence::hold(user, first.clone(), max_hold).await;
+                outbox::settle(&first).await;
+                continue;
+            }
+        }
+        let mut message = match first.channel {
+            // Posted with the bot's permissions, so only people are pinged, never @everyone
+            // or roles the author couldn't ping themselves
+            Some(_) => delivery_message(http, &first)
+                .await
+                .allowed_mentions(CreateAllowedMentions::new().all_users(true)),
+            None => owner_message(http, user, &first).await,
+        };
+        if first.channel.is_none() {
+            // A retry copy's buttons act on the reminder it came from
+            let step = sequence::current_step(user, first.origin()).await.is_some();
+            let recurring = first.interval.is_some();
+            let mut rows = vec![interaction::reminder_buttons(
+                user,
+                first.origin(),
+                step,
+                recurring,
+            )];
+            rows.extend(checklist::start(user, &first).await);
+            message = message.components(rows);
+        }
+        let mut message = emphasis::apply(message, first.emphasis, user);
+        if first.tts {
+            message = message.tts(true);
+        }
+        if let (Some(role), Some(_)) = (first.callout, first.channel) {
+            let owner = (first.emphasis == Emphasis::Urgent).then_some(user);
+            message = callout::apply(message, role, owner);
+        }
+        let result = delivery::send(http, recipient, message).await;
+        if result.is_ok() {
+            dedup::mark(occurrence).await;
+            emphasis::follow_up(recipient, user, &first).await;
+        }
+        if let (Ok(Some(sent)), None) = (&result, first.channel) {
+            let preferences = get_preferences(user, None).await;
+            expiry::track(&first, &preferences, sent).await;
+        }
+        outbox::settle(&first).await;
+        if first.channel.is_none() {
+            delivery::mirror(user, &first.message).await;
+        }
+        let status = match &result {
+            Ok(_) => DeliveryStatus::Delivered,
+            Err(_) => match delivery::retry(&first) {
+                Some(retry) => {
+                    let reminders = cache.entry(user).or_default();
+                    reminders.push(retry);
+                    reminders.sort_by(|a, b| a.time.cmp(&b.time));
+                    DeliveryStatus::Retrying
+                }
+                None => DeliveryStatus::Failed,
+            },
+        };
+        let action = match status {
+            DeliveryStatus::Retrying => Action::Retried,
+            DeliveryStatus::Failed => Action::Failed,
+            _ => Action::Fired,
+        };
+        audit::record(user, &first, action, "scheduler").await;
+        delivery::record(user, &first.message, &result, status).await;
+        if result.is_ok() {
+            stats::record(user, |stats| stats.delivered += 1).await;
+            completion::delivered(user, &first).await;
+        }
+        log_error(result);
+    }
+    drop(cache);
+    dedup::flush().await;
+    // The lists have to be on disk before the outbox is emptied
+    flush().await;
+    outbox::clear().await;
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
+
+fn format_time(time: &Zoned, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::H12 => time.strftime("%A, %B %d, %Y at %-I:%M%P %Z").to_string(),
+        TimeFormat::H24 => time.strftime("%A, %B %d, %Y at %-H:%M %Z").to_string(),
+    }
+}
+
+fn strip_prefix<'a>(content: &'a str, prefix: &str, bot: UserId) -> Option<&'a str> {
+    if let Some(rest) = content.strip_prefix(prefix) {
+        return Some(rest);
+    }
+    if !content.starts_with("<@") {
+        return None;
+    }
+    let rest = content
+        .strip_prefix(&format!("<@{bot}>"))
+        .or_else(|| content.strip_prefix(&format!("<@!{bot}>")))?
+        .trim_start();
+    Some(rest.strip_prefix(prefix).unwrap_or(rest))
+}
+
+const MAX_COMMANDS_PER_MESSAGE: usize = 10;
+
+fn split_commands<'a>(input: &'a str, prefix: &str) -> Vec<&'a str> {
+    let mut commands = vec![];
+    let mut start = 0;
+    let mut pos = 0;
+    for line in input.split_inclusive('\n') {
+        let line_start = pos;
+        pos += line.len();
+        if line_start == 0 {
+            continue;
+        }
+        let Some(rest) = line
+            .strip_prefix(prefix)
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+        else {
+            continue;
+        };
+        commands.push(input[start..line_start].trim_end_matches(['\n', '\r']));
+        start = pos - rest.len();
+    }
+    commands.push(input[start..].trim_end_matches(['\n', '\r']));
+    commands
+}
+
+async fn run_command(ctx: &Context, msg: &Message, input: &str, prefix: &str) -> Reply {
+    let preferences = get_preferences(msg.author.id, msg.guild_id).await;
+    let input = command::normalize(input);
+    let (input, reading) = match natural::rewrite(&input, preferences.natural) {
+        Some((rewritten, reading)) => (rewritten, reading),
+        None => (input, None),
+    };
+    let input = input.as_str();
+    let result = match ambiguity::resolve(input, &preferences) {
+        Resolution::Ambiguous(options) => {
+            let user = msg.author.id;
+            return ambiguity::ask(user, msg.channel_id, msg.id, options, &preferences).await;
+        }
+        Resolution::Single(command) => Ok(command),
+        Resolution::Clear => {
+            let mut parser_context = ParserContext::new(input, preferences.time_context());
+            parser_context.result(command::command(&parser_context))
+        }
+    };
+
+    let command = match result {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            let word = input.split_whitespace().next().unwrap_or_default();
+            let name = command::match_command_name(input);
+            usage::record(match name {
+                CommandName::Known => Outcome::Invalid,
+                CommandName::Unknown(_) => Outcome::Unknown,
+            })
+            .await;
+            return Reply::error(match name {
+                CommandName::Known => {
+                    let (span, e) = e.first().unwrap();
+                    let shifted = span.start + prefix.len()..span.end + prefix.len();
+                    let marked = syntax::highlight(&format!("{prefix}{input}"), &shifted);
+                    format!("Invalid command: {e}\n```\n{marked}\n```")
+                }
+                CommandName::Unknown(Some(suggestion)) => format!(
+                    "Unknown command `{prefix}{word}`, did you mean `{prefix}{suggestion}`?"
+                ),
+                CommandName::Unknown(None) => format!(
+                    "Unknown command `{prefix}{word}`, use `{prefix}help` to see all commands"
+                ),
+            });
+        }
+    };
+
+    match handle_command(ctx, msg, command).await {
+        Ok(mut reply) => {
+            if let Some(reading) = reading {
+                reply.content = format!("{reading}\n{}", reply.content);
+            }
+            reply
+        }
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+struct Handler;
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot || !leader::is_leader() {
+            return;
+        }
+        let config = config().await;
+        if msg.guild_id.is_some() && !config.features.guild_mode {
+            return;
+        }
+        if let Some(guild) = msg.guild_id {
+            let event = trigger::Event::Message {
+                guild,
+                author: msg.author.id,
+                channel: msg.channel_id,
+                message: msg.id,
+                content: msg.content.clone(),
+            };
+            trigger::fire(&ctx.http, event).await;
+        }
+
+        let bot = ctx.cache.current_user().id;
+        let Some(input) = strip_prefix(&msg.content, &config.prefix, bot) else {
+            natural::offer(&ctx, &msg).await;
+            return;
+        };
+        if !input.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return;
+        }
+
+        match ratelimit::check(msg.author.id, &config.rate_limit) {
+            ratelimit::Decision::Allow => {}
+            ratelimit::Decision::Ignore => return,
+            ratelimit::Decision::Cooldown(cooldown) => {
+                let response = format!(
+                    "You're sending commands too quickly, please wait {} before trying again",
+                    format_duration(cooldown)
+                );
+                let reply = Reply::error(response).into_message();
+                log_error(msg.channel_id.send_message(&ctx.http, reply).await);
+                return;
+            }
+        }
+
+        let new_user = onboarding::is_new(msg.author.id).await;
+        let commands = split_commands(input, &config.prefix);
+        if commands.len() > MAX_COMMANDS_PER_MESSAGE {
+            let response =
+                format!("Too many commands, send at most {MAX_COMMANDS_PER_MESSAGE} per message");
+            let reply = Reply::error(response).into_message();
+            log_error(msg.channel_id.send_message(&ctx.http, reply).await);
+            return;
+        }
+
+        let mut replies = vec![];
+        for input in commands {
+            let (task_ctx, task_msg) = (ctx.clone(), msg.clone());
+            let (input, prefix) = (input.to_string(), config.prefix.clone());
+            let reply = crash::catch("Command handler", async move {
+                run_command(&task_ctx, &task_msg, &input, &prefix).await
+            })
+            .await;
+            replies.push(reply.unwrap_or_else(|| {
+                Reply::error(
+                    "Something went wrong handling that command, the operator has been notified",
+                )
+            }));
+        }
+
+        let mut reply = Reply::combine(replies);
+        let preferences = get_preferences(msg.author.id, msg.guild_id).await;
+        let private = msg.guild_id.is_none();
+        if let Some(digest) = delivery::digest(msg.author.id, private, &preferences).await {
+            reply.content = format!("{digest}\n\n{}", reply.content);
+            save();
+        }
+        let sent = msg
+            .channel_id
+            .send_message(&ctx.http, reply.into_message())
+            .await;
+        if let Ok(sent) = &sent {
+            undo::expire(ctx.http.clone(), sent.clone());
+        }
+        log_error(sent);
+        if new_user {
+            onboarding::start(&ctx.http, msg.author.id).await;
+        }
+    }
+
+    async fn presence_update(&self, ctx: Context, new_data: Presence) {
+        let online = !matches!(
+            new_data.status,
+            OnlineStatus::Offline | OnlineStatus::Invisible
+        );
+        if online && leader::is_leader() {
+            presence::release(&ctx.http, new_data.user.id).await;
+        }
+    }
+
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        if leader::is_leader() {
+            let bot = ctx.cache.current_user().id;
+            reaction::schedule(&ctx.http, &reaction, bot).await;
+        }
+    }
+
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        let joined = old.is_none_or(|old| old.channel_id.is_none());
+        let (Some(guild), Some(channel)) = (new.guild_id, new.channel_id) else {
+            return;
+        };
+        if joined && leader::is_leader() {
+            let event = trigger::Event::VoiceJoin {
+                guild,
+                user: new.user_id,
+                channel,
+            };
+            trigger::fire(&ctx.http, event).await;
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if !leader::is_leader() {
+            return;
+        }
+        match interaction {
+            Interaction::Component(component) => {
+                interaction::handle_component(&ctx, component).await
+            }
+            Interaction::Modal(modal) => interaction::handle_modal(&ctx, modal).await,
+            _ => {}
+        }
+    }
+}
+
+pub async fn run() {
+    LazyLock::force(&STARTED_AT);
+    if std::env::args().any(|arg| arg == "--dry-run") {
+        delivery::enable_dry_run();
+        eprintln!("Dry run: scheduled messages are logged instead of sent");
+    }
+    if !leader::elect() {
+        eprintln!("Another instance holds the delivery lock, waiting on standby");
+    }
+    load().await;
+    let token = std::env::var("DISCORD_TOKEN")
+        .expect("Discord token not set in DISCORD_TOKEN environment variable");
+    let intents = config().await.intents();
+    let mut client = Client::builder(token, intents)
+        .event_handler(Handler)
+        .await
+        .unwrap();
+
+    let http = client.http.clone();
+    let _ = condition::CACHE.set(client.cache.clone());
+    let _ = diagnostics::SHARDS.set(client.shard_manager.clone());
+    crash::install(http.clone());
+    integrity::report(&http).await;
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            if !leader::is_leader() {
+                if !leader::elect() {
+                    continue;
+                }
+                // The old leader kept writing while this instance waited
+                eprintln!("Took over the delivery lock");
+                load().await;
+            }
+            let http = http.clone();
+            crash::catch("Scheduler", async move {
+                diagnostics::track("scheduler", async {
+                    presence::release_expired(&http).await;
+                    delivery::release_spread(&http).await;
+                    expiry::release(&http).await;
+                    emphasis::release_pings(&http).await;
+                    process_announcements(&http).await;
+                    process_shared_lists(&http).await;
+                    process_reminders(&http).await;
+                    pinned::refresh(&http).await;
+                    review::process_reviews(&http).await;
+                    habit::process_nudges(&http).await;
+                })
+                .await;
+            })
+            .await;
+        }
+    });
+
+    if let Some(token) = telegram::token() {
+        tokio::spawn(crash::catch(
+            "Telegram",
+            diagnostics::track("telegram", telegram::poll(token)),
+        ));
+    }
+
+    let http_api = config().await.features.http_api;
+    if let Some(addr) = dashboard::address().filter(|_| http_api) {
+        tokio::spawn(crash::catch(
+            "Dashboard",
+            diagnostics::track("dashboard", dashboard::serve(addr)),
+        ));
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(calendar::INTERVAL).await;
+            if !leader::is_leader() {
+                continue;
+            }
+            crash::catch("Calendar", diagnostics::track("calendar", calendar::sync())).await;
+        }
+    });
+
+    let feed_http = client.http.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(feeds::INTERVAL).await;
+            if !leader::is_leader() {
+                continue;
+            }
+            let http = feed_http.clone();
+            crash::catch("Feeds", async move {
+                diagnostics::track("feeds", feeds::poll(&http)).await;
+            })
+            .await;
+        }
+    });
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tasks::INTERVAL).await;
+            if !leader::is_leader() {
+                continue;
+            }
+            crash::catch("Tasks", diagnostics::track("tasks", tasks::poll())).await;
+        }
+    });
+
+    let maintenance_http = client.http.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(maintenance::INTERVAL).await;
+            if !leader::is_leader() {
+                continue;
+            }
+            let http = maintenance_http.clone();
+            crash::catch("Maintenance", async move {
+                diagnostics::track("maintenance", maintenance::run(&http)).await;
+            })
+            .await;
+        }
+    });
+
+    client.start().await.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{date, Date};
+
+    use super::*;
+
+    fn at(year: i16, month: i8, day: i8, hour: i8) -> Zoned {
+        date(year, month, day)
+            .at(hour, 0, 0, 0)
+            .in_tz(DEFAULT_TIMEZONE)
+            .unwrap()
+    }
+
+    fn repeating(time: Zoned, interval: Vec<TimeModifier>) -> Reminder {
+        Reminder {
+            interval: Some(interval),
+            ..Reminder::new(time, "test".to_string())
+        }
+    }
+
+    fn days(times: &[Zoned]) -> Vec<Date> {
+        times.iter().map(Zoned::date).collect()
+    }
+
+    #[test]
+    fn months_clamp_to_the_end_of_february() {
+        let month = TimeModifier::Months(1);
+        let next = month.modify(at(2025, 1, 31, 9)).unwrap();
+        assert_eq!(next.date(), date(2025, 2, 28));
+        let next = month.modify(at(2024, 1, 31, 9)).unwrap();
+        assert_eq!(next.date(), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn monthly_reminder_returns_to_its_day_after_february() {
+        let reminder = repeating(at(2025, 1, 31, 9), vec![TimeModifier::Months(1)]);
+        let times = reminder
+            .occurrences(&HolidayCalendar::default(), 4)
+            .unwrap();
+        assert_eq!(
+            days(&times),
+            [
+                date(2025, 1, 31),
+                date(2025, 2, 28),
+                date(2025, 3, 31),
+                date(2025, 4, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn yearly_reminder_on_february_29th() {
+        let reminder = repeating(at(2024, 2, 29, 9), vec![TimeModifier::Years(1)]);
+        let times = reminder
+            .occurrences(&HolidayCalendar::default(), 5)
+            .unwrap();
+        assert_eq!(
+            days(&times),
+            [
+                date(2024, 2, 29),
+                date(2025, 2, 28),
+                date(2026, 2, 28),
+                date(2027, 2, 28),
+                date(2028, 2, 29),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchor_day_survives_rolling_over_a_holiday() {
+        let holidays = HolidayCalendar {
+            country: None,
+            custom: vec![date(2025, 2, 28)],
+        };
+        let mut reminder = Reminder {
+            skip_holidays: true,
+            roll_holidays: true,
+            ..repeating(at(2025, 1, 31, 9), vec![TimeModifier::Months(1)])
+        };
+        let next = reminder.next_occurrence(&holidays).unwrap().unwrap();
+        assert_eq!(next.date(), date(2025, 3, 1));
+        reminder.advance(next, &holidays);
+        assert_eq!(reminder.anchor_day, Some(31));
+        assert_eq!(
+            reminder.rolled_from.as_ref().map(Zoned::date),
+            Some(date(2025, 2, 28))
+        );
+        let next = reminder.next_occurrence(&holidays).unwrap().unwrap();
+        assert_eq!(next.date(), date(2025, 3, 31));
+    }
+
+    #[test]
+    fn daily_reminder_keeps_its_time_of_day_across_dst() {
+        let day = TimeModifier::Delay(24 * 60 * 60 * 1000);
+        let nine = TimeModifier::TimeOfDay { hour: 9, minute: 0 };
+        let reminder = repeating(at(2025, 3, 8, 9), vec![day.clone(), nine]);
+        let times = reminder
+            .occurrences(&HolidayCalendar::default(), 3)
+            .unwrap();
+        assert_eq!(
+            days(&times),
+            [date(2025, 3, 8), date(2025, 3, 9), date(2025, 3, 10)]
+        );
+        assert!(times.iter().all(|time| time.hour() == 9));
+        assert_ne!(times[0].offset(), times[2].offset());
+
+        // Without a time of day the delay is exact, so the clock time moves with DST
+        let reminder = repeating(at(2025, 3, 8, 9), vec![day]);
+        let times = reminder
+            .occurrences(&HolidayCalendar::default(), 2)
+            .unwrap();
+        assert_eq!(times[1].date(), date(2025, 3, 9));
+        assert_eq!(times[1].hour(), 10);
+    }
+
+    #[test]
+    fn occurrences_cross_a_month_end() {
+        let week = TimeModifier::Delay(7 * 24 * 60 * 60 * 1000);
+        let reminder = repeating(at(2025, 1, 24, 9), vec![week]);
+        let times = reminder
+            .occurrences(&HolidayCalendar::default(), 3)
+            .unwrap();
+        assert_eq!(
+            days(&times),
+            [date(2025, 1, 24), date(2025, 1, 31), date(2025, 2, 7)]
+        );
+    }
+
+    fn parse(input: &str) -> Result<command::Command, String> {
+        let mut parser_context = ParserContext::new(input, Preferences::default().time_context());
+        parser_context
+            .result(command::command(&parser_context))
+            .map_err(|errors| {
+                let messages: Vec<_> = errors.iter().map(|(_, e)| e.to_string()).collect();
+                messages.join("\n")
+            })
+    }
+
+    #[test]
+    fn overflowing_delays_are_rejected() {
+        for input in [
+            "r 99999999999999w; too far",
+            "r 9000000000000000000ms 9000000000000000000ms; too far",
+            "r -9999999999999999999ms; too far",
+        ] {
+            let err = parse(input).err().unwrap();
+            assert!(err.contains("That delay is too long"), "{input}: {err}");
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    content, emphasis::Emphasis, format_time, habit, holidays::HolidayMode, humanize, interval,
+    stats::Streak, Preferences, Reminder, TimeFormat,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListStyle {
+    /// Short time and message only
+    Compact,
+    #[default]
+    Verbose,
+}
+
+impl Display for ListStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListStyle::Compact => write!(f, "compact"),
+            ListStyle::Verbose => write!(f, "verbose"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ListSort {
+    #[default]
+    Time,
+    Created,
+    Alpha,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ListGroup {
+    #[default]
+    None,
+    Day,
+    Tag,
+}
+
+pub enum ListOption {
+    Sort(ListSort),
+    Group(ListGroup),
+}
+
+#[derive(Default)]
+pub struct ListOptions {
+    pub sort: ListSort,
+    pub group: ListGroup,
+}
+
+impl FromIterator<ListOption> for ListOptions {
+    fn from_iter<T: IntoIterator<Item = ListOption>>(iter: T) -> Self {
+        let mut options = ListOptions::default();
+        for option in iter {
+            match option {
+                ListOption::Sort(sort) => options.sort = sort,
+                ListOption::Group(group) => options.group = group,
+            }
+        }
+        options
+    }
+}
+
+/// The `#hashtags` in a reminder's message, lowercased and without the `#`.
+pub fn tags(message: &str) -> Vec<String> {
+    let mut tags: Vec<String> = message
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .map(|tag| tag.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_'))
+        .filter(|tag| {
+            !tag.is_empty()
+                && tag
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_lowercase)
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn compact_line(
+    id: usize,
+    reminder: &Reminder,
+    format: TimeFormat,
+    streak: Option<&Streak>,
+) -> String {
+    let time = match format {
+        TimeFormat::H12 => reminder.time.strftime("%a %b %-d %-I:%M%P"),
+        TimeFormat::H24 => reminder.time.strftime("%a %b %-d %-H:%M"),
+    };
+    let repeats = if reminder.interval.is_some() {
+        " ↻"
+    } else {
+        ""
+    };
+    let content = content::parse(&reminder.message);
+    let emoji = content
+        .emoji
+        .map(|emoji| format!("{emoji} "))
+        .unwrap_or_default();
+    let notes = if reminder.notes.is_empty() {
+        ""
+    } else {
+        " 📝"
+    };
+    let habit = match streak {
+        Some(streak) if reminder.habit => format!(" 🔥{}", streak.current),
+        _ => String::new(),
+    };
+    format!(
+        "{emoji}{id}: {time} - {}{repeats}{notes}{habit}",
+        content.summary()
+    )
+}
+
+fn verbose_line(
+    id: usize,
+    reminder: &Reminder,
+    reminders: &[Reminder],
+    preferences: &Preferences,
+    streak: Option<&Streak>,
+) -> Result<String, jiff::Error> {
+    let content = content::parse(&reminder.message);
+    let emoji = content
+        .emoji
+        .map(|emoji| format!("{emoji} "))
+        .unwrap_or_default();
+    let mut line = format!(
+        "{emoji}{id}: [{}] {} - {}",
+        reminder.status(),
+        format_time(&reminder.time, preferences.time_format()),
+        content.text()
+    );
+    if reminder.attempts > 0 {
+        line.push_str(&format!(" ({} failed attempts)", reminder.attempts));
+    }
+    if let Some(channel) = reminder.channel {
+        line.push_str(&format!(" (in <#{channel}>)"));
+    }
+    if reminder.public {
+        line.push_str(&format!(
+            " (public, {} followers)",
+            reminder.followers.len()
+        ));
+    }
+    if let (Some(interval), Some(next)) = (
+        &reminder.interval,
+        reminder.next_occurrence(&preferences.holidays)?,
+    ) {
+        line.push_str(&format!(
+            " (Repeats {}, then at {}",
+            interval::describe(interval, preferences.time_format()),
+            format_time(&next, preferences.time_format())
+        ));
+        match reminder.holiday_mode() {
+            HolidayMode::Send => {}
+            HolidayMode::Skip => line.push_str(", skipping holidays"),
+            HolidayMode::Roll => line.push_str(", moving holidays to the next working day"),
+        }
+        line.push(')');
+    }
+    if reminder.paused {
+        line.push_str(" (paused)");
+    }
+    if reminder.habit {
+        line.push_str(&format!(" (habit: {})", habit::describe(streak)));
+    }
+    if reminder.emphasis != Emphasis::Normal {
+        line.push_str(&format!(" ({})", reminder.emphasis));
+    }
+    if reminder.tts {
+        line.push_str(" (read aloud)");
+    }
+    if let Some(role) = reminder.callout {
+        line.push_str(&format!(" (pings <@&{role}>)"));
+    }
+    match reminder.expire {
+        Some(0) => line.push_str(" (kept after delivery)"),
+        Some(lifetime) => line.push_str(&format!(
+            " (deleted {} after delivery)",
+            humanize::duration(Duration::from_millis(lifetime), preferences.language())
+        )),
+        None => {}
+    }
+    if let Some(condition) = &reminder.condition {
+        line.push_str(&format!(" ({condition})"));
+    }
+    if !reminder.notes.is_empty() {
+        line.push_str(&format!(" (notes: {})", reminder.notes.join("; ")));
+    }
+    if let Some(dependency) = reminder.after {
+        if let Some(position) = reminders
+            .iter()
+            .position(|other| other.key == dependency.key)
+        {
+            line.push_str(&format!(
+                " ({} after #{position})",
+                humanize::duration(
+                    Duration::from_millis(dependency.offset),
+                    preferences.language()
+                )
+            ));
+        }
+    }
+    let until = reminder.time.duration_since(&Zoned::now());
+    if until.is_negative() {
+        line.push_str(" (due now)");
+    } else {
+        let until = humanize::until(until.unsigned_abs(), preferences.language());
+        line.push_str(&format!(" ({until})"));
+    }
+    let tags = tags(&reminder.message);
+    if !tags.is_empty() {
+        line.push_str(&format!(" (tags: {})", tags.join(", ")));
+    }
+    Ok(line)
+}
+
+/// Formats a user's reminders for `$rs`, keeping their IDs whatever the order.
+pub fn list(
+    reminders: &[Reminder],
+    options: &ListOptions,
+    preferences: &Preferences,
+    streaks: &BTreeMap<u64, Streak>,
+) -> Result<Vec<String>, jiff::Error> {
+    let mut entries: Vec<_> = reminders.iter().enumerate().collect();
+    match options.sort {
+        ListSort::Time => {}
+        ListSort::Created => entries.sort_by_key(|(_, reminder)| reminder.key),
+        ListSort::Alpha => entries.sort_by_key(|(_, reminder)| reminder.message.to_lowercase()),
+    }
+
+    // Keyed by (ungrouped last, sort key, heading)
+    let mut groups: BTreeMap<(bool, String, String), Vec<String>> = BTreeMap::new();
+    let mut lines = vec![];
+    for (id, reminder) in entries {
+        let streak = streaks.get(&reminder.key);
+        let line = match preferences.list_style() {
+            ListStyle::Compact => compact_line(id, reminder, preferences.time_format(), streak),
+            ListStyle::Verbose => verbose_line(id, reminder, reminders, preferences, streak)?,
+        };
+        let keys = match options.group {
+            ListGroup::None => {
+                lines.push(line);
+                continue;
+            }
+            ListGroup::Day => vec![(
+                false,
+                reminder.time.date().to_string(),
+                reminder.time.strftime("%A, %B %d, %Y").to_string(),
+            )],
+            ListGroup::Tag => match tags(&reminder.message) {
+                tags if tags.is_empty() => vec![(true, String::new(), "Untagged".into())],
+                tags => tags
+                    .into_iter()
+                    .map(|tag| (false, tag.clone(), format!("#{tag}")))
+                    .collect(),
+            },
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(line.clone());
+        }
+    }
+    for ((_, _, heading), group) in groups {
+        lines.push(format!("**{heading}**"));
+        lines.extend(group);
+    }
+    Ok(lines)
+}
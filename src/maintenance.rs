@@ -0,0 +1,53 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use jiff::{ToSpan, Zoned};
+use serenity::all::Http;
+
+use crate::{
+    audit, checklist, config::config, delivery, integrity, ratelimit, save, sequence::SEQUENCES,
+    stats, storage, REMINDERS,
+};
+
+/// How often stale data is cleaned up.
+pub const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Drops delivery history, audit entries, what fired reminders left behind and quarantined records
+/// past their retention age, along with users left with nothing stored, so save files stay
+/// bounded however long the bot runs.
+pub async fn run(http: &Http) {
+    storage::check(http).await;
+    let retention = config().await.retention;
+    let now = Zoned::now();
+    if let Ok(cutoff) = now.checked_sub((retention.history_days as i64).days()) {
+        delivery::purge(&cutoff).await;
+    }
+    if let Ok(cutoff) = now.checked_sub((retention.audit_days as i64).days()) {
+        audit::purge(&cutoff).await;
+    }
+
+    if let Ok(cutoff) = now.checked_sub((retention.quarantine_days as i64).days()) {
+        integrity::purge(&cutoff).await;
+    }
+
+    let mut cache = REMINDERS.lock().await;
+    cache.retain(|_, list| !list.is_empty());
+    let live: HashMap<_, HashSet<_>> = cache
+        .iter()
+        .map(|(user, list)| (*user, list.iter().map(|r| r.key).collect()))
+        .collect();
+    SEQUENCES
+        .lock()
+        .await
+        .retain(|_, sequences| !sequences.is_empty());
+    drop(cache);
+    if let Ok(cutoff) = now.checked_sub((retention.fired_days as i64).days()) {
+        checklist::purge(&cutoff, &live).await;
+        stats::purge_streaks(&cutoff, &live).await;
+    }
+
+    ratelimit::prune();
+    save();
+}
@@ -0,0 +1,160 @@
+//! The "Manage" button under a repeating reminder, for adjusting it straight from the delivery
+//! instead of looking up its ID.
+
+use jiff::Zoned;
+use serenity::all::{
+    CreateActionRow, CreateInputText, CreateModal, CreateSelectMenu, CreateSelectMenuKind,
+    CreateSelectMenuOption, InputTextStyle, UserId,
+};
+use untwine::prelude::ParserContext;
+
+use crate::{
+    audit::{self, Action},
+    command::{self, Command},
+    dependency, format_time, get_preferences, save, validate_times, Preferences, Reply, REMINDERS,
+};
+
+/// Lists the changes that can be made to a repeating reminder, found by its key since IDs shift.
+pub async fn menu(user: UserId, key: u64) -> Result<Reply, &'static str> {
+    let preferences = get_preferences(user, None).await;
+    let cache = REMINDERS.lock().await;
+    let reminder = cache
+        .get(&user)
+        .and_then(|list| list.iter().find(|r| r.key == key))
+        .filter(|r| r.interval.is_some())
+        .ok_or("This reminder no longer repeats")?;
+    let pause = if reminder.paused {
+        CreateSelectMenuOption::new("Resume", "resume").description("Start sending it again")
+    } else {
+        CreateSelectMenuOption::new("Pause", "pause")
+            .description("Keep the schedule but stop sending it until resumed")
+    };
+    let options = vec![
+        CreateSelectMenuOption::new("Skip next", "skip")
+            .description("Don't send the next occurrence"),
+        pause,
+        CreateSelectMenuOption::new("Change time", "time")
+            .description("Move the next occurrence, later ones follow"),
+        CreateSelectMenuOption::new("Stop repeating", "stop")
+            .description("Send it once more, then remove it"),
+    ];
+    let menu = CreateSelectMenu::new(
+        format!("manage:{user}:{key}"),
+        CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Choose a change");
+    Ok(Reply {
+        components: vec![CreateActionRow::SelectMenu(menu)],
+        ..format!(
+            "'{}' is next at {}",
+            &reminder.message,
+            format_time(&reminder.time, preferences.time_format())
+        )
+        .into()
+    })
+}
+
+pub fn time_modal(key: u64) -> CreateModal {
+    let input = CreateInputText::new(InputTextStyle::Short, "New time", "time")
+        .placeholder("tomorrow 9am, friday 17:30, 2h");
+    CreateModal::new(format!("manage:{key}"), "Change time")
+        .components(vec![CreateActionRow::InputText(input)])
+}
+
+/// Applies a choice from the menu other than changing the time, which goes through the modal.
+pub async fn apply(user: UserId, key: u64, action: &str) -> Result<String, String> {
+    let preferences = get_preferences(user, None).await;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    let reminder = list
+        .iter_mut()
+        .find(|r| r.key == key)
+        .filter(|r| r.interval.is_some())
+        .ok_or("This reminder no longer repeats")?;
+    let response = match action {
+        "skip" => {
+            let next = reminder
+                .next_occurrence(&preferences.holidays)
+                .map_err(|e| e.to_string())?
+                .ok_or("This reminder has no later occurrence to move to")?;
+            let response = format!(
+                "Skipped '{}' on {}, next reminder is {}",
+                &reminder.message,
+                format_time(&reminder.time, preferences.time_format()),
+                format_time(&next, preferences.time_format())
+            );
+            reminder.advance(next, &preferences.holidays);
+            response
+        }
+        "pause" => {
+            reminder.paused = true;
+            format!(
+                "Paused '{}', it won't be sent until resumed",
+                &reminder.message
+            )
+        }
+        "resume" => {
+            reminder.paused = false;
+            format!(
+                "Resumed '{}', next reminder is {}",
+                &reminder.message,
+                format_time(&reminder.time, preferences.time_format())
+            )
+        }
+        "stop" => {
+            reminder.interval = None;
+            // A paused reminder that no longer repeats could never be resumed from this menu
+            reminder.paused = false;
+            format!(
+                "'{}' no longer repeats, it will be sent once more at {}",
+                &reminder.message,
+                format_time(&reminder.time, preferences.time_format())
+            )
+        }
+        _ => return Err("Unknown choice".into()),
+    };
+    audit::record(user, reminder, Action::Edited, "manage").await;
+    dependency::shift(list, key);
+    drop(cache);
+    save();
+    Ok(response)
+}
+
+/// Reads a single time typed outside a command, the same way as `$r`.
+pub fn read_time(text: &str, preferences: &Preferences) -> Result<Zoned, String> {
+    let input = format!("r {}; -", text.trim());
+    let mut parser_context = ParserContext::new(&input, preferences.time_context());
+    let time = match parser_context.result(command::command(&parser_context)) {
+        Ok(Command::ScheduleReminder(times, ..)) if times.len() == 1 => times[0].clone(),
+        Ok(Command::ScheduleReminder(..)) => return Err("Enter a single time".into()),
+        _ => return Err(format!("Couldn't read the time `{}`", text.trim())),
+    };
+    validate_times(std::slice::from_ref(&time), preferences).map_err(|e| e.to_string())?;
+    Ok(time)
+}
+
+/// Moves the next occurrence to a time typed into the modal.
+pub async fn retime(user: UserId, key: u64, text: &str) -> Result<String, String> {
+    let preferences = get_preferences(user, None).await;
+    let time = read_time(text, &preferences)?;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    let reminder = list
+        .iter_mut()
+        .find(|r| r.key == key)
+        .filter(|r| r.interval.is_some())
+        .ok_or("This reminder no longer repeats")?;
+    reminder.time = time;
+    reminder.anchor_day = None;
+    reminder.rolled_from = None;
+    let response = format!(
+        "'{}' is now next at {}, later occurrences follow from there",
+        &reminder.message,
+        format_time(&reminder.time, preferences.time_format())
+    );
+    audit::record(user, reminder, Action::Edited, "manage").await;
+    dependency::shift(list, key);
+    drop(cache);
+    save();
+    Ok(response)
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::Url;
+use ring::aead::LessSafeKey;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    delivery::Notifier,
+    save,
+    secret::{self, open, seal},
+    web, CommandError, Reply,
+};
+
+pub const MATRIX_FILE: &str = "matrix.json";
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A room reminders are posted to, as the user whose access token this is.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MatrixRoom {
+    homeserver: String,
+    room: String,
+    /// The access token, sealed with the key in `MATRIX_KEY`
+    token: String,
+    /// Rooms saved before tokens were sealed have theirs in the clear until a key is set
+    #[serde(default)]
+    sealed: bool,
+}
+
+pub static ROOMS: LazyLock<Mutex<HashMap<UserId, MatrixRoom>>> = LazyLock::new(Default::default);
+
+pub enum MatrixCommand {
+    Show,
+    Set {
+        homeserver: String,
+        room: String,
+        token: String,
+    },
+    Clear,
+}
+
+pub async fn load_rooms() {
+    let Ok(rooms_json) = tokio::fs::read_to_string(MATRIX_FILE).await else {
+        return;
+    };
+    let mut rooms: HashMap<UserId, MatrixRoom> = serde_json::from_str(&rooms_json).unwrap();
+    let unsealed = rooms.values().filter(|room| !room.sealed).count();
+    if unsealed > 0 {
+        match key() {
+            Some(key) => {
+                for room in rooms.values_mut().filter(|room| !room.sealed) {
+                    room.token = seal(&key, &room.token);
+                    room.sealed = true;
+                }
+                save();
+            }
+            None => eprintln!(
+                "{unsealed} Matrix access tokens are saved in the clear, set MATRIX_KEY to seal them"
+            ),
+        }
+    }
+    *ROOMS.lock().await = rooms;
+}
+
+/// Setting up Matrix is enabled by setting `MATRIX_KEY` to 64 hex digits, which seal saved
+/// access tokens.
+fn key() -> Option<LessSafeKey> {
+    secret::key("MATRIX_KEY")
+}
+
+/// Escapes a room ID like `!abc:example.org` for use in a URL path.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+impl Notifier for MatrixRoom {
+    fn name(&self) -> String {
+        format!("matrix room {}", self.room)
+    }
+
+    async fn notify(&self, text: &str) -> Result<(), String> {
+        let token = if self.sealed {
+            let key = key().ok_or("MATRIX_KEY isn't set")?;
+            open(&key, &self.token).ok_or("The saved access token couldn't be unsealed")?
+        } else {
+            self.token.clone()
+        };
+        // Only needs to be unique per access token, the homeserver drops repeats of one
+        let transaction = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let url = format!(
+            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{transaction}",
+            self.homeserver.trim_end_matches('/'),
+            encode(&self.room)
+        );
+        let url = Url::parse(&url).map_err(|err| err.to_string())?;
+        web::public_client(&url, TIMEOUT)
+            .await?
+            .put(url)
+            .bearer_auth(token)
+            .json(&json!({"msgtype": "m.text", "body": text}))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| err.without_url().to_string())
+    }
+}
+
+pub async fn notifier(user: UserId) -> Option<MatrixRoom> {
+    ROOMS.lock().await.get(&user).cloned()
+}
+
+pub async fn handle(
+    user: UserId,
+    command: MatrixCommand,
+    private: bool,
+) -> Result<Reply, CommandError> {
+    match command {
+        MatrixCommand::Show => Ok(match notifier(user).await {
+            Some(room) => format!(
+                "Your reminders are also posted to {} on {}",
+                room.room, room.homeserver
+            ),
+            None => "You haven't set up Matrix, see `$help prefs`".into(),
+        }
+        .into()),
+        MatrixCommand::Set {
+            homeserver,
+            room,
+            token,
+        } => {
+            if !private {
+                return Err(CommandError::MatrixInGuild);
+            }
+            let key = key().ok_or(CommandError::MatrixDisabled)?;
+            if !homeserver.starts_with("https://") || Url::parse(&homeserver).is_err() {
+                return Err(CommandError::InvalidHomeserver);
+            }
+            let room = MatrixRoom {
+                homeserver,
+                room,
+                token: seal(&key, &token),
+                sealed: true,
+            };
+            // Checked up front so a typo doesn't silently lose every later reminder
+            room.notify("Reminders from Discord will be posted here")
+                .await
+                .map_err(CommandError::Matrix)?;
+            let response = format!("Your reminders will also be posted to {}", room.room);
+            ROOMS.lock().await.insert(user, room);
+            save();
+            Ok(response.into())
+        }
+        MatrixCommand::Clear => {
+            if ROOMS.lock().await.remove(&user).is_none() {
+                return Ok("You haven't set up Matrix".into());
+            }
+            save();
+            Ok("Your reminders won't be posted to Matrix any more".into())
+        }
+    }
+}
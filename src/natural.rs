@@ -0,0 +1,512 @@
+//! Spots "remind me to X tomorrow" in channels a server has opted in, separately from the command
+//! grammar. The time is translated into the strict syntax and parsed as a normal `$r`.
+
+use serenity::all::{
+    ButtonStyle, ChannelId, Context, CreateActionRow, CreateButton, CreateMessage, Message,
+    MessageId, UserId,
+};
+use untwine::prelude::ParserContext;
+
+use crate::{
+    command::{self, Command},
+    config::config,
+    delivery::{self, Recipient},
+    format_time, get_preferences, guild, log_error, run_command,
+};
+
+const PHRASE: &str = "remind me to ";
+
+/// A reminder found in a message, with the time in `$r` syntax.
+pub struct Suggestion {
+    pub task: String,
+    pub time: String,
+}
+
+fn unit(word: &str) -> Option<&'static str> {
+    match word {
+        "second" | "seconds" | "sec" | "secs" => Some("s"),
+        "minute" | "minutes" | "min" | "mins" => Some("m"),
+        "hour" | "hours" | "hr" | "hrs" => Some("h"),
+        "day" | "days" => Some("d"),
+        "week" | "weeks" => Some("w"),
+        "month" | "months" => Some("mo"),
+        "year" | "years" => Some("y"),
+        _ => None,
+    }
+}
+
+/// A single word that's a time on its own, in `$r` syntax.
+fn time_word(word: &str) -> Option<String> {
+    let translated = match word {
+        "tomorrow" => "1d",
+        "tonight" => "8pm",
+        "morning" => "9am",
+        "noon" | "midday" => "12pm",
+        "afternoon" => "3pm",
+        "evening" => "6pm",
+        "monday" | "tuesday" | "wednesday" | "thursday" | "friday" | "saturday" | "sunday" => word,
+        _ => {
+            let digits = word.trim_end_matches("am").trim_end_matches("pm");
+            let is_clock = !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit() || c == ':')
+                && (digits.len() < word.len() || digits.contains(':'));
+            return is_clock.then(|| word.to_string());
+        }
+    };
+    Some(translated.to_string())
+}
+
+/// Words that join time words without meaning anything themselves.
+fn is_glue(word: &str) -> bool {
+    matches!(word, "at" | "on" | "next" | "this" | "in" | "the" | "by")
+}
+
+/// Finds "remind me to <task> <time>" in `text`, the time has to come last.
+pub fn detect(text: &str) -> Option<Suggestion> {
+    // ASCII lowercasing keeps byte offsets the same in both
+    let lower = text.to_ascii_lowercase();
+    let start = lower.find(PHRASE)? + PHRASE.len();
+    let words: Vec<_> = text[start..]
+        .split_whitespace()
+        .map(|word| word.trim_end_matches(['.', ',', '!', '?']))
+        .collect();
+    let lowered: Vec<_> = words.iter().map(|word| word.to_lowercase()).collect();
+
+    let mut modifiers = vec![];
+    let mut end = words.len();
+    while end > 0 {
+        let word = lowered[end - 1].as_str();
+        if let Some(unit) = unit(word) {
+            // "in 2 hours", where "in" is required so "for 2 hours" stays in the task
+            let n = end.checked_sub(3).filter(|&n| lowered[n] == "in");
+            let Some(amount) = n.and_then(|n| number_word(&lowered[n + 1])) else {
+                break;
+            };
+            modifiers.push(format!("{amount}{unit}"));
+            end -= 3;
+        } else if let Some(time) = time_word(word) {
+            modifiers.push(time);
+            end -= 1;
+        } else if is_glue(word) && !modifiers.is_empty() {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    // Glue words at the start of the time belong to it, not the task
+    while end > 0 && is_glue(&lowered[end - 1]) && !modifiers.is_empty() {
+        end -= 1;
+    }
+    if modifiers.is_empty() || end == 0 {
+        return None;
+    }
+    modifiers.reverse();
+    Some(Suggestion {
+        task: words[..end].join(" "),
+        time: modifiers.join(" "),
+    })
+}
+
+/// Offers to schedule a reminder found in a message in a channel its server listens in.
+pub async fn offer(ctx: &Context, msg: &Message) {
+    let Some(guild) = msg.guild_id else {
+        return;
+    };
+    if !guild::get_guild_settings(guild)
+        .await
+        .listening
+        .contains(&msg.channel_id)
+    {
+        return;
+    }
+    let Some(suggestion) = detect(&msg.content) else {
+        return;
+    };
+    let preferences = get_preferences(msg.author.id, msg.guild_id).await;
+    let input = format!("r {}; {}", suggestion.time, suggestion.task);
+    let mut parser_context = ParserContext::new(&input, preferences.time_context());
+    let Ok(Command::ScheduleReminder(times, ..)) =
+        parser_context.result(command::command(&parser_context))
+    else {
+        return;
+    };
+    let Some(time) = times.first() else {
+        return;
+    };
+    let buttons = vec![
+        CreateButton::new(format!(
+            "nl:schedule:{}:{}:{}",
+            msg.author.id, msg.channel_id, msg.id
+        ))
+        .label("Schedule this")
+        .style(ButtonStyle::Primary),
+        CreateButton::new(format!("nl:dismiss:{}", msg.author.id))
+            .label("No thanks")
+            .style(ButtonStyle::Secondary),
+    ];
+    let message = CreateMessage::new()
+        .content(format!(
+            "Remind you to {} on {}?",
+            suggestion.task,
+            format_time(time, preferences.time_format())
+        ))
+        .components(vec![CreateActionRow::Buttons(buttons)])
+        .reference_message(msg);
+    log_error(delivery::send(&ctx.http, Recipient::Channel(msg.channel_id), message).await);
+}
+
+/// Schedules the reminder offered for a message, reading it again so nothing has to be kept
+/// between the offer and the click.
+pub async fn schedule(
+    ctx: &Context,
+    user: UserId,
+    channel: ChannelId,
+    message: MessageId,
+) -> Result<String, &'static str> {
+    let original = channel
+        .message(ctx, message)
+        .await
+        .map_err(|_| "That message is gone")?;
+    if original.author.id != user {
+        return Err("Only the person who asked can schedule this");
+    }
+    let suggestion =
+        detect(&original.content).ok_or("That message no longer asks for a reminder")?;
+    let input = format!("r {}; {}", suggestion.time, suggestion.task);
+    Ok(run_command(ctx, &original, &input, &config().await.prefix)
+        .await
+        .content)
+}
+
+fn number_word(word: &str) -> Option<u64> {
+    let n = match word {
+        "zero" => 0,
+        "one" | "a" | "an" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "thirteen" => 13,
+        "fourteen" => 14,
+        "fifteen" => 15,
+        "sixteen" => 16,
+        "seventeen" => 17,
+        "eighteen" => 18,
+        "nineteen" => 19,
+        "twenty" => 20,
+        "thirty" => 30,
+        "forty" => 40,
+        "fifty" => 50,
+        _ => return word.parse().ok(),
+    };
+    Some(n)
+}
+
+/// A number that can be part of a time of day, "a" only counts in "in a week".
+fn hour_word(word: &str) -> Option<u64> {
+    match word {
+        "a" | "an" => None,
+        word => number_word(word),
+    }
+}
+
+fn month(word: &str) -> Option<u8> {
+    let months = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    months
+        .iter()
+        .position(|month| *month == word || (word.len() >= 3 && month.starts_with(word)))
+        .map(|index| index as u8 + 1)
+}
+
+fn weekday(word: &str) -> Option<&'static str> {
+    let weekdays = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    weekdays
+        .into_iter()
+        .find(|weekday| *weekday == word || (word.len() >= 3 && weekday.starts_with(word)))
+}
+
+/// A day of the month like `3`, `3rd` or `third`.
+fn day(word: &str) -> Option<u8> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let day = match word {
+        "first" => 1,
+        "second" => 2,
+        "third" => 3,
+        "fourth" => 4,
+        "fifth" => 5,
+        _ if !digits.is_empty() => digits.parse().ok()?,
+        _ => return None,
+    };
+    (1..=31).contains(&day).then_some(day)
+}
+
+enum Meridiem {
+    Am,
+    Pm,
+}
+
+/// Reads lenient phrases like "the day after tomorrow at quarter past nine" into `$r` syntax.
+/// Returns `None` for anything it doesn't fully understand, which is then left to the strict
+/// grammar.
+pub fn translate(phrase: &str) -> Option<String> {
+    let words: Vec<String> = phrase
+        .to_lowercase()
+        .replace(['-', ','], " ")
+        .split_whitespace()
+        .map(|word| word.trim_end_matches('.').to_string())
+        .filter(|word| {
+            !matches!(
+                word.as_str(),
+                "the" | "at" | "on" | "of" | "o'clock" | "oclock"
+            )
+        })
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let mut modifiers = vec![];
+    let mut clock: Option<(u64, u64)> = None;
+    let mut meridiem = None;
+    let mut i = 0;
+    while i < words.len() {
+        let rest = &words[i..];
+        match rest {
+            ["day", "after", "tomorrow", ..] => {
+                modifiers.push("2d".to_string());
+                i += 3;
+            }
+            ["tomorrow", ..] => {
+                modifiers.push("1d".to_string());
+                i += 1;
+            }
+            ["today" | "this", ..] => {
+                i += 1;
+            }
+            ["next", word, ..] if weekday(word).is_some() => {
+                i += 1;
+            }
+            ["next", "week", ..] => {
+                modifiers.push("1w".to_string());
+                i += 2;
+            }
+            ["next", "month", ..] => {
+                modifiers.push("1mo".to_string());
+                i += 2;
+            }
+            ["next", "year", ..] => {
+                modifiers.push("1y".to_string());
+                i += 2;
+            }
+            ["in" | "after", n, unit_word, ..]
+                if number_word(n).is_some() && unit(unit_word).is_some() =>
+            {
+                modifiers.push(format!("{}{}", number_word(n)?, unit(unit_word)?));
+                i += 3;
+            }
+            [n, unit_word, "from", "now", ..]
+                if number_word(n).is_some() && unit(unit_word).is_some() =>
+            {
+                modifiers.push(format!("{}{}", number_word(n)?, unit(unit_word)?));
+                i += 4;
+            }
+            ["in", "morning", ..] => {
+                meridiem = Some(Meridiem::Am);
+                i += 2;
+            }
+            ["in", "afternoon" | "evening", ..] => {
+                meridiem = Some(Meridiem::Pm);
+                i += 2;
+            }
+            ["night", ..] => {
+                meridiem = Some(Meridiem::Pm);
+                i += 1;
+            }
+            ["am" | "a.m", ..] => {
+                meridiem = Some(Meridiem::Am);
+                i += 1;
+            }
+            ["pm" | "p.m", ..] => {
+                meridiem = Some(Meridiem::Pm);
+                i += 1;
+            }
+            ["noon" | "midday", ..] => {
+                clock = Some((12, 0));
+                i += 1;
+            }
+            ["morning", ..] if clock.is_none() => {
+                clock = Some((9, 0));
+                i += 1;
+            }
+            ["afternoon", ..] if clock.is_none() => {
+                clock = Some((15, 0));
+                i += 1;
+            }
+            ["evening", ..] if clock.is_none() => {
+                clock = Some((18, 0));
+                i += 1;
+            }
+            ["tonight", ..] => {
+                clock.get_or_insert((20, 0));
+                meridiem = Some(Meridiem::Pm);
+                i += 1;
+            }
+            ["quarter", "past" | "after", hour, ..] => {
+                clock = Some((hour_word(hour)?, 15));
+                i += 3;
+            }
+            ["half", "past", hour, ..] => {
+                clock = Some((hour_word(hour)?, 30));
+                i += 3;
+            }
+            ["quarter", "to" | "before", hour, ..] => {
+                clock = Some((hour_word(hour)?.checked_sub(1)?, 45));
+                i += 3;
+            }
+            [minutes, "minutes", "past" | "after", hour, ..]
+            | [minutes, "past" | "after", hour, ..]
+                if hour_word(minutes).is_some() =>
+            {
+                let skip = if rest[1] == "minutes" { 4 } else { 3 };
+                clock = Some((hour_word(hour)?, number_word(minutes)?));
+                i += skip;
+            }
+            [minutes, "minutes", "to" | "before", hour, ..]
+            | [minutes, "to" | "before", hour, ..]
+                if hour_word(minutes).is_some() =>
+            {
+                let skip = if rest[1] == "minutes" { 4 } else { 3 };
+                let (hour, minutes) = (hour_word(hour)?, number_word(minutes)?);
+                clock = Some((hour.checked_sub(1)?, 60u64.checked_sub(minutes)?));
+                i += skip;
+            }
+            [word, ..] if weekday(word).is_some() => {
+                modifiers.push(weekday(word)?.to_string());
+                i += 1;
+            }
+            [month_word, day_word, ..]
+                if month(month_word).is_some() && day(day_word).is_some() =>
+            {
+                modifiers.push(format!("-{:02}-{:02}", month(month_word)?, day(day_word)?));
+                i += 2;
+            }
+            [day_word, month_word, ..]
+                if day(day_word).is_some() && month(month_word).is_some() =>
+            {
+                modifiers.push(format!("-{:02}-{:02}", month(month_word)?, day(day_word)?));
+                i += 2;
+            }
+            [word, ..] if word.contains(':') || word.ends_with("am") || word.ends_with("pm") => {
+                let (digits, suffix) = match word.strip_suffix("am") {
+                    Some(digits) => (digits, Some(Meridiem::Am)),
+                    None => match word.strip_suffix("pm") {
+                        Some(digits) => (digits, Some(Meridiem::Pm)),
+                        None => (*word, None),
+                    },
+                };
+                let (hour, minute) = digits.split_once(':').unwrap_or((digits, "0"));
+                clock = Some((hour.parse().ok()?, minute.parse().ok()?));
+                if suffix.is_some() {
+                    meridiem = suffix;
+                }
+                i += 1;
+            }
+            [hour, minute, ..]
+                if clock.is_none()
+                    && hour_word(hour).is_some_and(|h| (1..=12).contains(&h))
+                    && hour_word(minute).is_some_and(|m| (10..60).contains(&m)) =>
+            {
+                // "nine thirty", "nine forty five"
+                let mut minutes = number_word(minute)?;
+                let mut skip = 2;
+                if let Some(ones) = rest.get(2).and_then(|word| number_word(word)) {
+                    if minutes % 10 == 0 && (1..10).contains(&ones) {
+                        minutes += ones;
+                        skip = 3;
+                    }
+                }
+                clock = Some((hour_word(hour)?, minutes));
+                i += skip;
+            }
+            [hour, ..] if clock.is_none() && hour_word(hour).is_some_and(|h| h <= 24) => {
+                clock = Some((hour_word(hour)?, 0));
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+
+    if let Some((hour, minute)) = clock {
+        let hour = match meridiem {
+            Some(Meridiem::Pm) if hour < 12 => hour + 12,
+            Some(Meridiem::Am) if hour == 12 => 0,
+            _ => hour,
+        };
+        if hour > 23 || minute > 59 {
+            return None;
+        }
+        modifiers.push(format!("{hour}:{minute:02}"));
+    }
+    (!modifiers.is_empty()).then(|| modifiers.join(" "))
+}
+
+/// Rewrites `$r nl <phrase>; message`, or any `$r` when `natural` is set, into strict syntax.
+/// Returns the new input and a note on how the phrase was read, or `None` to leave the input be.
+pub fn rewrite(input: &str, natural: bool) -> Option<(String, Option<String>)> {
+    let (word, rest) = input.split_once(' ')?;
+    if !matches!(word, "r" | "remindme" | "reminder") {
+        return None;
+    }
+    let (explicit, rest) = match rest.strip_prefix("nl ") {
+        Some(rest) => (true, rest),
+        None if natural => (false, rest),
+        None => return None,
+    };
+    let (when, message) = rest.split_once(';')?;
+    if ["list:", "after ", "when "]
+        .iter()
+        .any(|prefix| when.starts_with(prefix))
+    {
+        return None;
+    }
+    let split = [" if ", " unless "]
+        .iter()
+        .filter_map(|keyword| when.find(keyword))
+        .min()
+        .unwrap_or(when.len());
+    let (phrase, condition) = when.split_at(split);
+    match translate(phrase) {
+        Some(time) => Some((
+            format!("{word} {time}{condition};{message}"),
+            Some(format!("Read \"{}\" as `{time}`", phrase.trim())),
+        )),
+        None if explicit => Some((format!("{word} {when};{message}"), None)),
+        None => None,
+    }
+}
@@ -0,0 +1,188 @@
+//! A short guided setup DMed to new users after their first command, picking a timezone and
+//! time format before showing a few commands to try.
+
+use jiff::{tz::TimeZone, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateSelectMenu, CreateSelectMenuKind,
+    CreateSelectMenuOption, Http, UserId,
+};
+
+use crate::{
+    config::config,
+    delivery::{self, Recipient},
+    log_error, set_preferences, timezone, Reply, TimeFormat, PREFERENCES, REMINDERS,
+};
+
+/// Where a user is in the setup, kept in their preferences until they finish or skip it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+    Timezone,
+    TimeFormat,
+}
+
+/// Offered in the timezone picker, anywhere else is typed in through "Other".
+const ZONES: [(&str, &str); 17] = [
+    ("Los Angeles", "America/Los_Angeles"),
+    ("Denver", "America/Denver"),
+    ("Chicago", "America/Chicago"),
+    ("New York", "America/New_York"),
+    ("São Paulo", "America/Sao_Paulo"),
+    ("UTC", "UTC"),
+    ("London", "Europe/London"),
+    ("Paris", "Europe/Paris"),
+    ("Athens", "Europe/Athens"),
+    ("Moscow", "Europe/Moscow"),
+    ("Dubai", "Asia/Dubai"),
+    ("India", "Asia/Kolkata"),
+    ("Bangkok", "Asia/Bangkok"),
+    ("Singapore", "Asia/Singapore"),
+    ("Tokyo", "Asia/Tokyo"),
+    ("Sydney", "Australia/Sydney"),
+    ("Auckland", "Pacific/Auckland"),
+];
+
+/// Whether `user` has never set anything or scheduled anything, checked before their first
+/// command runs.
+pub async fn is_new(user: UserId) -> bool {
+    // Not held while locking REMINDERS, commands take those two locks the other way around
+    let has_preferences = PREFERENCES.read().await.contains_key(&user);
+    !has_preferences && !REMINDERS.lock().await.contains_key(&user)
+}
+
+/// DMs `user` the first step of the setup they haven't already done with their first command.
+pub async fn start(http: &Http, user: UserId) {
+    let (zone, time_format) = PREFERENCES
+        .read()
+        .await
+        .get(&user)
+        .map(|prefs| (prefs.timezone.clone(), prefs.time_format))
+        .unwrap_or_default();
+    let (step, reply) = match (zone, time_format) {
+        (Some(_), Some(_)) => return,
+        (Some(zone), None) => (Step::TimeFormat, time_format_step(user, &zone)),
+        (None, _) => (Step::Timezone, timezone_step(user)),
+    };
+    set_preferences(user, |prefs| prefs.onboarding = Some(step)).await;
+    log_error(delivery::send(http, Recipient::User(user), reply.into_message()).await);
+}
+
+fn skip_button(user: UserId) -> CreateButton {
+    CreateButton::new(format!("onboard:{user}:skip"))
+        .label("Skip setup")
+        .style(ButtonStyle::Secondary)
+}
+
+/// Each zone is labelled with its current time, so people can pick theirs by the clock.
+fn timezone_step(user: UserId) -> Reply {
+    let now = Zoned::now();
+    let options = ZONES
+        .iter()
+        .filter_map(|(name, zone)| {
+            let local = now.with_time_zone(TimeZone::get(zone).ok()?);
+            let label = format!("{name} ({})", local.strftime("%-H:%M"));
+            Some(CreateSelectMenuOption::new(label, *zone))
+        })
+        .collect();
+    let menu = CreateSelectMenu::new(
+        format!("onboard:{user}:timezone"),
+        CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Pick your timezone");
+    let buttons = vec![
+        CreateButton::new(format!("onboard:{user}:other"))
+            .label("Other…")
+            .style(ButtonStyle::Primary),
+        skip_button(user),
+    ];
+    Reply {
+        components: vec![
+            CreateActionRow::SelectMenu(menu),
+            CreateActionRow::Buttons(buttons),
+        ],
+        .."Welcome! Let's get you set up, it only takes a moment.\n\
+        **1/3** Which timezone are you in? Pick the one showing your time, or choose Other to \
+        type it."
+            .into()
+    }
+}
+
+fn time_format_step(user: UserId, zone: &str) -> Reply {
+    let now = Zoned::now().with_time_zone(timezone::lookup(zone).unwrap_or(TimeZone::UTC));
+    let buttons = vec![
+        CreateButton::new(format!("onboard:{user}:12h"))
+            .label(now.strftime("%-I:%M %p").to_string())
+            .style(ButtonStyle::Primary),
+        CreateButton::new(format!("onboard:{user}:24h"))
+            .label(now.strftime("%-H:%M").to_string())
+            .style(ButtonStyle::Primary),
+        skip_button(user),
+    ];
+    Reply {
+        components: vec![CreateActionRow::Buttons(buttons)],
+        ..format!("Timezone set to {zone}.\n**2/3** How should times be shown?").into()
+    }
+}
+
+async fn examples() -> Reply {
+    let prefix = config().await.prefix;
+    [
+        "**3/3** You're all set! A few things to try:".to_string(),
+        format!("`{prefix}r 2h; stretch` reminds you in 2 hours"),
+        format!("`{prefix}r friday 9am; standup notes` reminds you on Friday at 9am"),
+        format!("`{prefix}rs` lists your reminders"),
+        format!("See `{prefix}help` for everything else, and `{prefix}prefs` to change these"),
+    ]
+    .join("\n")
+    .into()
+}
+
+/// Moves the setup along from a button or picker. Answers for a step the user has already
+/// moved past are rejected, so an old message can't undo a later choice.
+pub async fn advance(user: UserId, action: &str, value: Option<&str>) -> Result<Reply, String> {
+    let step = PREFERENCES
+        .read()
+        .await
+        .get(&user)
+        .and_then(|prefs| prefs.onboarding);
+    match (step, action) {
+        (Some(_), "skip") => {
+            set_preferences(user, |prefs| prefs.onboarding = None).await;
+            let prefix = config().await.prefix;
+            Ok(format!("Setup skipped, you can change these any time with `{prefix}prefs`").into())
+        }
+        (Some(Step::Timezone), "timezone") => {
+            let zone = timezone::resolve(value.unwrap_or_default()).map_err(|e| e.to_string())?;
+            let time_format = PREFERENCES
+                .read()
+                .await
+                .get(&user)
+                .and_then(|prefs| prefs.time_format);
+            // A time format set with a command in the meantime finishes the setup here
+            let (step, reply) = match time_format {
+                Some(_) => (None, examples().await),
+                None => (Some(Step::TimeFormat), time_format_step(user, &zone)),
+            };
+            set_preferences(user, |prefs| {
+                prefs.timezone = Some(zone);
+                prefs.onboarding = step;
+            })
+            .await;
+            Ok(reply)
+        }
+        (Some(Step::TimeFormat), "12h" | "24h") => {
+            let time_format = match action {
+                "12h" => TimeFormat::H12,
+                _ => TimeFormat::H24,
+            };
+            set_preferences(user, |prefs| {
+                prefs.time_format = Some(time_format);
+                prefs.onboarding = None;
+            })
+            .await;
+            Ok(examples().await)
+        }
+        _ => Err("This setup step was already answered".into()),
+    }
+}
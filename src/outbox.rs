@@ -0,0 +1,100 @@
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{get_preferences, reschedule, write_state, Reminder, REMINDERS};
+
+pub const OUTBOX_FILE: &str = "outbox.json";
+
+/// A reminder taken off its list to be sent, written to disk before the send so a crash can be
+/// recovered from.
+#[derive(Serialize, Deserialize)]
+struct Intent {
+    user: UserId,
+    reminder: Reminder,
+    /// Whether the send finished, successfully or not
+    settled: bool,
+}
+
+static OUTBOX: LazyLock<Mutex<Vec<Intent>>> = LazyLock::new(Default::default);
+
+/// Logs failures instead of panicking, since this runs in the scheduler with the reminders locked.
+async fn write(outbox: &[Intent]) {
+    write_state(OUTBOX_FILE, serde_json::to_string(outbox)).await;
+}
+
+/// Records that `reminder` is about to be sent, returning once that's on disk.
+pub async fn begin(user: UserId, reminder: &Reminder) {
+    let mut outbox = OUTBOX.lock().await;
+    outbox.push(Intent {
+        user,
+        reminder: reminder.clone(),
+        settled: false,
+    });
+    write(&outbox).await;
+}
+
+/// Records that the send of `reminder` finished, returning once that's on disk.
+pub async fn settle(reminder: &Reminder) {
+    let mut outbox = OUTBOX.lock().await;
+    let intent = outbox
+        .iter_mut()
+        .find(|intent| intent.reminder.key == reminder.key && !intent.settled);
+    if let Some(intent) = intent {
+        intent.settled = true;
+        write(&outbox).await;
+    }
+}
+
+/// Forgets every intent, to be called once the reminder lists they came from have been written.
+pub async fn clear() {
+    let mut outbox = OUTBOX.lock().await;
+    if !outbox.is_empty() {
+        outbox.clear();
+        write(&outbox).await;
+    }
+}
+
+/// Reconciles the reminder lists with what was in flight when the bot last stopped. Reminders
+/// that were sent but still saved are moved on to their next occurrence, and ones that were taken
+/// off their list without the send finishing are put back to go out once. Ones that did go out
+/// are caught by `dedup` when they come due again, unless the crash came between the send and
+/// recording it.
+///
+/// Must run after the reminders are loaded. The intents are kept until the next `clear`, so
+/// crashing again before the lists are saved recovers the same way.
+pub async fn recover() {
+    let Ok(outbox_json) = tokio::fs::read_to_string(OUTBOX_FILE).await else {
+        return;
+    };
+    let intents: Vec<Intent> = serde_json::from_str(&outbox_json).unwrap();
+    let mut cache = REMINDERS.lock().await;
+    for intent in &intents {
+        let list = cache.entry(intent.user).or_default();
+        let sent = &intent.reminder;
+        let saved = list
+            .iter()
+            .position(|r| r.key == sent.key && r.time == sent.time);
+        match (saved, intent.settled) {
+            (Some(index), true) => {
+                list.remove(index);
+                let holidays = get_preferences(intent.user, None).await.holidays;
+                reschedule(list, sent, &holidays).await;
+            }
+            (None, false) => {
+                // The original is already on its next occurrence under the same key
+                let copy = Reminder {
+                    interval: None,
+                    ..sent.clone()
+                };
+                list.push(copy.stand_in_for(sent));
+                list.sort_by(|a, b| a.time.cmp(&b.time));
+            }
+            // Either still due and sent normally, or already done with
+            _ => {}
+        }
+    }
+    *OUTBOX.lock().await = intents;
+}
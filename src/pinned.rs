@@ -0,0 +1,117 @@
+//! `$nextup`, a pinned DM kept up to date with the user's next few reminders.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, EditMessage, Http, MessageId, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    delivery::{self, Recipient},
+    format_time, get_preferences, log_error, set_preferences, CommandError, Preferences, Reminder,
+    Reply, PREFERENCES, REMINDERS,
+};
+
+const SHOWN: usize = 5;
+/// Longer messages are cut off so five of them always fit in one Discord message.
+const MESSAGE_LENGTH: usize = 200;
+
+/// Where a user's pinned message is, kept in their preferences.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NextUp {
+    channel: ChannelId,
+    message: MessageId,
+}
+
+/// What each pinned message last showed, so it's only edited when the schedule changes.
+static RENDERED: LazyLock<Mutex<HashMap<UserId, String>>> = LazyLock::new(Default::default);
+
+fn render(reminders: &[Reminder], preferences: &Preferences) -> String {
+    if reminders.is_empty() {
+        return "**Next up**\nNothing scheduled".into();
+    }
+    let mut lines = vec!["**Next up**".to_string()];
+    for (id, reminder) in reminders.iter().take(SHOWN).enumerate() {
+        let message = match reminder.message.char_indices().nth(MESSAGE_LENGTH) {
+            Some((end, _)) => format!("{}...", &reminder.message[..end]),
+            None => reminder.message.clone(),
+        };
+        lines.push(format!(
+            "{id}: {} - {message}",
+            format_time(&reminder.time, preferences.time_format())
+        ));
+    }
+    if reminders.len() > SHOWN {
+        lines.push(format!("and {} more in `$rs`", reminders.len() - SHOWN));
+    }
+    lines.join("\n")
+}
+
+pub async fn set(
+    http: &Http,
+    user: UserId,
+    enabled: bool,
+    reminders: &[Reminder],
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    // Turning it on again replaces the old message, in case it was unpinned or lost
+    if let (Some(next_up), false) = (preferences.next_up, delivery::dry_run()) {
+        let _ = next_up.channel.delete_message(http, next_up.message).await;
+        RENDERED.lock().await.remove(&user);
+    }
+    if !enabled {
+        set_preferences(user, |prefs| prefs.next_up = None).await;
+        return Ok("Your next up message is removed".into());
+    }
+    let content = render(reminders, preferences);
+    let message = Reply::from(content.clone()).into_message();
+    let Some(message) = delivery::send(http, Recipient::User(user), message)
+        .await
+        .map_err(|_| CommandError::CannotMessage(user))?
+    else {
+        return Ok("Dry run, no next up message was pinned".into());
+    };
+    log_error(message.pin(http).await);
+    let next_up = NextUp {
+        channel: message.channel_id,
+        message: message.id,
+    };
+    set_preferences(user, |prefs| prefs.next_up = Some(next_up)).await;
+    RENDERED.lock().await.insert(user, content);
+    Ok("Your next 5 reminders are pinned in our DMs and will stay up to date".into())
+}
+
+/// Edits every pinned message whose reminders have changed since it was last shown.
+pub async fn refresh(http: &Http) {
+    if delivery::dry_run() {
+        return;
+    }
+    let pinned: Vec<_> = PREFERENCES
+        .read()
+        .await
+        .iter()
+        .filter_map(|(user, prefs)| Some((*user, prefs.next_up?)))
+        .collect();
+    for (user, next_up) in pinned {
+        let preferences = get_preferences(user, None).await;
+        let content = {
+            let cache = REMINDERS.lock().await;
+            let reminders = cache.get(&user).map(Vec::as_slice).unwrap_or_default();
+            render(reminders, &preferences)
+        };
+        let mut rendered = RENDERED.lock().await;
+        if rendered.get(&user) == Some(&content) {
+            continue;
+        }
+        // Recorded before the edit, so a message deleted by hand isn't retried every second
+        rendered.insert(user, content.clone());
+        drop(rendered);
+        let edit = EditMessage::new().content(content);
+        log_error(
+            next_up
+                .channel
+                .edit_message(http, next_up.message, edit)
+                .await,
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+use serenity::all::{Http, OnlineStatus, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    checklist, completion,
+    condition::CACHE,
+    dedup,
+    delivery::{self, DeliveryStatus, Recipient},
+    emphasis, expiry, get_preferences, interaction, log_error, owner_message, save, sequence,
+    stats, Reminder,
+};
+
+pub const HELD_FILE: &str = "held.json";
+
+/// How long `$delivery online` holds reminders when no limit is given, in milliseconds.
+pub const DEFAULT_MAX_HOLD: u64 = 8 * 60 * 60 * 1000;
+
+/// A reminder that fired while its owner was offline.
+#[derive(Serialize, Deserialize)]
+pub struct Held {
+    reminder: Reminder,
+    /// Sent at this point even if the owner still isn't online
+    until: Timestamp,
+}
+
+pub static HELD: LazyLock<Mutex<HashMap<UserId, Vec<Held>>>> = LazyLock::new(Default::default);
+
+pub async fn load_held() {
+    let Ok(held_json) = tokio::fs::read_to_string(HELD_FILE).await else {
+        return;
+    };
+    let held = serde_json::from_str(&held_json).unwrap();
+    *HELD.lock().await = held;
+}
+
+/// Whether the user shows as online, idle or busy in a server the bot shares with them, `None`
+/// if presences aren't tracked.
+pub fn online(user: UserId) -> Option<bool> {
+    let cache = CACHE.get()?;
+    // Offline members have no presence, so not finding one means offline
+    let online = cache.guilds().into_iter().any(|guild| {
+        cache.guild(guild).is_some_and(|guild| {
+            guild.presences.get(&user).is_some_and(|presence| {
+                !matches!(
+                    presence.status,
+                    OnlineStatus::Offline | OnlineStatus::Invisible
+                )
+            })
+        })
+    });
+    Some(online)
+}
+
+/// Keeps `reminder` until its owner comes online or `max_hold` milliseconds pass.
+pub async fn hold(user: UserId, reminder: Reminder, max_hold: u64) {
+    let until = Timestamp::now()
+        .checked_add(SignedDuration::from_millis(max_hold as i64))
+        .unwrap_or(Timestamp::MAX);
+    HELD.lock()
+        .await
+        .entry(user)
+        .or_default()
+        .push(Held { reminder, until });
+}
+
+async fn deliver(http: &Http, user: UserId, reminder: &Reminder, source: &str) {
+    let occurrence = dedup::key(reminder, Recipient::User(user));
+    if dedup::sent(&occurrence).await {
+        return;
+    }
+    let step = sequence::current_step(user, reminder.key).await.is_some();
+    let recurring = reminder.interval.is_some();
+    let mut rows = vec![interaction::reminder_buttons(
+        user,
+        reminder.key,
+        step,
+        recurring,
+    )];
+    rows.extend(checklist::start(user, reminder).await);
+    let message = owner_message(http, user, reminder).await.components(rows);
+    let message = emphasis::apply(message, reminder.emphasis, user);
+    let result = delivery::send(http, Recipient::User(user), message).await;
+    if result.is_ok() {
+        dedup::mark(occurrence).await;
+        emphasis::follow_up(Recipient::User(user), user, reminder).await;
+    }
+    if let Ok(Some(sent)) = &result {
+        let preferences = get_preferences(user, None).await;
+        expiry::track(reminder, &preferences, sent).await;
+    }
+    delivery::mirror(user, &reminder.message).await;
+    let (status, action) = match &result {
+        Ok(_) => (DeliveryStatus::Delivered, Action::Fired),
+        Err(_) => (DeliveryStatus::Failed, Action::Failed),
+    };
+    audit::record(user, reminder, action, source).await;
+    delivery::record(user, &reminder.message, &result, status).await;
+    if result.is_ok() {
+        stats::record(user, |stats| stats.delivered += 1).await;
+        completion::delivered(user, reminder).await;
+    }
+    log_error(result);
+}
+
+/// Sends everything held for `user`, for when they come online.
+pub async fn release(http: &Http, user: UserId) {
+    let Some(held) = HELD.lock().await.remove(&user) else {
+        return;
+    };
+    for held in held {
+        deliver(http, user, &held.reminder, "presence").await;
+    }
+    save();
+}
+
+/// Sends reminders that have been held as long as their owners allow.
+pub async fn release_expired(http: &Http) {
+    let now = Timestamp::now();
+    let mut expired = vec![];
+    {
+        let mut all = HELD.lock().await;
+        for (user, held) in all.iter_mut() {
+            let (due, waiting) = std::mem::take(held)
+                .into_iter()
+                .partition(|held| held.until <= now);
+            *held = waiting;
+            expired.extend(due.into_iter().map(|held: Held| (*user, held.reminder)));
+        }
+        all.retain(|_, held| !held.is_empty());
+    }
+    if expired.is_empty() {
+        return;
+    }
+    for (user, reminder) in expired {
+        let reminder = Reminder {
+            message: format!("{} (held while you were offline)", reminder.message),
+            ..reminder
+        };
+        deliver(http, user, &reminder, "scheduler").await;
+    }
+    save();
+}
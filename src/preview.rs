@@ -0,0 +1,151 @@
+//! `$si` shows where a new interval would land and waits for a button press before saving it, so
+//! a mistyped interval can't quietly turn into a reminder firing every few seconds.
+
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use jiff::SignedDuration;
+use ring::rand::{SecureRandom, SystemRandom};
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    format_time, interval, rrule, save, CommandError, Preferences, Reminder, Reply, TimeModifier,
+    REMINDERS,
+};
+
+const SHOWN: usize = 3;
+
+/// Occurrences closer together than this get called out in the preview.
+const TOO_CLOSE: SignedDuration = SignedDuration::from_secs(60);
+
+/// How long a preview's buttons can be answered for.
+const LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+struct Pending {
+    user: UserId,
+    /// The reminder's key, since IDs shift while the preview is open
+    key: u64,
+    interval: Vec<TimeModifier>,
+    response: String,
+    created: Instant,
+}
+
+/// Open previews by a random ID carried in their buttons, so each button saves its own preview.
+static PENDING: LazyLock<Mutex<HashMap<u64, Pending>>> = LazyLock::new(Default::default);
+
+pub async fn interval(
+    user: UserId,
+    id: u64,
+    reminder: &Reminder,
+    modifiers: Vec<TimeModifier>,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    let mut description = interval::describe(&modifiers, preferences.time_format());
+    if let Some(rule) = rrule::from_modifiers(&modifiers) {
+        description.push_str(&format!(" (RRULE:{rule})"));
+    }
+    let candidate = Reminder {
+        interval: Some(modifiers.clone()),
+        ..reminder.clone()
+    };
+    let times = candidate.occurrences(&preferences.holidays, SHOWN + 1)?;
+    let mut lines = vec![
+        format!(
+            "Reminder '{}' (#{id}) would repeat {description}",
+            &reminder.message
+        ),
+        format!(
+            "After {}, the next {} occurrences would be:",
+            format_time(&reminder.time, preferences.time_format()),
+            times.len() - 1
+        ),
+    ];
+    for (n, time) in times.iter().skip(1).enumerate() {
+        lines.push(format!(
+            "{}. {}",
+            n + 1,
+            format_time(time, preferences.time_format())
+        ));
+    }
+    if times.len() <= SHOWN {
+        lines.push("The reminder stops repeating after this".into());
+    }
+    if times
+        .windows(2)
+        .any(|pair| pair[0].duration_until(&pair[1]) < TOO_CLOSE)
+    {
+        lines.push("**Warning:** these occurrences are less than a minute apart".into());
+    }
+    let mut bytes = [0; 8];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("No secure random source");
+    let preview = u64::from_le_bytes(bytes);
+    let buttons = vec![
+        CreateButton::new(format!("interval:{user}:{preview}:apply"))
+            .label("Save interval")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("interval:{user}:{preview}:cancel"))
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ];
+    let pending = Pending {
+        user,
+        key: reminder.key,
+        interval: modifiers,
+        response: format!(
+            "Reminder '{}' (#{id}) now repeats {description}",
+            &reminder.message
+        ),
+        created: Instant::now(),
+    };
+    let mut previews = PENDING.lock().await;
+    previews.retain(|_, pending| pending.created.elapsed() < LIFETIME);
+    previews.insert(preview, pending);
+    drop(previews);
+    Ok(Reply {
+        components: vec![CreateActionRow::Buttons(buttons)],
+        ..lines.join("\n").into()
+    })
+}
+
+/// Takes the preview with ID `preview` if it's `user`'s own and hasn't expired.
+async fn take(user: UserId, preview: u64) -> Result<Pending, &'static str> {
+    let mut previews = PENDING.lock().await;
+    if previews
+        .get(&preview)
+        .is_none_or(|pending| pending.user != user)
+    {
+        return Err("This interval has already been handled");
+    }
+    let pending = previews.remove(&preview).unwrap();
+    if pending.created.elapsed() >= LIFETIME {
+        return Err("This preview has expired, run the command again");
+    }
+    Ok(pending)
+}
+
+/// Saves the interval from the preview with ID `preview`.
+pub async fn apply(user: UserId, preview: u64) -> Result<String, &'static str> {
+    let pending = take(user, preview).await?;
+    let mut cache = REMINDERS.lock().await;
+    let reminder = cache
+        .get_mut(&user)
+        .and_then(|list| list.iter_mut().find(|r| r.key == pending.key))
+        .ok_or("That reminder no longer exists")?;
+    reminder.interval = Some(pending.interval);
+    audit::record(user, reminder, Action::Edited, "$si").await;
+    drop(cache);
+    save();
+    Ok(pending.response)
+}
+
+pub async fn cancel(user: UserId, preview: u64) -> &'static str {
+    let _ = take(user, preview).await;
+    "Interval not changed"
+}
@@ -0,0 +1,53 @@
+use std::fmt::Display;
+
+use jiff::{civil::Date, ToSpan};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum QuarterPosition {
+    /// The next first day of fiscal quarter 1-4, today if it already is
+    Start(i8),
+    /// The last day of the current quarter
+    End,
+    /// The first day of the following quarter
+    Next,
+}
+
+impl Display for QuarterPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuarterPosition::Start(quarter) => write!(f, "at the start of Q{quarter}"),
+            QuarterPosition::End => write!(f, "at the end of the quarter"),
+            QuarterPosition::Next => write!(f, "at the start of the next quarter"),
+        }
+    }
+}
+
+/// The first day of the quarter containing `date` and that quarter's number, for a fiscal year
+/// starting in month `fiscal_start`.
+fn current(date: Date, fiscal_start: i8) -> Result<(Date, i8), jiff::Error> {
+    let into_year = (date.month() - fiscal_start).rem_euclid(12);
+    let start = date
+        .first_of_month()
+        .checked_sub((into_year % 3).months())?;
+    Ok((start, into_year / 3 + 1))
+}
+
+pub fn resolve(
+    date: Date,
+    position: QuarterPosition,
+    fiscal_start: i8,
+) -> Result<Date, jiff::Error> {
+    let (start, quarter) = current(date, fiscal_start)?;
+    match position {
+        QuarterPosition::Next => start.checked_add(3.months()),
+        QuarterPosition::End => start.checked_add(3.months())?.yesterday(),
+        QuarterPosition::Start(target) => {
+            let mut ahead = (target - quarter).rem_euclid(4);
+            if ahead == 0 && start < date {
+                ahead = 4;
+            }
+            start.checked_add((ahead as i64 * 3).months())
+        }
+    }
+}
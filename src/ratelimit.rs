@@ -0,0 +1,68 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use serenity::all::UserId;
+
+use crate::config::RateLimit;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    cooldown_until: Option<Instant>,
+}
+
+pub enum Decision {
+    Allow,
+    Cooldown(Duration),
+    Ignore,
+}
+
+static BUCKETS: LazyLock<Mutex<HashMap<UserId, Bucket>>> = LazyLock::new(Default::default);
+
+/// Buckets untouched for this long are full again and can be dropped.
+const IDLE: Duration = Duration::from_secs(60 * 60);
+
+/// Drops buckets of users who haven't sent a command in a while.
+pub fn prune() {
+    let now = Instant::now();
+    BUCKETS.lock().unwrap().retain(|_, bucket| {
+        now.duration_since(bucket.updated) < IDLE
+            || bucket.cooldown_until.is_some_and(|until| until > now)
+    });
+}
+
+pub fn check(user: UserId, limit: &RateLimit) -> Decision {
+    let now = Instant::now();
+    let burst = limit.burst as f64;
+    let mut buckets = BUCKETS.lock().unwrap();
+    let bucket = buckets.entry(user).or_insert(Bucket {
+        tokens: burst,
+        updated: now,
+        cooldown_until: None,
+    });
+
+    if let Some(until) = bucket.cooldown_until {
+        if now < until {
+            return Decision::Ignore;
+        }
+        bucket.cooldown_until = None;
+        bucket.tokens = burst;
+        bucket.updated = now;
+    }
+
+    let refill = now.duration_since(bucket.updated).as_secs_f64() * limit.per_minute as f64 / 60.0;
+    bucket.tokens = (bucket.tokens + refill).min(burst);
+    bucket.updated = now;
+
+    if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        return Decision::Allow;
+    }
+
+    let cooldown = Duration::from_secs(limit.cooldown_secs);
+    bucket.cooldown_until = Some(now + cooldown);
+    Decision::Cooldown(cooldown)
+}
@@ -0,0 +1,64 @@
+//! Reacting to a message with ⏰ schedules a reminder about it an hour later, when
+//! `features.reactions` is on.
+
+use jiff::{SignedDuration, Zoned};
+use serenity::all::{CreateMessage, Http, Reaction, ReactionType, UserId};
+
+use crate::{
+    audit::{self, Action},
+    config::config,
+    delivery::{self, Recipient},
+    format_time, get_preferences, log_error, ratelimit, save, stats, Reminder, REMINDERS,
+};
+
+pub const EMOJI: &str = "⏰";
+const DELAY: SignedDuration = SignedDuration::from_hours(1);
+
+/// Schedules a reminder for whoever added `reaction`, if it's the alarm clock and not from `bot`.
+pub async fn schedule(http: &Http, reaction: &Reaction, bot: UserId) {
+    let config = config().await;
+    if !config.features.reactions {
+        return;
+    }
+    if reaction.guild_id.is_some() && !config.features.guild_mode {
+        return;
+    }
+    if !matches!(&reaction.emoji, ReactionType::Unicode(emoji) if emoji == EMOJI) {
+        return;
+    }
+    let Some(user) = reaction.user_id.filter(|user| *user != bot) else {
+        return;
+    };
+    // Counted like commands, so reacting and unreacting can't pile up reminders
+    if !matches!(
+        ratelimit::check(user, &config.rate_limit),
+        ratelimit::Decision::Allow
+    ) {
+        return;
+    }
+    let link = reaction
+        .message_id
+        .link(reaction.channel_id, reaction.guild_id);
+    let preferences = get_preferences(user, reaction.guild_id).await;
+    let Ok(time) = Zoned::now()
+        .with_time_zone(preferences.time_zone())
+        .checked_add(DELAY)
+    else {
+        return;
+    };
+    let reminder = Reminder::new(time.clone(), format!("You reacted {EMOJI} to {link}"));
+    audit::record(user, &reminder, Action::Created, "reaction").await;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    list.push(reminder);
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+    drop(cache);
+    stats::record(user, |stats| stats.scheduled += 1).await;
+    save();
+    let notice = format!(
+        "I'll remind you about {link} on {}",
+        format_time(&time, preferences.time_format())
+    );
+    let message = CreateMessage::new().content(notice);
+    log_error(delivery::send(http, Recipient::User(user), message).await);
+}
@@ -0,0 +1,127 @@
+//! `$review`, an opt-in DM on Sunday evenings looking back at the reminders delivered that week
+//! and ahead at the ones coming up, for a weekly planning pass.
+
+use jiff::{civil::Weekday, ToSpan, Zoned};
+use serenity::all::{CreateMessage, Http, UserId};
+
+use crate::{
+    config::config,
+    delivery::{self, DeliveryStatus, Recipient, HISTORY},
+    get_preferences, log_error, set_preferences, Preferences, TimeFormat, PREFERENCES, REMINDERS,
+};
+
+/// Local hour on Sunday the review is sent at.
+pub const REVIEW_HOUR: i8 = 18;
+/// Entries listed in each half before the rest are counted.
+const LISTED: usize = 10;
+const MESSAGE_LENGTH: usize = 80;
+/// Occurrences of one repeating reminder counted for the week ahead, so an every-minute reminder
+/// doesn't have to be walked through ten thousand times.
+const MAX_OCCURRENCES: usize = 100;
+
+fn short_time(time: &Zoned, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::H12 => time.strftime("%a %-I:%M%P").to_string(),
+        TimeFormat::H24 => time.strftime("%a %-H:%M").to_string(),
+    }
+}
+
+fn section(heading: String, mut entries: Vec<(Zoned, String)>, format: TimeFormat) -> String {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut lines = vec![heading];
+    for (time, message) in entries.iter().take(LISTED) {
+        let message = match message.char_indices().nth(MESSAGE_LENGTH) {
+            Some((end, _)) => format!("{}...", &message[..end]),
+            None => message.clone(),
+        };
+        lines.push(format!("• {} - {message}", short_time(time, format)));
+    }
+    if entries.len() > LISTED {
+        lines.push(format!("and {} more", entries.len() - LISTED));
+    }
+    lines.join("\n")
+}
+
+async fn render(user: UserId, preferences: &Preferences, now: &Zoned) -> String {
+    let format = preferences.time_format();
+    let start = now.saturating_sub(7.days());
+    let end = now.saturating_add(7.days());
+    let delivered: Vec<_> = HISTORY
+        .lock()
+        .await
+        .get(&user)
+        .into_iter()
+        .flatten()
+        .filter(|d| d.status == DeliveryStatus::Delivered && d.attempted >= start)
+        .map(|d| {
+            (
+                d.attempted.with_time_zone(now.time_zone().clone()),
+                d.message.clone(),
+            )
+        })
+        .collect();
+    let mut upcoming = vec![];
+    for reminder in REMINDERS.lock().await.get(&user).into_iter().flatten() {
+        if reminder.paused {
+            continue;
+        }
+        let mut reminder = reminder.clone();
+        for _ in 0..MAX_OCCURRENCES {
+            if reminder.time >= end {
+                break;
+            }
+            upcoming.push((reminder.time.clone(), reminder.message.clone()));
+            match reminder.next_occurrence(&preferences.holidays) {
+                Ok(Some(next)) => reminder.advance(next, &preferences.holidays),
+                _ => break,
+            }
+        }
+    }
+    let looking_back = match delivered.len() {
+        0 => "**Last 7 days:** nothing delivered".to_string(),
+        count => section(
+            format!("**Last 7 days:** {count} delivered"),
+            delivered,
+            format,
+        ),
+    };
+    let looking_ahead = match upcoming.len() {
+        0 => "**Next 7 days:** nothing scheduled".to_string(),
+        count => section(
+            format!("**Next 7 days:** {count} coming up"),
+            upcoming,
+            format,
+        ),
+    };
+    format!("📅 Your week in review\n\n{looking_back}\n\n{looking_ahead}")
+}
+
+/// Sends the review to everyone who opted in and hasn't had this Sunday's yet.
+pub async fn process_reviews(http: &Http) {
+    if !config().await.features.digests {
+        return;
+    }
+    let opted_in: Vec<_> = PREFERENCES
+        .read()
+        .await
+        .iter()
+        .filter(|(_, prefs)| prefs.weekly_review)
+        .map(|(user, _)| *user)
+        .collect();
+    for user in opted_in {
+        let preferences = get_preferences(user, None).await;
+        let now = Zoned::now().with_time_zone(preferences.time_zone());
+        if now.weekday() != Weekday::Sunday || now.hour() < REVIEW_HOUR {
+            continue;
+        }
+        let today = now.date();
+        if preferences.last_review == Some(today) {
+            continue;
+        }
+        // Marked first so a failed DM isn't retried every second
+        set_preferences(user, |prefs| prefs.last_review = Some(today)).await;
+        let content = render(user, &preferences, &now).await;
+        let message = CreateMessage::new().content(content);
+        log_error(delivery::send(http, Recipient::User(user), message).await);
+    }
+}
@@ -0,0 +1,638 @@
+use std::fmt::Display;
+
+use jiff::{
+    civil::{Date, DateTime, Weekday},
+    fmt::strtime,
+    tz::TimeZone,
+    Span, Timestamp, Zoned,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::TimeModifier;
+
+#[derive(Error, Debug)]
+pub enum RRuleError {
+    #[error("Invalid RRULE part: {0}")]
+    InvalidPart(String),
+    #[error("An RRULE needs a FREQ, e.g. FREQ=WEEKLY;BYDAY=MO")]
+    MissingFrequency,
+    #[error("RRULE {0} isn't supported{hint}", hint = if .0 == "COUNT" { ", use UNTIL instead" } else { "" })]
+    Unsupported(String),
+    #[error("RRULE {0} never matches a real date")]
+    NeverMatches(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Frequency {
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+const FREQUENCIES: [(&str, Frequency); 6] = [
+    ("MINUTELY", Frequency::Minutely),
+    ("HOURLY", Frequency::Hourly),
+    ("DAILY", Frequency::Daily),
+    ("WEEKLY", Frequency::Weekly),
+    ("MONTHLY", Frequency::Monthly),
+    ("YEARLY", Frequency::Yearly),
+];
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Monday),
+    ("TU", Weekday::Tuesday),
+    ("WE", Weekday::Wednesday),
+    ("TH", Weekday::Thursday),
+    ("FR", Weekday::Friday),
+    ("SA", Weekday::Saturday),
+    ("SU", Weekday::Sunday),
+];
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    WEEKDAYS[weekday.to_monday_zero_offset() as usize].0
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, weekday)| *weekday)
+}
+
+#[derive(Debug, Clone)]
+enum Until {
+    Date(Date),
+    Instant(Timestamp),
+}
+
+impl Until {
+    fn parse(input: &str) -> Option<Until> {
+        if let Some(datetime) = input.strip_suffix('Z') {
+            let datetime = strtime::parse("%Y%m%dT%H%M%S", datetime)
+                .ok()?
+                .to_datetime()
+                .ok()?;
+            return Some(Until::Instant(
+                datetime.to_zoned(TimeZone::UTC).ok()?.timestamp(),
+            ));
+        }
+        let date = strtime::parse("%Y%m%d", input).ok()?.to_date().ok()?;
+        Some(Until::Date(date))
+    }
+
+    fn to_datetime(&self, tz: &TimeZone) -> DateTime {
+        match self {
+            Until::Date(date) => date.at(23, 59, 59, 0),
+            Until::Instant(timestamp) => timestamp.to_zoned(tz.clone()).datetime(),
+        }
+    }
+}
+
+impl Display for Until {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Until::Date(date) => write!(f, "{}", date.strftime("%Y%m%d")),
+            Until::Instant(timestamp) => write!(f, "{}", timestamp.strftime("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+/// A subset of the RFC 5545 recurrence rule, evaluated relative to the previous occurrence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RRule {
+    freq: Frequency,
+    interval: i64,
+    by_month: Vec<i8>,
+    by_month_day: Vec<i8>,
+    by_day: Vec<(i8, Weekday)>,
+    by_hour: Vec<i8>,
+    by_minute: Vec<i8>,
+    by_set_pos: Vec<i16>,
+    week_start: Weekday,
+    until: Option<Until>,
+}
+
+fn numbers(value: &str, min: i16, max: i16, signed: bool) -> Option<Vec<i16>> {
+    value
+        .split(',')
+        .map(|n| {
+            let n: i16 = n.parse().ok()?;
+            let valid = if signed {
+                n != 0 && (min..=max).contains(&n.abs())
+            } else {
+                (min..=max).contains(&n)
+            };
+            valid.then_some(n)
+        })
+        .collect()
+}
+
+/// Periods searched for the next occurrence before giving up, a rule that still hasn't matched
+/// by then is as good as one that never does.
+const MAX_PERIODS: usize = 100_000;
+/// The calendar repeats every 400 years, so a rule with no match within them never matches.
+const SEARCH_YEARS: i64 = 400;
+
+/// jiff has no public way to build its errors, so a search that gives up fails the same way as
+/// one that runs off the end of the calendar.
+fn exhausted() -> jiff::Error {
+    match DateTime::MAX.checked_add(Span::new().days(1)) {
+        Err(err) => err,
+        Ok(_) => unreachable!("the calendar ends at DateTime::MAX"),
+    }
+}
+
+fn days_in_month(month: i8) -> i8 {
+    match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl RRule {
+    pub fn parse(input: &str) -> Result<RRule, RRuleError> {
+        let input = input.trim();
+        let input = match input.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &input[6..],
+            _ => input,
+        };
+        let mut freq = None;
+        let mut rule = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_month: vec![],
+            by_month_day: vec![],
+            by_day: vec![],
+            by_hour: vec![],
+            by_minute: vec![],
+            by_set_pos: vec![],
+            week_start: Weekday::Monday,
+            until: None,
+        };
+
+        for part in input.split(';').filter(|part| !part.is_empty()) {
+            let invalid = || RRuleError::InvalidPart(part.to_string());
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+            let (key, value) = (key.to_ascii_uppercase(), value.to_ascii_uppercase());
+            let small = |numbers: Vec<i16>| numbers.into_iter().map(|n| n as i8).collect();
+            match key.as_str() {
+                "FREQ" => {
+                    freq = FREQUENCIES
+                        .iter()
+                        .find(|(name, _)| *name == value)
+                        .map(|(_, freq)| *freq);
+                    freq.ok_or_else(invalid)?;
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=1000).contains(n))
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTH" => {
+                    rule.by_month = small(numbers(&value, 1, 12, false).ok_or_else(invalid)?)
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = small(numbers(&value, 1, 31, true).ok_or_else(invalid)?)
+                }
+                "BYHOUR" => {
+                    rule.by_hour = small(numbers(&value, 0, 23, false).ok_or_else(invalid)?)
+                }
+                "BYMINUTE" => {
+                    rule.by_minute = small(numbers(&value, 0, 59, false).ok_or_else(invalid)?)
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = numbers(&value, 1, 366, true).ok_or_else(invalid)?
+                }
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|day| {
+                            let (nth, code) = day.split_at_checked(day.len().checked_sub(2)?)?;
+                            let nth = match nth {
+                                "" => 0,
+                                nth => {
+                                    nth.parse().ok().filter(|n: &i8| *n != 0 && n.abs() <= 53)?
+                                }
+                            };
+                            Some((nth, parse_weekday(code)?))
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "WKST" => rule.week_start = parse_weekday(&value).ok_or_else(invalid)?,
+                "UNTIL" => rule.until = Some(Until::parse(&value).ok_or_else(invalid)?),
+                _ => return Err(RRuleError::Unsupported(key)),
+            }
+        }
+        rule.freq = freq.ok_or(RRuleError::MissingFrequency)?;
+
+        // Ordinal weekdays like 1MO only make sense within a month or year
+        if rule.freq < Frequency::Monthly && rule.by_day.iter().any(|(nth, _)| *nth != 0) {
+            return Err(RRuleError::InvalidPart(format!(
+                "BYDAY with FREQ={}",
+                rule.freq_name()
+            )));
+        }
+        let months = match rule.by_month.as_slice() {
+            [] => (1..=12).collect(),
+            months => months.to_vec(),
+        };
+        let possible = rule.by_month_day.is_empty()
+            || months.iter().any(|month| {
+                rule.by_month_day
+                    .iter()
+                    .any(|day| day.abs() <= days_in_month(*month))
+            });
+        if !possible {
+            return Err(RRuleError::NeverMatches(rule.to_string()));
+        }
+        // A month has at most five of each weekday, 5MO is the furthest in
+        let in_month = rule.freq == Frequency::Monthly || !rule.by_month.is_empty();
+        if in_month && rule.by_day.iter().any(|(nth, _)| nth.abs() > 5) {
+            return Err(RRuleError::NeverMatches(rule.to_string()));
+        }
+        let largest = rule.largest_set() as i16;
+        if !rule.by_set_pos.is_empty() && rule.by_set_pos.iter().all(|pos| pos.abs() > largest) {
+            return Err(RRuleError::NeverMatches(rule.to_string()));
+        }
+        Ok(rule)
+    }
+
+    /// The most candidates one period can have, which BYSETPOS has to pick from.
+    fn largest_set(&self) -> usize {
+        let mut days = match self.freq {
+            Frequency::Minutely | Frequency::Hourly | Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+            Frequency::Monthly => 31,
+            Frequency::Yearly => 366,
+        };
+        let months = match self.by_month.len() {
+            0 => 12,
+            months => months,
+        };
+        if !self.by_month_day.is_empty() {
+            let per_month = self.by_month_day.len();
+            days = days.min(match self.freq {
+                Frequency::Yearly => per_month * months,
+                _ => per_month,
+            });
+        }
+        if !self.by_day.is_empty() {
+            let per_week = self.by_day.iter().filter(|(nth, _)| *nth == 0).count();
+            let ordinal = self.by_day.len() - per_week;
+            days = days.min(match self.freq {
+                Frequency::Weekly => per_week,
+                Frequency::Monthly => per_week * 5 + ordinal,
+                _ => per_week * 53 + ordinal * months,
+            });
+        }
+        let hours = if self.freq > Frequency::Hourly {
+            self.by_hour.len().max(1)
+        } else {
+            1
+        };
+        let minutes = if self.freq > Frequency::Minutely {
+            self.by_minute.len().max(1)
+        } else {
+            1
+        };
+        days * hours * minutes
+    }
+
+    fn freq_name(&self) -> &'static str {
+        FREQUENCIES
+            .iter()
+            .find(|(_, freq)| *freq == self.freq)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    fn period_start(&self, at: DateTime) -> Result<DateTime, jiff::Error> {
+        let date = at.date();
+        Ok(match self.freq {
+            Frequency::Minutely => date.at(at.hour(), at.minute(), 0, 0),
+            Frequency::Hourly => date.at(at.hour(), 0, 0, 0),
+            Frequency::Daily => date.at(0, 0, 0, 0),
+            Frequency::Weekly => {
+                let offset = (at.weekday().to_monday_zero_offset()
+                    - self.week_start.to_monday_zero_offset())
+                .rem_euclid(7);
+                date.checked_sub(Span::new().days(offset))?.at(0, 0, 0, 0)
+            }
+            Frequency::Monthly => date.first_of_month().at(0, 0, 0, 0),
+            Frequency::Yearly => date.first_of_year().at(0, 0, 0, 0),
+        })
+    }
+
+    fn period_span(&self) -> Span {
+        match self.freq {
+            Frequency::Minutely => Span::new().minutes(self.interval),
+            Frequency::Hourly => Span::new().hours(self.interval),
+            Frequency::Daily => Span::new().days(self.interval),
+            Frequency::Weekly => Span::new().weeks(self.interval),
+            Frequency::Monthly => Span::new().months(self.interval),
+            Frequency::Yearly => Span::new().years(self.interval),
+        }
+    }
+
+    fn date_matches(&self, day: Date, anchor: Date) -> bool {
+        if !self.by_month.is_empty() && !self.by_month.contains(&day.month()) {
+            return false;
+        }
+        let from_end = day.day() - day.days_in_month() - 1;
+        if !self.by_month_day.is_empty()
+            && !self
+                .by_month_day
+                .iter()
+                .any(|d| *d == day.day() || *d == from_end)
+        {
+            return false;
+        }
+        if !self.by_day.is_empty() {
+            let in_year = self.freq == Frequency::Yearly && self.by_month.is_empty();
+            let (index, len) = if in_year {
+                (day.day_of_year(), day.days_in_year())
+            } else {
+                (day.day() as i16, day.days_in_month() as i16)
+            };
+            let matched = self.by_day.iter().any(|(nth, weekday)| {
+                let nth = *nth as i16;
+                *weekday == day.weekday()
+                    && match nth {
+                        0 => true,
+                        1.. => (index - 1) / 7 + 1 == nth,
+                        _ => (len - index) / 7 + 1 == -nth,
+                    }
+            });
+            if !matched {
+                return false;
+            }
+        }
+
+        // Without BYxxx parts narrowing the day, recur on the same day as the previous occurrence
+        let unrestricted = self.by_day.is_empty() && self.by_month_day.is_empty();
+        match self.freq {
+            Frequency::Weekly if self.by_day.is_empty() => day.weekday() == anchor.weekday(),
+            Frequency::Monthly if unrestricted => day.day() == anchor.day(),
+            Frequency::Yearly if unrestricted => {
+                day.day() == anchor.day()
+                    && (!self.by_month.is_empty() || day.month() == anchor.month())
+            }
+            _ => true,
+        }
+    }
+
+    fn candidates(&self, period: DateTime, anchor: DateTime) -> Vec<DateTime> {
+        let start = period.date();
+        let days = match self.freq {
+            Frequency::Minutely | Frequency::Hourly | Frequency::Daily => 1,
+            Frequency::Weekly => 7,
+            Frequency::Monthly => start.days_in_month() as i64,
+            Frequency::Yearly => start.days_in_year() as i64,
+        };
+        let dates = (0..days)
+            .filter_map(|i| start.checked_add(Span::new().days(i)).ok())
+            .filter(|day| self.date_matches(*day, anchor.date()));
+
+        let select = |own: i8, by: &[i8], expand: bool, default: i8| {
+            let mut values = match (expand, by) {
+                (false, []) => vec![own],
+                (false, by) => by.iter().copied().filter(|v| *v == own).collect(),
+                (true, []) => vec![default],
+                (true, by) => by.to_vec(),
+            };
+            values.sort();
+            values.dedup();
+            values
+        };
+        let hours = select(
+            period.hour(),
+            &self.by_hour,
+            self.freq > Frequency::Hourly,
+            anchor.hour(),
+        );
+        let minutes = select(
+            period.minute(),
+            &self.by_minute,
+            self.freq > Frequency::Minutely,
+            anchor.minute(),
+        );
+
+        let mut set = vec![];
+        for day in dates {
+            for hour in &hours {
+                for minute in &minutes {
+                    set.push(day.at(*hour, *minute, anchor.second(), 0));
+                }
+            }
+        }
+        if self.by_set_pos.is_empty() {
+            return set;
+        }
+        let len = set.len() as i16;
+        let mut selected: Vec<_> = self
+            .by_set_pos
+            .iter()
+            .map(|pos| if *pos > 0 { pos - 1 } else { len + pos })
+            .filter(|index| (0..len).contains(index))
+            .map(|index| set[index as usize])
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+
+    /// Finds the first occurrence after `time`, or a time past the rule's end if it has one.
+    pub fn next_after(&self, time: &Zoned) -> Result<Zoned, jiff::Error> {
+        let tz = time.time_zone().clone();
+        let anchor = time.datetime();
+        let until = self.until.as_ref().map(|until| until.to_datetime(&tz));
+        let horizon = anchor.checked_add(Span::new().years(SEARCH_YEARS)).ok();
+        let mut period = self.period_start(anchor)?;
+        for _ in 0..MAX_PERIODS {
+            if until.is_some_and(|until| period > until) {
+                return period.to_zoned(tz);
+            }
+            if horizon.is_some_and(|horizon| period > horizon) {
+                break;
+            }
+            // Minutes and hours on a day that can't match are passed over all at once
+            if self.freq < Frequency::Daily && !self.date_matches(period.date(), anchor.date()) {
+                let midnight = period.date().tomorrow()?.at(0, 0, 0, 0);
+                let step = match self.freq {
+                    Frequency::Minutely => self.interval,
+                    _ => self.interval * 60,
+                };
+                let gap = period.duration_until(midnight).as_mins();
+                let steps = (gap + step - 1) / step;
+                period = period.checked_add(Span::new().minutes(steps * step))?;
+                continue;
+            }
+            for candidate in self.candidates(period, anchor) {
+                if candidate <= anchor {
+                    continue;
+                }
+                let zoned = candidate.to_zoned(tz.clone())?;
+                if zoned > *time {
+                    return Ok(zoned);
+                }
+            }
+            period = period.checked_add(self.period_span())?;
+        }
+        Err(exhausted())
+    }
+
+    pub fn ended(&self, time: &Zoned) -> bool {
+        self.until
+            .as_ref()
+            .is_some_and(|until| time.datetime() > until.to_datetime(time.time_zone()))
+    }
+}
+
+fn join<T: Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Display for RRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FREQ={}", self.freq_name())?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_month.is_empty() {
+            write!(f, ";BYMONTH={}", join(&self.by_month))?;
+        }
+        if !self.by_month_day.is_empty() {
+            write!(f, ";BYMONTHDAY={}", join(&self.by_month_day))?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self
+                .by_day
+                .iter()
+                .map(|(nth, weekday)| match nth {
+                    0 => weekday_code(*weekday).to_string(),
+                    nth => format!("{nth}{}", weekday_code(*weekday)),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_hour.is_empty() {
+            write!(f, ";BYHOUR={}", join(&self.by_hour))?;
+        }
+        if !self.by_minute.is_empty() {
+            write!(f, ";BYMINUTE={}", join(&self.by_minute))?;
+        }
+        if !self.by_set_pos.is_empty() {
+            write!(f, ";BYSETPOS={}", join(&self.by_set_pos))?;
+        }
+        if self.week_start != Weekday::Monday {
+            write!(f, ";WKST={}", weekday_code(self.week_start))?;
+        }
+        if let Some(until) = &self.until {
+            write!(f, ";UNTIL={until}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for RRule {
+    type Error = RRuleError;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        RRule::parse(&rule)
+    }
+}
+
+impl From<RRule> for String {
+    fn from(rule: RRule) -> Self {
+        rule.to_string()
+    }
+}
+
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+const DAY_MS: u64 = 24 * HOUR_MS;
+const WEEK_MS: u64 = 7 * DAY_MS;
+
+/// Expresses a modifier-based interval as an RRULE, if it has an exact equivalent.
+pub fn from_modifiers(interval: &[TimeModifier]) -> Option<String> {
+    let (base, rest) = interval.split_first()?;
+    let (freq, interval) = match base {
+        TimeModifier::RRule(rule) if rest.is_empty() => return Some(rule.to_string()),
+        TimeModifier::Delay(ms) => {
+            let ms = u64::try_from(*ms).ok()?;
+            [
+                (Frequency::Weekly, WEEK_MS),
+                (Frequency::Daily, DAY_MS),
+                (Frequency::Hourly, HOUR_MS),
+                (Frequency::Minutely, MINUTE_MS),
+            ]
+            .into_iter()
+            .find(|(_, unit)| ms > 0 && ms % unit == 0)
+            .map(|(freq, unit)| (freq, ms / unit))?
+        }
+        TimeModifier::Weekday(weekday) => {
+            let weekday = Weekday::from_monday_zero_offset(*weekday).ok()?;
+            let rule = format!("FREQ=WEEKLY;BYDAY={}", weekday_code(weekday));
+            return with_time_of_day(rule, rest);
+        }
+        TimeModifier::Months(months) => (Frequency::Monthly, *months),
+        TimeModifier::Years(years) => (Frequency::Yearly, *years),
+        _ => return None,
+    };
+    let freq_name = FREQUENCIES.iter().find(|(_, f)| *f == freq)?.0;
+    let mut rule = format!("FREQ={freq_name}");
+    if interval != 1 {
+        rule.push_str(&format!(";INTERVAL={interval}"));
+    }
+
+    let rest = match (freq, rest) {
+        (
+            Frequency::Monthly,
+            [TimeModifier::Date {
+                year: None,
+                month: None,
+                day,
+            }, rest @ ..],
+        ) => {
+            rule.push_str(&format!(";BYMONTHDAY={day}"));
+            rest
+        }
+        (
+            Frequency::Yearly,
+            [TimeModifier::Date {
+                year: None,
+                month: Some(month),
+                day,
+            }, rest @ ..],
+        ) => {
+            rule.push_str(&format!(";BYMONTH={month};BYMONTHDAY={day}"));
+            rest
+        }
+        (Frequency::Minutely | Frequency::Hourly, [_, ..]) => return None,
+        (_, rest) => rest,
+    };
+    with_time_of_day(rule, rest)
+}
+
+fn with_time_of_day(rule: String, rest: &[TimeModifier]) -> Option<String> {
+    match rest {
+        [] => Some(rule),
+        [TimeModifier::TimeOfDay { hour, minute }] => {
+            Some(format!("{rule};BYHOUR={hour};BYMINUTE={minute}"))
+        }
+        _ => None,
+    }
+}
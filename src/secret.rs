@@ -0,0 +1,53 @@
+//! Sealing tokens saved for integrations, so the state files alone don't give access to anyone's
+//! accounts elsewhere.
+
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+
+/// Reads a key of 64 hex digits from the environment variable `var`.
+pub fn key(var: &str) -> Option<LessSafeKey> {
+    let hex = std::env::var(var).ok()?;
+    let bytes = decode_hex(&hex)?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).ok()?;
+    Some(LessSafeKey::new(key))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn seal(key: &LessSafeKey, token: &str) -> String {
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .expect("No secure random source");
+    let mut sealed = token.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut sealed,
+    )
+    .expect("Token too long to seal");
+    encode_hex(&[&nonce[..], &sealed].concat())
+}
+
+pub fn open(key: &LessSafeKey, sealed: &str) -> Option<String> {
+    let bytes = decode_hex(sealed)?;
+    let (nonce, sealed) = bytes.split_at_checked(NONCE_LEN)?;
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut sealed = sealed.to_vec();
+    let token = key.open_in_place(nonce, Aad::empty(), &mut sealed).ok()?;
+    String::from_utf8(token.to_vec()).ok()
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    format_time, humanize, save, validate_message, validate_times, CommandError, Preferences,
+    Reminder, Reply,
+};
+
+pub const SEQUENCES_FILE: &str = "sequences.json";
+
+/// A step after the first, scheduled `delay` milliseconds after the previous one is marked done.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Step {
+    message: String,
+    delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sequence {
+    /// Every step, the first one's delay is unused since it has a time of its own
+    steps: Vec<Step>,
+    current: usize,
+    /// Key of the reminder for the current step
+    reminder: u64,
+}
+
+/// Each user's sequences by name.
+pub static SEQUENCES: LazyLock<Mutex<HashMap<UserId, BTreeMap<String, Sequence>>>> =
+    LazyLock::new(Default::default);
+
+pub enum SequenceCommand {
+    Mine,
+    Create {
+        name: String,
+        first: (String, Vec<Zoned>),
+        rest: Vec<(String, u64)>,
+    },
+    Done(String),
+    Cancel(String),
+}
+
+pub async fn load_sequences() {
+    let Ok(sequences_json) = tokio::fs::read_to_string(SEQUENCES_FILE).await else {
+        return;
+    };
+    let sequences = serde_json::from_str(&sequences_json).unwrap();
+    *SEQUENCES.lock().await = sequences;
+}
+
+/// Adds the reminder for the current step to `list`, returning its time.
+async fn schedule_step(
+    user: UserId,
+    list: &mut Vec<Reminder>,
+    name: &str,
+    sequence: &mut Sequence,
+    time: Zoned,
+) -> Zoned {
+    let step = &sequence.steps[sequence.current];
+    let reference = if name.contains(' ') {
+        format!("\"{name}\"")
+    } else {
+        name.to_string()
+    };
+    let reminder = Reminder::new(
+        time.clone(),
+        format!(
+            "{} ({name} step {}/{}, `$seq done {reference}` when finished)",
+            step.message,
+            sequence.current + 1,
+            sequence.steps.len()
+        ),
+    );
+    sequence.reminder = reminder.key;
+    audit::record(user, &reminder, Action::Created, "$seq").await;
+    list.push(reminder);
+    list.sort_by(|a, b| a.time.cmp(&b.time));
+    time
+}
+
+/// The name of the sequence whose current step is the reminder with `key`.
+pub async fn current_step(user: UserId, key: u64) -> Option<String> {
+    let sequences = SEQUENCES.lock().await;
+    sequences
+        .get(&user)?
+        .iter()
+        .find(|(_, sequence)| sequence.reminder == key)
+        .map(|(name, _)| name.clone())
+}
+
+/// Takes the current step's reminder out of `list` if it hasn't fired yet.
+fn unschedule(list: &mut Vec<Reminder>, sequence: &Sequence) {
+    list.retain(|reminder| reminder.key != sequence.reminder);
+}
+
+pub async fn handle(
+    user: UserId,
+    command: SequenceCommand,
+    list: &mut Vec<Reminder>,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let mut sequences = SEQUENCES.lock().await;
+    let mine = sequences.entry(user).or_default();
+    let response = match command {
+        SequenceCommand::Mine => {
+            if mine.is_empty() {
+                return Ok("You don't have any sequences".into());
+            }
+            let lines: Vec<_> = mine
+                .iter()
+                .map(|(name, sequence)| {
+                    format!(
+                        "{name}: step {}/{} - {}",
+                        sequence.current + 1,
+                        sequence.steps.len(),
+                        sequence.steps[sequence.current].message
+                    )
+                })
+                .collect();
+            return Ok(format!("Your sequences:\n{}", lines.join("\n")).into());
+        }
+        SequenceCommand::Create { name, first, rest } => {
+            if mine.contains_key(&name) {
+                return Err(SequenceExists(name));
+            }
+            let (message, times) = first;
+            let [time] = &times[..] else {
+                return Err(SequenceStart);
+            };
+            validate_times(&times, preferences)?;
+            let mut steps = vec![Step {
+                message: validate_message(&message)?,
+                delay: 0,
+            }];
+            for (message, delay) in rest {
+                steps.push(Step {
+                    message: validate_message(&message)?,
+                    delay,
+                });
+            }
+            let mut sequence = Sequence {
+                steps,
+                current: 0,
+                reminder: 0,
+            };
+            let time = schedule_step(user, list, &name, &mut sequence, time.clone()).await;
+            let response = format!(
+                "Created sequence {name} with {} steps, '{}' is first on {}",
+                sequence.steps.len(),
+                sequence.steps[0].message,
+                format_time(&time, preferences.time_format())
+            );
+            mine.insert(name, sequence);
+            response
+        }
+        SequenceCommand::Done(name) => {
+            let sequence = mine
+                .get_mut(&name)
+                .ok_or_else(|| UnknownSequence(name.clone()))?;
+            unschedule(list, sequence);
+            let finished = sequence.steps[sequence.current].message.clone();
+            sequence.current += 1;
+            match sequence.steps.get(sequence.current) {
+                Some(step) => {
+                    let delay = Duration::from_millis(step.delay);
+                    let time = &Zoned::now().with_time_zone(preferences.time_zone()) + delay;
+                    let time = schedule_step(user, list, &name, sequence, time).await;
+                    format!(
+                        "Marked '{finished}' done, '{}' is next {} on {}",
+                        sequence.steps[sequence.current].message,
+                        humanize::until(delay, preferences.language()),
+                        format_time(&time, preferences.time_format())
+                    )
+                }
+                None => {
+                    mine.remove(&name);
+                    format!("Marked '{finished}' done, that was the last step of {name}")
+                }
+            }
+        }
+        SequenceCommand::Cancel(name) => {
+            let sequence = mine
+                .remove(&name)
+                .ok_or_else(|| UnknownSequence(name.clone()))?;
+            unschedule(list, &sequence);
+            format!("Cancelled sequence {name}")
+        }
+    };
+    save();
+    Ok(response.into())
+}
@@ -0,0 +1,361 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+use serenity::all::{CreateMessage, Http, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit,
+    delivery::{self, Recipient},
+    delivery_message, format_time,
+    holidays::HolidayCalendar,
+    interval, log_error, reschedule, save, validate_message, validate_times, CommandError,
+    Preferences, Reminder, Reply, TimeModifier,
+};
+
+pub const SHARED_LISTS_FILE: &str = "shared_lists.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SharedList {
+    /// Only unique among the lists each member is on, so different people can use the same name
+    #[serde(default)]
+    name: String,
+    owner: UserId,
+    subscribers: Vec<UserId>,
+    /// Added by the owner but not on the list until they accept
+    #[serde(default)]
+    invited: Vec<UserId>,
+    reminders: Vec<Reminder>,
+    /// Who scheduled each reminder by key, they and the owner are the only ones who can change it
+    #[serde(default)]
+    creators: HashMap<u64, UserId>,
+}
+
+pub static SHARED_LISTS: LazyLock<Mutex<Vec<SharedList>>> = LazyLock::new(Default::default);
+
+pub enum SharedListCommand {
+    Mine,
+    Create(String),
+    Delete(String),
+    Show(String),
+    Add(String, u64),
+    Remove(String, u64),
+    Leave(String),
+    Accept(String),
+    Decline(String),
+    Cancel(String, u64),
+    Repeat(String, u64, Vec<TimeModifier>),
+    Jitter(String, u64, Option<u64>),
+}
+
+pub async fn load_shared_lists() {
+    let Ok(lists_json) = tokio::fs::read_to_string(SHARED_LISTS_FILE).await else {
+        return;
+    };
+    // Lists used to be saved by their name when names were global
+    let mut lists: Vec<SharedList> = match serde_json::from_str(&lists_json) {
+        Ok(lists) => lists,
+        Err(_) => {
+            let named: HashMap<String, SharedList> = serde_json::from_str(&lists_json).unwrap();
+            named
+                .into_iter()
+                .map(|(name, list)| SharedList { name, ..list })
+                .collect()
+        }
+    };
+    // Keys tell occurrences apart for `dedup`, so older lists need them too
+    audit::assign_keys(lists.iter_mut().flat_map(|list| list.reminders.iter_mut()));
+    *SHARED_LISTS.lock().await = lists;
+}
+
+fn member<'a>(
+    lists: &'a mut [SharedList],
+    name: &str,
+    user: UserId,
+) -> Result<&'a mut SharedList, CommandError> {
+    lists
+        .iter_mut()
+        .find(|list| list.name == name && list.subscribers.contains(&user))
+        .ok_or_else(|| CommandError::UnknownList(name.to_string()))
+}
+
+fn owner<'a>(
+    lists: &'a mut [SharedList],
+    name: &str,
+    user: UserId,
+) -> Result<&'a mut SharedList, CommandError> {
+    let list = member(lists, name, user)?;
+    if list.owner != user {
+        return Err(CommandError::NotListOwner(name.to_string()));
+    }
+    Ok(list)
+}
+
+/// The list called `name` that `user` was invited to.
+fn invitation<'a>(
+    lists: &'a mut [SharedList],
+    name: &str,
+    user: UserId,
+) -> Result<&'a mut SharedList, CommandError> {
+    lists
+        .iter_mut()
+        .find(|list| list.name == name && list.invited.contains(&user))
+        .ok_or_else(|| CommandError::NotInvited(name.to_string()))
+}
+
+/// Whether `user` is on a list called `name`, which would make the name ambiguous for them.
+fn on_list_named(lists: &[SharedList], name: &str, user: UserId) -> bool {
+    lists
+        .iter()
+        .any(|list| list.name == name && list.subscribers.contains(&user))
+}
+
+/// The reminder at `id`, if `user` scheduled it or owns the list.
+fn editable(list: &mut SharedList, id: u64, user: UserId) -> Result<&mut Reminder, CommandError> {
+    let reminder = list
+        .reminders
+        .get_mut(id as usize)
+        .ok_or(CommandError::InvalidID(id))?;
+    if list.owner != user && list.creators.get(&reminder.key) != Some(&user) {
+        return Err(CommandError::NotListReminderCreator(list.name.clone()));
+    }
+    Ok(reminder)
+}
+
+pub async fn schedule(
+    user: UserId,
+    name: String,
+    times: Vec<Zoned>,
+    message: String,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    let message = validate_message(&message)?;
+    validate_times(&times, preferences)?;
+    let mut lists = SHARED_LISTS.lock().await;
+    let list = member(&mut lists, &name, user)?;
+    let mut lines = vec![];
+    for time in times {
+        let key = audit::next_key();
+        list.creators.insert(key, user);
+        list.reminders.push(Reminder {
+            key,
+            ..Reminder::new(time.clone(), message.clone())
+        });
+        list.reminders.sort_by(|a, b| a.time.cmp(&b.time));
+        let id = list
+            .reminders
+            .iter()
+            .position(|r| r.time == time && r.message == message)
+            .expect("Reminder was not inserted");
+        lines.push(format!(
+            "Scheduled reminder for {} on list {name} (#{id}), {} subscribers will be notified",
+            format_time(&time, preferences.time_format()),
+            list.subscribers.len()
+        ));
+    }
+    save();
+    Ok(lines.join("\n").into())
+}
+
+pub async fn handle(
+    http: &Http,
+    user: UserId,
+    command: SharedListCommand,
+    preferences: &Preferences,
+) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let mut lists = SHARED_LISTS.lock().await;
+    let response = match command {
+        SharedListCommand::Mine => {
+            let mut names: Vec<_> = lists
+                .iter()
+                .filter(|list| list.subscribers.contains(&user))
+                .map(|list| format!("{} ({} reminders)", list.name, list.reminders.len()))
+                .collect();
+            let mut invitations: Vec<_> = lists
+                .iter()
+                .filter(|list| list.invited.contains(&user))
+                .map(|list| format!("{} from <@{}>", list.name, list.owner))
+                .collect();
+            if names.is_empty() && invitations.is_empty() {
+                return Ok("You aren't on any shared lists".into());
+            }
+            names.sort();
+            invitations.sort();
+            let mut lines = vec![];
+            if !names.is_empty() {
+                lines.push(format!("Your shared lists:\n{}", names.join("\n")));
+            }
+            if !invitations.is_empty() {
+                lines.push(format!(
+                    "Invitations, join with `$list accept <name>`:\n{}",
+                    invitations.join("\n")
+                ));
+            }
+            lines.join("\n\n")
+        }
+        SharedListCommand::Create(name) => {
+            if on_list_named(&lists, &name, user) {
+                return Err(ListExists(name));
+            }
+            lists.push(SharedList {
+                name: name.clone(),
+                owner: user,
+                subscribers: vec![user],
+                invited: vec![],
+                reminders: vec![],
+                creators: HashMap::new(),
+            });
+            format!("Created shared list {name}, invite people with `$list add {name} @user`")
+        }
+        SharedListCommand::Delete(name) => {
+            owner(&mut lists, &name, user)?;
+            lists.retain(|list| list.name != name || list.owner != user);
+            format!("Deleted shared list {name}")
+        }
+        SharedListCommand::Show(name) => {
+            let list = member(&mut lists, &name, user)?;
+            let subscribers: Vec<_> = list
+                .subscribers
+                .iter()
+                .map(|user| format!("<@{user}>"))
+                .collect();
+            let mut lines = vec![format!("Subscribers: {}", subscribers.join(", "))];
+            if !list.invited.is_empty() {
+                let invited: Vec<_> = list
+                    .invited
+                    .iter()
+                    .map(|user| format!("<@{user}>"))
+                    .collect();
+                lines.push(format!("Invited: {}", invited.join(", ")));
+            }
+            for (id, reminder) in list.reminders.iter().enumerate() {
+                let mut line = format!(
+                    "{id}: {} - {}",
+                    format_time(&reminder.time, preferences.time_format()),
+                    &reminder.message
+                );
+                if let Some(interval) = &reminder.interval {
+                    line.push_str(&format!(
+                        " (Repeats {})",
+                        interval::describe(interval, preferences.time_format())
+                    ));
+                }
+                lines.push(line);
+            }
+            lines.join("\n")
+        }
+        SharedListCommand::Add(name, subscriber) => {
+            let list = owner(&mut lists, &name, user)?;
+            let subscriber = match subscriber {
+                0 => return Err(InvalidRecipient),
+                subscriber => UserId::new(subscriber),
+            };
+            if list.subscribers.contains(&subscriber) {
+                return Ok(format!("<@{subscriber}> is already on {name}").into());
+            }
+            if !list.invited.contains(&subscriber) {
+                list.invited.push(subscriber);
+            }
+            let invite = CreateMessage::new().content(format!(
+                "<@{user}> invited you to the shared list {name}, join with `$list accept {name}` \
+                or turn it down with `$list decline {name}`"
+            ));
+            log_error(delivery::send(http, Recipient::User(subscriber), invite).await);
+            format!("Invited <@{subscriber}> to {name}, they'll be added once they accept")
+        }
+        SharedListCommand::Remove(name, subscriber) => {
+            let list = owner(&mut lists, &name, user)?;
+            let subscriber = match subscriber {
+                0 => return Err(InvalidRecipient),
+                subscriber => UserId::new(subscriber),
+            };
+            if subscriber == list.owner {
+                return Err(InvalidRecipient);
+            }
+            list.subscribers.retain(|s| *s != subscriber);
+            list.invited.retain(|s| *s != subscriber);
+            format!("Removed <@{subscriber}> from {name}")
+        }
+        SharedListCommand::Leave(name) => {
+            let list = member(&mut lists, &name, user)?;
+            if list.owner == user {
+                return Err(OwnerCannotLeave(name));
+            }
+            list.subscribers.retain(|s| *s != user);
+            format!("Left shared list {name}")
+        }
+        SharedListCommand::Accept(name) => {
+            if on_list_named(&lists, &name, user) {
+                return Err(ListExists(name));
+            }
+            let list = invitation(&mut lists, &name, user)?;
+            list.invited.retain(|s| *s != user);
+            list.subscribers.push(user);
+            format!("Joined shared list {name}")
+        }
+        SharedListCommand::Decline(name) => {
+            let list = invitation(&mut lists, &name, user)?;
+            list.invited.retain(|s| *s != user);
+            format!("Turned down the invitation to {name}")
+        }
+        SharedListCommand::Cancel(name, id) => {
+            let list = member(&mut lists, &name, user)?;
+            editable(list, id, user)?;
+            let reminder = list.reminders.remove(id as usize);
+            list.creators.remove(&reminder.key);
+            format!("Removed reminder '{}' from {name}", reminder.message)
+        }
+        SharedListCommand::Repeat(name, id, modifiers) => {
+            let list = member(&mut lists, &name, user)?;
+            let reminder = editable(list, id, user)?;
+            let description = interval::describe(&modifiers, preferences.time_format());
+            reminder.interval = Some(modifiers);
+            format!(
+                "Reminder '{}' on {name} now repeats {description}",
+                &reminder.message
+            )
+        }
+        SharedListCommand::Jitter(name, id, jitter) => {
+            let window = delivery::describe_jitter(jitter)?;
+            let list = owner(&mut lists, &name, user)?;
+            let reminder = list.reminders.get_mut(id as usize).ok_or(InvalidID(id))?;
+            reminder.jitter = jitter;
+            format!(
+                "Reminder '{}' on {name} is now {window} for its {} subscribers",
+                &reminder.message,
+                list.subscribers.len()
+            )
+        }
+    };
+    save();
+    Ok(response.into())
+}
+
+pub async fn process_shared_lists(http: &Http) {
+    let mut lists = SHARED_LISTS.lock().await;
+    let now = Zoned::now();
+    for list in lists.iter_mut() {
+        while list.reminders.first().is_some_and(|f| f.time < now) {
+            let first = list.reminders.remove(0);
+            reschedule(&mut list.reminders, &first, &HolidayCalendar::default()).await;
+            if !list.reminders.iter().any(|r| r.key == first.key) {
+                list.creators.remove(&first.key);
+            }
+            let labelled = Reminder {
+                message: format!("[{}] {}", list.name, first.message),
+                ..first
+            };
+            let message = delivery_message(http, &labelled).await;
+            delivery::fan_out(
+                http,
+                &list.subscribers,
+                message,
+                &labelled,
+                Some(&labelled.message),
+            )
+            .await;
+        }
+    }
+}
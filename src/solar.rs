@@ -0,0 +1,90 @@
+use std::fmt::Display;
+
+use jiff::{civil::Date, tz::TimeZone, Timestamp, Unit};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SunEvent {
+    Sunrise,
+    Sunset,
+}
+
+impl Display for SunEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SunEvent::Sunrise => write!(f, "sunrise"),
+            SunEvent::Sunset => write!(f, "sunset"),
+        }
+    }
+}
+
+/// Degrees, north and east positive.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Location {
+    pub fn new(latitude: f64, longitude: f64) -> Option<Location> {
+        (latitude.abs() < 90.0 && longitude.abs() <= 180.0).then_some(Location {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.4}, {:.4}", self.latitude, self.longitude)
+    }
+}
+
+const J2000: f64 = 2451545.0;
+const UNIX_EPOCH_JULIAN: f64 = 2440587.5;
+/// Days from the Unix epoch to 2000-01-01.
+const J2000_DAYS: i64 = 10957;
+/// The sun's center is this far below the horizon at sunrise, for refraction and its radius.
+const HORIZON: f64 = -0.833;
+const OBLIQUITY: f64 = 23.4397;
+
+/// When `event` happens on `date` at `location`, using the sunrise equation. `None` during polar
+/// day or night.
+fn on(date: Date, event: SunEvent, location: Location) -> Result<Option<Timestamp>, jiff::Error> {
+    let days = date.to_zoned(TimeZone::UTC)?.timestamp().as_second() / 86400;
+    let mean_solar_time = (days - J2000_DAYS) as f64 - location.longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit =
+        J2000 + mean_solar_time + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+    let declination = (ecliptic_longitude.sin() * OBLIQUITY.to_radians().sin()).asin();
+    let latitude = location.latitude.to_radians();
+    let cos_hour_angle = (HORIZON.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return Ok(None);
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees() / 360.0;
+    let julian = match event {
+        SunEvent::Sunrise => transit - hour_angle,
+        SunEvent::Sunset => transit + hour_angle,
+    };
+    let seconds = ((julian - UNIX_EPOCH_JULIAN) * 86400.0).round() as i64;
+    Ok(Some(Timestamp::from_second(seconds)?.round(Unit::Minute)?))
+}
+
+/// The first `event` on or after `date`, skipping days the sun doesn't rise or set.
+pub fn next(date: Date, event: SunEvent, location: Location) -> Result<Timestamp, jiff::Error> {
+    let mut date = date;
+    loop {
+        if let Some(time) = on(date, event, location)? {
+            return Ok(time);
+        }
+        date = date.tomorrow()?;
+    }
+}
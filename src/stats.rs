@@ -0,0 +1,71 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use jiff::{Timestamp, Zoned};
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use tokio::sync::RwLock;
+
+pub const STATS_FILE: &str = "stats.json";
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UserStats {
+    pub scheduled: u64,
+    pub delivered: u64,
+    pub cancelled: u64,
+    pub completed: u64,
+    /// Snoozed from the button under a delivered reminder
+    pub snoozed: u64,
+    /// By reminder key, for repeating reminders
+    pub streaks: BTreeMap<u64, Streak>,
+}
+
+/// How many occurrences of a repeating reminder in a row were marked done.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Streak {
+    pub current: u64,
+    pub best: u64,
+    /// Whether the latest delivered occurrence is still waiting to be marked done
+    pub open: bool,
+    /// Occurrences that fired again before being marked done
+    pub misses: u64,
+    /// When the open occurrence was delivered
+    pub opened: Option<Timestamp>,
+    /// Whether the habit nudge went out for the open occurrence
+    pub nudged: bool,
+}
+
+pub static STATS: LazyLock<RwLock<HashMap<UserId, UserStats>>> = LazyLock::new(Default::default);
+
+pub async fn get_stats(user: UserId) -> UserStats {
+    STATS.read().await.get(&user).cloned().unwrap_or_default()
+}
+
+pub async fn record(user: UserId, cb: impl FnOnce(&mut UserStats)) {
+    cb(STATS.write().await.entry(user).or_default());
+}
+
+pub async fn load_stats() {
+    let Ok(stats_json) = tokio::fs::read_to_string(STATS_FILE).await else {
+        return;
+    };
+    let stats = serde_json::from_str(&stats_json).unwrap();
+    *STATS.write().await = stats;
+}
+
+/// Forgets the streaks of reminders that aren't in `live` any more and weren't delivered since
+/// `cutoff`.
+pub async fn purge_streaks(cutoff: &Zoned, live: &HashMap<UserId, HashSet<u64>>) {
+    let cutoff = cutoff.timestamp();
+    for (user, stats) in STATS.write().await.iter_mut() {
+        let keys = live.get(user);
+        stats.streaks.retain(|key, streak| {
+            keys.is_some_and(|keys| keys.contains(key))
+                || streak.opened.is_some_and(|opened| opened >= cutoff)
+        });
+    }
+}
@@ -0,0 +1,199 @@
+//! Watches how big the save files get, so runaway recurring reminders or import loops are caught
+//! before they fill the disk.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{LazyLock, Mutex},
+};
+
+use serenity::all::Http;
+
+use crate::{
+    announcement::ANNOUNCEMENTS_FILE,
+    audit::{self, Action, AUDIT_FILE},
+    bookmarks::BOOKMARKS_FILE,
+    calendar::CALENDAR_FILE,
+    checklist::CHECKLISTS_FILE,
+    crash,
+    dedup::SENT_FILE,
+    delivery::HISTORY_FILE,
+    expiry::EXPIRING_FILE,
+    feeds::FEEDS_FILE,
+    flush,
+    guild::GUILD_SETTINGS_FILE,
+    integrity::BROKEN_FILE,
+    matrix::MATRIX_FILE,
+    outbox::OUTBOX_FILE,
+    presence::HELD_FILE,
+    sequence::SEQUENCES_FILE,
+    shared::SHARED_LISTS_FILE,
+    stats::STATS_FILE,
+    tasks::TASKS_FILE,
+    telegram::TELEGRAM_FILE,
+    transfer::TRANSFERS_FILE,
+    trigger::TRIGGERS_FILE,
+    usage::USAGE_FILE,
+    PREFERENCES_FILE, REMINDERS, SAVE_FILE,
+};
+
+const FILES: &[&str] = &[
+    SAVE_FILE,
+    PREFERENCES_FILE,
+    GUILD_SETTINGS_FILE,
+    SHARED_LISTS_FILE,
+    SEQUENCES_FILE,
+    ANNOUNCEMENTS_FILE,
+    STATS_FILE,
+    USAGE_FILE,
+    HISTORY_FILE,
+    AUDIT_FILE,
+    TELEGRAM_FILE,
+    MATRIX_FILE,
+    CALENDAR_FILE,
+    TASKS_FILE,
+    FEEDS_FILE,
+    HELD_FILE,
+    TRIGGERS_FILE,
+    BOOKMARKS_FILE,
+    CHECKLISTS_FILE,
+    OUTBOX_FILE,
+    SENT_FILE,
+    EXPIRING_FILE,
+    TRANSFERS_FILE,
+    BROKEN_FILE,
+];
+
+/// Growth by more than this factor between two checks is treated as abnormal.
+const GROWTH_FACTOR: u64 = 2;
+/// Files smaller than this are never reported, doubling from a few bytes is normal.
+const MIN_ALERT_SIZE: u64 = 1024 * 1024;
+/// How many of the users with the most reminders are named in an alert.
+const TOP_USERS: usize = 3;
+
+/// Sizes from the last check, to compare against.
+static LAST: LazyLock<Mutex<BTreeMap<&'static str, u64>>> = LazyLock::new(Default::default);
+
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Sizes of the save files that exist, in bytes.
+pub async fn sizes() -> Vec<(&'static str, u64)> {
+    let mut sizes = vec![];
+    for file in FILES {
+        if let Ok(metadata) = tokio::fs::metadata(file).await {
+            sizes.push((*file, metadata.len()));
+        }
+    }
+    sizes
+}
+
+/// One line for `$admin tasks`.
+pub fn summary() -> String {
+    let last = LAST.lock().unwrap();
+    let total: u64 = last.values().sum();
+    format!(
+        "Storage: {} across {} files at the last check",
+        format_size(total),
+        last.len()
+    )
+}
+
+/// Every file with its size and change since the last check, for `$admin stats`.
+pub async fn report() -> String {
+    let sizes = sizes().await;
+    let last = LAST.lock().unwrap();
+    let mut lines = vec![];
+    for (file, size) in &sizes {
+        let mut line = format!("{file}: {}", format_size(*size));
+        if let Some(previous) = last.get(file).filter(|previous| *previous != size) {
+            let sign = if size > previous { "+" } else { "-" };
+            line.push_str(&format!(
+                " ({sign}{} since the last check)",
+                format_size(size.abs_diff(*previous))
+            ));
+        }
+        lines.push(line);
+    }
+    let total: u64 = sizes.iter().map(|(_, size)| size).sum();
+    lines.push(format!("Total: {}", format_size(total)));
+    lines.join("\n")
+}
+
+/// Drops reminders that are exact copies of another one the same user has, which is what an
+/// import loop or a runaway client leaves behind. Returns how many were dropped.
+pub async fn compact() -> usize {
+    let mut cache = REMINDERS.lock().await;
+    let mut dropped = vec![];
+    for (user, list) in cache.iter_mut() {
+        let mut seen = HashSet::new();
+        let mut index = 0;
+        while index < list.len() {
+            let reminder = &list[index];
+            let identity = (
+                reminder.time.timestamp(),
+                reminder.message.clone(),
+                reminder.channel,
+            );
+            if seen.insert(identity) {
+                index += 1;
+            } else {
+                dropped.push((*user, list.remove(index)));
+            }
+        }
+    }
+    drop(cache);
+    for (user, reminder) in &dropped {
+        audit::record(*user, reminder, Action::Cancelled, "compaction").await;
+    }
+    dropped.len()
+}
+
+/// Compares file sizes with the last check, compacting and alerting the operator when one grew
+/// abnormally fast.
+pub async fn check(http: &Http) {
+    let sizes = sizes().await;
+    let grown: Vec<_> = {
+        let last = LAST.lock().unwrap();
+        sizes
+            .iter()
+            .filter(|(file, size)| {
+                *size >= MIN_ALERT_SIZE
+                    && last
+                        .get(file)
+                        .is_some_and(|previous| *size > previous * GROWTH_FACTOR)
+            })
+            .map(|(file, size)| format!("{file} is now {}", format_size(*size)))
+            .collect()
+    };
+    *LAST.lock().unwrap() = sizes.into_iter().collect();
+    if grown.is_empty() {
+        return;
+    }
+
+    let dropped = compact().await;
+    flush().await;
+    let cache = REMINDERS.lock().await;
+    let mut users: Vec<_> = cache
+        .iter()
+        .map(|(user, list)| (list.len(), *user))
+        .collect();
+    drop(cache);
+    users.sort_unstable_by(|a, b| b.cmp(a));
+    let top: Vec<_> = users
+        .iter()
+        .take(TOP_USERS)
+        .map(|(count, user)| format!("<@{user}> has {count}"))
+        .collect();
+    let content = format!(
+        "**reedbot storage warning**\nSave files more than doubled since the last check:\n{}\n\
+        Compaction dropped {dropped} duplicate reminders. Most reminders: {}",
+        grown.join("\n"),
+        top.join(", ")
+    );
+    crash::alert(http, &content).await;
+}
@@ -0,0 +1,72 @@
+//! The time grammar as a library, for tools that want to parse `$r` times the way the bot does
+//! and point at the part of the input each piece came from.
+
+use std::ops::Range;
+
+use jiff::{tz::TimeZone, Zoned};
+use untwine::prelude::ParserContext;
+
+use crate::{command, Preferences};
+
+pub use crate::command::Modifier;
+
+/// A parsed time, each modifier with the byte range of the input it was read from.
+#[derive(Debug)]
+pub struct ParsedTime {
+    pub modifiers: Vec<(Range<usize>, Modifier)>,
+    /// Every time the modifiers resolve to, more than one when they include permutations
+    pub times: Vec<Zoned>,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+fn context(time_zone: TimeZone) -> command::TimeContext {
+    let mut context = Preferences::default().time_context();
+    context.time_zone = time_zone;
+    context
+}
+
+fn first_error<E: ToString>(errors: Vec<(Range<usize>, E)>) -> ParseError {
+    let (span, error) = errors
+        .into_iter()
+        .next()
+        .expect("A failed parse has an error");
+    ParseError {
+        span,
+        message: error.to_string(),
+    }
+}
+
+/// Parses a time like `1d (9am, 5pm)` relative to now, with default preferences.
+pub fn parse_time(input: &str, time_zone: TimeZone) -> Result<ParsedTime, ParseError> {
+    let mut parser_context = ParserContext::new(input, context(time_zone));
+    let (modifiers, times) = parser_context
+        .result(command::time(&parser_context))
+        .map_err(first_error)?;
+    Ok(ParsedTime { modifiers, times })
+}
+
+/// Checks that a whole command, without its prefix, would be accepted.
+pub fn check_command(input: &str, time_zone: TimeZone) -> Result<(), ParseError> {
+    let input = command::normalize(input);
+    let mut parser_context = ParserContext::new(&input, context(time_zone));
+    parser_context
+        .result(command::command(&parser_context))
+        .map(|_| ())
+        .map_err(first_error)
+}
+
+/// The input with the span underlined on the line below, for a code block.
+pub fn highlight(input: &str, span: &Range<usize>) -> String {
+    let start = input
+        .get(..span.start)
+        .map_or(0, |before| before.chars().count());
+    let width = input
+        .get(span.clone())
+        .map_or(1, |marked| marked.chars().count().max(1));
+    format!("{input}\n{}{}", " ".repeat(start), "^".repeat(width))
+}
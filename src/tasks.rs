@@ -0,0 +1,457 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    sync::LazyLock,
+    time::Duration,
+};
+
+use jiff::{civil::Date, tz::TimeZone, Timestamp, Zoned};
+use ring::aead::LessSafeKey;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    get_preferences, save,
+    secret::{self, open, seal},
+    validate_message,
+    web::CLIENT,
+    CommandError, Reminder, Reply, REMINDERS,
+};
+
+pub const TASKS_FILE: &str = "tasks.json";
+
+/// How often linked task lists are checked for new tasks.
+pub const INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Hour of the day reminders for tasks due on a date without a time are set for.
+const DATE_ONLY_HOUR: i8 = 9;
+const TODOIST_API: &str = "https://api.todoist.com/rest/v2";
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Todoist,
+    Notion,
+}
+
+impl Display for ProviderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProviderKind::Todoist => "Todoist",
+            ProviderKind::Notion => "Notion",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Account {
+    /// The API token, sealed with the key in `TASKS_KEY`
+    token: String,
+    /// The Notion database tasks are kept in
+    database: Option<String>,
+    /// Tasks already turned into reminders
+    #[serde(default)]
+    imported: BTreeSet<String>,
+}
+
+pub static ACCOUNTS: LazyLock<Mutex<HashMap<UserId, BTreeMap<ProviderKind, Account>>>> =
+    LazyLock::new(Default::default);
+
+pub enum TasksCommand {
+    Show,
+    Link {
+        provider: ProviderKind,
+        token: String,
+        database: Option<String>,
+    },
+    Unlink(ProviderKind),
+    Push(u64, ProviderKind),
+}
+
+/// A task with a due time, from any provider.
+pub struct Task {
+    id: String,
+    title: String,
+    due: Zoned,
+}
+
+/// A task app reminders can be exported to and imported from.
+pub trait TaskProvider {
+    async fn export(&self, reminder: &Reminder) -> Result<(), String>;
+
+    /// Open tasks that have a due date, due dates without a time are read in `time_zone`.
+    async fn due_tasks(&self, time_zone: &TimeZone) -> Result<Vec<Task>, String>;
+}
+
+pub async fn load_tasks() {
+    let Ok(tasks_json) = tokio::fs::read_to_string(TASKS_FILE).await else {
+        return;
+    };
+    let mut accounts: HashMap<UserId, BTreeMap<ProviderKind, Account>> =
+        serde_json::from_str(&tasks_json).unwrap();
+    // Left behind by `$tasks` before it stopped adding empty entries
+    accounts.retain(|_, mine| !mine.is_empty());
+    *ACCOUNTS.lock().await = accounts;
+}
+
+/// The integration is enabled by setting `TASKS_KEY` to 64 hex digits, which seal saved tokens.
+fn key() -> Option<LessSafeKey> {
+    secret::key("TASKS_KEY")
+}
+
+/// Reads a due date that may or may not have a time.
+fn due_time(due: &str, time_zone: &TimeZone) -> Option<Zoned> {
+    if let Ok(timestamp) = due.parse::<Timestamp>() {
+        return Some(timestamp.to_zoned(time_zone.clone()));
+    }
+    let date: Date = due.parse().ok()?;
+    date.at(DATE_ONLY_HOUR, 0, 0, 0)
+        .to_zoned(time_zone.clone())
+        .ok()
+}
+
+fn request_error(err: reqwest::Error) -> String {
+    err.without_url().to_string()
+}
+
+pub struct Todoist {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct TodoistTask {
+    id: String,
+    content: String,
+    due: Option<TodoistDue>,
+}
+
+#[derive(Deserialize)]
+struct TodoistDue {
+    date: String,
+    datetime: Option<String>,
+}
+
+impl TaskProvider for Todoist {
+    async fn export(&self, reminder: &Reminder) -> Result<(), String> {
+        CLIENT
+            .post(format!("{TODOIST_API}/tasks"))
+            .bearer_auth(&self.token)
+            .json(&json!({
+                "content": reminder.message,
+                "due_datetime": reminder.time.timestamp().to_string(),
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(request_error)
+    }
+
+    async fn due_tasks(&self, time_zone: &TimeZone) -> Result<Vec<Task>, String> {
+        let tasks: Vec<TodoistTask> = CLIENT
+            .get(format!("{TODOIST_API}/tasks"))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(request_error)?
+            .json()
+            .await
+            .map_err(request_error)?;
+        Ok(tasks
+            .into_iter()
+            .filter_map(|task| {
+                let due = task.due?;
+                let due = due_time(due.datetime.as_ref().unwrap_or(&due.date), time_zone)?;
+                Some(Task {
+                    id: task.id,
+                    title: task.content,
+                    due,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Tasks are pages in a database, named by its title property and due by its first date property.
+pub struct Notion {
+    token: String,
+    database: String,
+}
+
+impl Notion {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        CLIENT
+            .request(method, format!("{NOTION_API}/{path}"))
+            .bearer_auth(&self.token)
+            .header("Notion-Version", NOTION_VERSION)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(request_error)?
+            .json()
+            .await
+            .map_err(request_error)
+    }
+}
+
+/// The name of the first property of type `kind`.
+fn property_named(properties: &serde_json::Value, kind: &str) -> Option<String> {
+    properties
+        .as_object()?
+        .iter()
+        .find(|(_, property)| property["type"] == kind)
+        .map(|(name, _)| name.clone())
+}
+
+impl TaskProvider for Notion {
+    async fn export(&self, reminder: &Reminder) -> Result<(), String> {
+        let path = format!("databases/{}", self.database);
+        let database = self.send(self.request(reqwest::Method::GET, &path)).await?;
+        let properties = &database["properties"];
+        let title = property_named(properties, "title").ok_or("The database has no title")?;
+        let date = property_named(properties, "date").ok_or("The database has no date property")?;
+        let page = json!({
+            "parent": {"database_id": self.database},
+            "properties": {
+                title: {"title": [{"text": {"content": reminder.message}}]},
+                date: {"date": {"start": reminder.time.timestamp().to_string()}},
+            },
+        });
+        self.send(self.request(reqwest::Method::POST, "pages").json(&page))
+            .await
+            .map(|_| ())
+    }
+
+    async fn due_tasks(&self, time_zone: &TimeZone) -> Result<Vec<Task>, String> {
+        let path = format!("databases/{}/query", self.database);
+        let results = self
+            .send(self.request(reqwest::Method::POST, &path).json(&json!({})))
+            .await?;
+        let pages = results["results"].as_array().cloned().unwrap_or_default();
+        Ok(pages
+            .iter()
+            .filter_map(|page| {
+                let properties = page["properties"].as_object()?;
+                let title = properties.values().find(|p| p["type"] == "title")?;
+                let title: String = title["title"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|text| text["plain_text"].as_str())
+                    .collect();
+                let date = properties.values().find(|p| p["type"] == "date")?;
+                let due = due_time(date["date"]["start"].as_str()?, time_zone)?;
+                Some(Task {
+                    id: page["id"].as_str()?.to_string(),
+                    title,
+                    due,
+                })
+            })
+            .collect())
+    }
+}
+
+/// The provider for a linked account, whichever kind it is.
+enum Provider {
+    Todoist(Todoist),
+    Notion(Notion),
+}
+
+impl Provider {
+    fn new(key: &LessSafeKey, kind: ProviderKind, account: &Account) -> Result<Self, String> {
+        let token = open(key, &account.token).ok_or("The saved token couldn't be unsealed")?;
+        Ok(match kind {
+            ProviderKind::Todoist => Provider::Todoist(Todoist { token }),
+            ProviderKind::Notion => Provider::Notion(Notion {
+                token,
+                database: account.database.clone().unwrap_or_default(),
+            }),
+        })
+    }
+}
+
+impl TaskProvider for Provider {
+    async fn export(&self, reminder: &Reminder) -> Result<(), String> {
+        match self {
+            Provider::Todoist(todoist) => todoist.export(reminder).await,
+            Provider::Notion(notion) => notion.export(reminder).await,
+        }
+    }
+
+    async fn due_tasks(&self, time_zone: &TimeZone) -> Result<Vec<Task>, String> {
+        match self {
+            Provider::Todoist(todoist) => todoist.due_tasks(time_zone).await,
+            Provider::Notion(notion) => notion.due_tasks(time_zone).await,
+        }
+    }
+}
+
+async fn due_tasks(
+    key: &LessSafeKey,
+    kind: ProviderKind,
+    account: &Account,
+    time_zone: &TimeZone,
+) -> Result<Vec<Task>, String> {
+    Provider::new(key, kind, account)?
+        .due_tasks(time_zone)
+        .await
+}
+
+/// Called without the reminders locked, and only locks the accounts between requests, since the
+/// task apps can be slow to answer.
+pub async fn handle(
+    user: UserId,
+    command: TasksCommand,
+    private: bool,
+) -> Result<Reply, CommandError> {
+    use CommandError::*;
+    let key = key().ok_or(TasksDisabled)?;
+    let response = match command {
+        TasksCommand::Show => {
+            let accounts = ACCOUNTS.lock().await;
+            let Some(mine) = accounts.get(&user).filter(|mine| !mine.is_empty()) else {
+                return Ok("You haven't linked a task app, see `$help tasks`".into());
+            };
+            let names: Vec<_> = mine.keys().map(ProviderKind::to_string).collect();
+            return Ok(format!("Linked task apps: {}", names.join(", ")).into());
+        }
+        TasksCommand::Link {
+            provider,
+            token,
+            database,
+        } => {
+            if !private {
+                return Err(TasksInGuild);
+            }
+            let account = Account {
+                token: seal(&key, &token),
+                database,
+                imported: BTreeSet::new(),
+            };
+            // Existing tasks are skipped, only ones added from now on become reminders
+            let time_zone = get_preferences(user, None).await.time_zone();
+            let tasks = due_tasks(&key, provider, &account, &time_zone)
+                .await
+                .map_err(|err| Tasks(provider, err))?;
+            let account = Account {
+                imported: tasks.into_iter().map(|task| task.id).collect(),
+                ..account
+            };
+            ACCOUNTS
+                .lock()
+                .await
+                .entry(user)
+                .or_default()
+                .insert(provider, account);
+            format!("Linked {provider}, new tasks with a due date will become reminders")
+        }
+        TasksCommand::Unlink(provider) => {
+            let mut accounts = ACCOUNTS.lock().await;
+            let Some(mine) = accounts.get_mut(&user) else {
+                return Ok(format!("You haven't linked {provider}").into());
+            };
+            if mine.remove(&provider).is_none() {
+                return Ok(format!("You haven't linked {provider}").into());
+            }
+            if mine.is_empty() {
+                accounts.remove(&user);
+            }
+            format!("Unlinked {provider}")
+        }
+        TasksCommand::Push(id, provider) => {
+            let reminder = REMINDERS
+                .lock()
+                .await
+                .get(&user)
+                .and_then(|list| list.get(id as usize).cloned())
+                .ok_or(InvalidID(id))?;
+            let account = ACCOUNTS
+                .lock()
+                .await
+                .get(&user)
+                .and_then(|mine| mine.get(&provider))
+                .map(|account| Provider::new(&key, provider, account))
+                .ok_or(TasksNotLinked(provider))?;
+            account
+                .map_err(|err| Tasks(provider, err))?
+                .export(&reminder)
+                .await
+                .map_err(|err| Tasks(provider, err))?;
+            format!("Added '{}' to {provider}", reminder.message)
+        }
+    };
+    save();
+    Ok(response.into())
+}
+
+/// Turns tasks added to linked apps since the last poll into reminders.
+pub async fn poll() {
+    let Some(key) = key() else {
+        return;
+    };
+    let users: Vec<_> = ACCOUNTS.lock().await.keys().copied().collect();
+    for user in users {
+        let time_zone = get_preferences(user, None).await.time_zone();
+        // Copied out so the accounts aren't locked during requests
+        let linked: Vec<_> = ACCOUNTS
+            .lock()
+            .await
+            .get(&user)
+            .into_iter()
+            .flatten()
+            .map(|(kind, account)| {
+                let provider = Provider::new(&key, *kind, account);
+                (*kind, provider, account.imported.clone())
+            })
+            .collect();
+        let mut found = vec![];
+        for (kind, provider, imported) in linked {
+            let tasks = match provider {
+                Ok(provider) => provider.due_tasks(&time_zone).await,
+                Err(err) => Err(err),
+            };
+            match tasks {
+                Ok(tasks) => found.extend(
+                    tasks
+                        .into_iter()
+                        .filter(|task| !imported.contains(&task.id))
+                        .map(|task| (kind, task)),
+                ),
+                Err(err) => eprintln!("Failed to poll {kind} for {user}: {err}"),
+            }
+        }
+        if found.is_empty() {
+            continue;
+        }
+
+        let mut cache = REMINDERS.lock().await;
+        let mut accounts = ACCOUNTS.lock().await;
+        let list = cache.entry(user).or_default();
+        let now = Zoned::now();
+        for (kind, task) in found {
+            if let Some(account) = accounts.get_mut(&user).and_then(|mine| mine.get_mut(&kind)) {
+                account.imported.insert(task.id);
+            }
+            let Ok(message) = validate_message(&format!("{} (from {kind})", task.title)) else {
+                continue;
+            };
+            if task.due <= now {
+                continue;
+            }
+            let reminder = Reminder::new(task.due, message);
+            audit::record(user, &reminder, Action::Created, "tasks").await;
+            list.push(reminder);
+        }
+        list.sort_by(|a, b| a.time.cmp(&b.time));
+    }
+    save();
+}
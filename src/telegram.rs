@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{delivery::Notifier, leader, log_error, save, web::CLIENT, CommandError, Reply};
+
+pub const TELEGRAM_FILE: &str = "telegram.json";
+
+const API: &str = "https://api.telegram.org";
+/// How long a code from `$telegram link` can be redeemed for.
+const CODE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+/// Wrong codes a chat can send before it has to wait out `CODE_LIFETIME`, so codes can't be
+/// guessed.
+const MAX_ATTEMPTS: u32 = 5;
+/// Seconds each `getUpdates` call waits for new messages.
+const POLL_TIMEOUT: u64 = 30;
+
+/// The Telegram chat each linked Discord user's reminders are copied to.
+pub static LINKS: LazyLock<Mutex<HashMap<UserId, i64>>> = LazyLock::new(Default::default);
+
+/// Unredeemed link codes, these only need to outlive a few minutes so they aren't saved.
+static CODES: LazyLock<Mutex<HashMap<String, (UserId, Instant)>>> = LazyLock::new(Default::default);
+
+/// Wrong codes sent from each Telegram chat, and when the first of them was.
+static ATTEMPTS: LazyLock<Mutex<HashMap<i64, (u32, Instant)>>> = LazyLock::new(Default::default);
+
+pub enum TelegramCommand {
+    Link,
+    Unlink,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// The bridge is enabled by setting the bot token in `TELEGRAM_TOKEN`.
+pub fn token() -> Option<String> {
+    std::env::var("TELEGRAM_TOKEN").ok()
+}
+
+pub async fn load_links() {
+    let Ok(links_json) = tokio::fs::read_to_string(TELEGRAM_FILE).await else {
+        return;
+    };
+    let links = serde_json::from_str(&links_json).unwrap();
+    *LINKS.lock().await = links;
+}
+
+async fn call<T: DeserializeOwned>(
+    token: &str,
+    method: &str,
+    body: serde_json::Value,
+) -> Result<T, String> {
+    let response: Response<T> = CLIENT
+        .post(format!("{API}/bot{token}/{method}"))
+        .json(&body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.without_url().to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())?;
+    response
+        .result
+        .ok_or_else(|| response.description.unwrap_or_default())
+}
+
+async fn send(token: &str, chat: i64, text: &str) -> Result<(), String> {
+    call::<serde_json::Value>(token, "sendMessage", json!({"chat_id": chat, "text": text}))
+        .await
+        .map(|_| ())
+}
+
+pub async fn handle(user: UserId, command: TelegramCommand) -> Result<Reply, CommandError> {
+    if token().is_none() {
+        return Err(CommandError::TelegramDisabled);
+    }
+    match command {
+        TelegramCommand::Link => {
+            let mut random = [0; 8];
+            SystemRandom::new()
+                .fill(&mut random)
+                .expect("No secure random source");
+            let code = format!("{:010}", u64::from_le_bytes(random) % 10_000_000_000);
+            let mut codes = CODES.lock().await;
+            // Only the newest code works, so asking again doesn't leave more codes to guess
+            codes.retain(|_, (owner, created)| *owner != user && created.elapsed() < CODE_LIFETIME);
+            codes.insert(code.clone(), (user, Instant::now()));
+            Ok(format!(
+                "Send `/link {code}` to the Telegram bot within 10 minutes to get your reminders there too"
+            )
+            .into())
+        }
+        TelegramCommand::Unlink => {
+            if LINKS.lock().await.remove(&user).is_none() {
+                return Ok("You don't have a linked Telegram chat".into());
+            }
+            save();
+            Ok("Unlinked your Telegram chat".into())
+        }
+    }
+}
+
+pub struct TelegramChat {
+    token: String,
+    chat: i64,
+}
+
+impl Notifier for TelegramChat {
+    fn name(&self) -> String {
+        format!("telegram chat {}", self.chat)
+    }
+
+    async fn notify(&self, text: &str) -> Result<(), String> {
+        send(&self.token, self.chat, text).await
+    }
+}
+
+/// The user's linked chat, if they have one and the bridge is enabled.
+pub async fn notifier(user: UserId) -> Option<TelegramChat> {
+    let token = token()?;
+    let chat = LINKS.lock().await.get(&user).copied()?;
+    Some(TelegramChat { token, chat })
+}
+
+async fn redeem(chat: i64, text: &str) -> String {
+    let code = text
+        .strip_prefix("/link")
+        .or_else(|| text.strip_prefix("/start"))
+        .map(str::trim);
+    let Some(code) = code.filter(|code| !code.is_empty()) else {
+        return "Get a code with `$telegram link` on Discord, then send it here as /link <code>"
+            .into();
+    };
+    let mut attempts = ATTEMPTS.lock().await;
+    attempts.retain(|_, (_, first)| first.elapsed() < CODE_LIFETIME);
+    if attempts
+        .get(&chat)
+        .is_some_and(|(count, _)| *count >= MAX_ATTEMPTS)
+    {
+        return "Too many wrong codes, wait a few minutes and get a new one with `$telegram link`"
+            .into();
+    }
+    let mut codes = CODES.lock().await;
+    let Some((user, created)) = codes.remove(code) else {
+        attempts.entry(chat).or_insert((0, Instant::now())).0 += 1;
+        return "That code isn't valid, get a new one with `$telegram link`".into();
+    };
+    attempts.remove(&chat);
+    if created.elapsed() >= CODE_LIFETIME {
+        return "That code has expired, get a new one with `$telegram link`".into();
+    }
+    LINKS.lock().await.insert(user, chat);
+    save();
+    "Linked, your Discord reminders will be sent here too".into()
+}
+
+/// Answers link requests sent to the Telegram bot, for as long as this instance is the leader.
+pub async fn poll(token: String) {
+    let mut offset = 0;
+    loop {
+        if !leader::is_leader() {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let body =
+            json!({"offset": offset, "timeout": POLL_TIMEOUT, "allowed_updates": ["message"]});
+        let updates: Vec<Update> = match call(&token, "getUpdates", body).await {
+            Ok(updates) => updates,
+            Err(err) => {
+                eprintln!("Failed to get Telegram updates: {err}");
+                tokio::time::sleep(Duration::from_secs(POLL_TIMEOUT)).await;
+                continue;
+            }
+        };
+        for update in updates {
+            offset = update.update_id + 1;
+            let Some(Message {
+                chat,
+                text: Some(text),
+            }) = update.message
+            else {
+                continue;
+            };
+            let reply = redeem(chat.id, &text).await;
+            log_error(send(&token, chat.id, &reply).await);
+        }
+    }
+}
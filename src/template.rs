@@ -0,0 +1,125 @@
+use jiff::Zoned;
+use serenity::all::UserId;
+
+use crate::{
+    audit::{self, Action},
+    format_time, humanize, listing, CommandError, Preferences, Reminder,
+};
+
+/// Longest template accepted, placeholders included.
+pub const MAX_TEMPLATE_LENGTH: usize = 200;
+
+pub const PLACEHOLDERS: &[&str] = &["message", "time", "created", "created_rel", "tags"];
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a template into text and `{placeholder}`s, `{{` and `}}` stand for literal braces.
+fn parse(template: &str) -> Result<Vec<Part<'_>>, CommandError> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        parts.push(Part::Text(&rest[..start]));
+        let brace = &rest[start..start + 1];
+        if rest[start + 1..].starts_with(brace) {
+            parts.push(Part::Text(brace));
+            rest = &rest[start + 2..];
+            continue;
+        }
+        if brace == "}" {
+            return Err(CommandError::InvalidTemplate(
+                "Unmatched `}`, write `}}` for a literal brace".into(),
+            ));
+        }
+        let Some(end) = rest[start..].find('}') else {
+            return Err(CommandError::InvalidTemplate(
+                "Unclosed `{`, write `{{` for a literal brace".into(),
+            ));
+        };
+        parts.push(Part::Placeholder(&rest[start + 1..start + end]));
+        rest = &rest[start + end + 1..];
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+pub fn validate(template: &str) -> Result<(), CommandError> {
+    let length = template.chars().count();
+    if length > MAX_TEMPLATE_LENGTH {
+        return Err(CommandError::TemplateTooLong(length));
+    }
+    let parts = parse(template)?;
+    let mut has_message = false;
+    for part in parts {
+        let Part::Placeholder(name) = part else {
+            continue;
+        };
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(CommandError::InvalidTemplate(format!(
+                "There's no `{{{name}}}` placeholder, use one of {}",
+                PLACEHOLDERS
+                    .iter()
+                    .map(|name| format!("`{{{name}}}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        has_message |= name == "message";
+    }
+    if !has_message {
+        return Err(CommandError::InvalidTemplate(
+            "Templates need a `{message}` placeholder".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lays out `reminder` for delivery to `user`. Templates are validated when they're set, so an
+/// unknown placeholder is left as written.
+pub async fn render(
+    template: &str,
+    user: UserId,
+    reminder: &Reminder,
+    preferences: &Preferences,
+) -> String {
+    let format = preferences.time_format();
+    // Recurring reminders keep their key, so this is when the first occurrence was set
+    let created = audit::entries(user, Some(reminder.key), usize::MAX)
+        .await
+        .into_iter()
+        .rfind(|entry| entry.action == Action::Created)
+        .map(|entry| entry.time);
+    let Ok(parts) = parse(template) else {
+        return reminder.message.clone();
+    };
+    let mut content = String::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => content.push_str(text),
+            Part::Placeholder("message") => content.push_str(&reminder.message),
+            Part::Placeholder("time") => content.push_str(&format_time(&reminder.time, format)),
+            Part::Placeholder("created") => match &created {
+                Some(created) => content.push_str(&format_time(created, format)),
+                None => content.push_str("an unknown time"),
+            },
+            Part::Placeholder("created_rel") => match &created {
+                Some(created) => {
+                    let elapsed = Zoned::now().duration_since(created).unsigned_abs();
+                    content.push_str(&humanize::duration(elapsed, preferences.language()));
+                }
+                None => content.push_str("a while"),
+            },
+            Part::Placeholder("tags") => {
+                let tags: Vec<_> = listing::tags(&reminder.message)
+                    .into_iter()
+                    .map(|tag| format!("#{tag}"))
+                    .collect();
+                content.push_str(&tags.join(" "));
+            }
+            Part::Placeholder(name) => content.push_str(&format!("{{{name}}}")),
+        }
+    }
+    content
+}
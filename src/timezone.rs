@@ -0,0 +1,152 @@
+use jiff::tz::{Offset, TimeZone};
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct Abbreviation {
+    abbreviation: &'static str,
+    zone: &'static str,
+    region: &'static str,
+}
+
+const fn abbr(
+    abbreviation: &'static str,
+    zone: &'static str,
+    region: &'static str,
+) -> Abbreviation {
+    Abbreviation {
+        abbreviation,
+        zone,
+        region,
+    }
+}
+
+const ABBREVIATIONS: &[Abbreviation] = &[
+    abbr("ET", "America/New_York", "US Eastern"),
+    abbr("EST", "America/New_York", "US Eastern"),
+    abbr("EDT", "America/New_York", "US Eastern"),
+    abbr("CT", "America/Chicago", "US Central"),
+    abbr("CST", "America/Chicago", "US Central"),
+    abbr("CST", "Asia/Shanghai", "China"),
+    abbr("CDT", "America/Chicago", "US Central"),
+    abbr("MT", "America/Denver", "US Mountain"),
+    abbr("MST", "America/Denver", "US Mountain"),
+    abbr("MST", "America/Phoenix", "Arizona"),
+    abbr("MDT", "America/Denver", "US Mountain"),
+    abbr("PT", "America/Los_Angeles", "US Pacific"),
+    abbr("PST", "America/Los_Angeles", "US Pacific"),
+    abbr("PDT", "America/Los_Angeles", "US Pacific"),
+    abbr("AKST", "America/Anchorage", "Alaska"),
+    abbr("AKDT", "America/Anchorage", "Alaska"),
+    abbr("HST", "Pacific/Honolulu", "Hawaii"),
+    abbr("AST", "America/Halifax", "Atlantic"),
+    abbr("AST", "Asia/Riyadh", "Arabia"),
+    abbr("ADT", "America/Halifax", "Atlantic"),
+    abbr("NST", "America/St_Johns", "Newfoundland"),
+    abbr("NDT", "America/St_Johns", "Newfoundland"),
+    abbr("BRT", "America/Sao_Paulo", "Brazil"),
+    abbr("ART", "America/Argentina/Buenos_Aires", "Argentina"),
+    abbr("GMT", "Europe/London", "UK"),
+    abbr("GMT", "UTC", "Coordinated Universal Time"),
+    abbr("BST", "Europe/London", "UK"),
+    abbr("BST", "Asia/Dhaka", "Bangladesh"),
+    abbr("IST", "Asia/Kolkata", "India"),
+    abbr("IST", "Europe/Dublin", "Ireland"),
+    abbr("IST", "Asia/Jerusalem", "Israel"),
+    abbr("WET", "Europe/Lisbon", "Western Europe"),
+    abbr("WEST", "Europe/Lisbon", "Western Europe"),
+    abbr("CET", "Europe/Paris", "Central Europe"),
+    abbr("CEST", "Europe/Paris", "Central Europe"),
+    abbr("EET", "Europe/Athens", "Eastern Europe"),
+    abbr("EEST", "Europe/Athens", "Eastern Europe"),
+    abbr("MSK", "Europe/Moscow", "Moscow"),
+    abbr("SAST", "Africa/Johannesburg", "South Africa"),
+    abbr("PKT", "Asia/Karachi", "Pakistan"),
+    abbr("WIB", "Asia/Jakarta", "Western Indonesia"),
+    abbr("ICT", "Asia/Bangkok", "Indochina"),
+    abbr("SGT", "Asia/Singapore", "Singapore"),
+    abbr("HKT", "Asia/Hong_Kong", "Hong Kong"),
+    abbr("PHT", "Asia/Manila", "Philippines"),
+    abbr("JST", "Asia/Tokyo", "Japan"),
+    abbr("KST", "Asia/Seoul", "Korea"),
+    abbr("AWST", "Australia/Perth", "Western Australia"),
+    abbr("ACST", "Australia/Adelaide", "Central Australia"),
+    abbr("ACDT", "Australia/Adelaide", "Central Australia"),
+    abbr("AEST", "Australia/Sydney", "Eastern Australia"),
+    abbr("AEDT", "Australia/Sydney", "Eastern Australia"),
+    abbr("NZST", "Pacific/Auckland", "New Zealand"),
+    abbr("NZDT", "Pacific/Auckland", "New Zealand"),
+];
+
+fn format_candidates(candidates: &[&Abbreviation]) -> String {
+    candidates
+        .iter()
+        .map(|c| format!("`$tz {}` ({})", c.zone, c.region))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Error, Debug)]
+pub enum TimezoneError {
+    #[error("Unknown timezone: {0}. Use an IANA name like Europe/London, an abbreviation like EST, or an offset like UTC+5:30")]
+    Unknown(String),
+    #[error("{0} is ambiguous, did you mean one of: {candidates}", candidates = format_candidates(.1))]
+    Ambiguous(String, Vec<&'static Abbreviation>),
+}
+
+fn parse_offset(input: &str) -> Option<i32> {
+    let rest = input
+        .strip_prefix("UTC")
+        .or_else(|| input.strip_prefix("GMT"))
+        .unwrap_or(input);
+    let (sign, rest) = match rest.as_bytes().first()? {
+        b'+' => (1, &rest[1..]),
+        b'-' => (-1, &rest[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!("UTC{sign}{:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+/// Resolves user input into the canonical timezone name stored in preferences.
+pub fn resolve(input: &str) -> Result<String, TimezoneError> {
+    let input = input.trim();
+    let upper = input.to_ascii_uppercase();
+
+    if let Some(seconds) = parse_offset(&upper) {
+        return Ok(format_offset(seconds));
+    }
+
+    let candidates: Vec<_> = ABBREVIATIONS
+        .iter()
+        .filter(|a| a.abbreviation == upper)
+        .collect();
+    match candidates.as_slice() {
+        [] => {}
+        [candidate] => return Ok(candidate.zone.to_string()),
+        _ => return Err(TimezoneError::Ambiguous(upper, candidates)),
+    }
+
+    match jiff::tz::db().get(input) {
+        Ok(tz) => Ok(tz.iana_name().unwrap_or(input).to_string()),
+        Err(_) => Err(TimezoneError::Unknown(input.to_string())),
+    }
+}
+
+/// Looks up a timezone previously produced by [`resolve`].
+pub fn lookup(name: &str) -> Option<TimeZone> {
+    if let Some(seconds) = parse_offset(name) {
+        return Offset::from_seconds(seconds).ok().map(TimeZone::fixed);
+    }
+    jiff::tz::db().get(name).ok()
+}
@@ -0,0 +1,128 @@
+use std::sync::LazyLock;
+
+use jiff::{SignedDuration, Timestamp};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ButtonStyle, Context, CreateActionRow, CreateButton, CreateMessage, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    delivery::{self, Recipient},
+    log_error, save, REMINDERS,
+};
+
+pub const TRANSFERS_FILE: &str = "transfers.json";
+
+/// How long an offer can be answered for.
+const OFFER_LIFETIME: SignedDuration = SignedDuration::from_hours(24);
+
+/// An offer of the reminder with `key` from one user to another. Offers are kept on disk and
+/// the buttons only carry the offer's ID, so a button can't be pointed at someone else's reminder.
+#[derive(Serialize, Deserialize)]
+struct Offer {
+    id: u64,
+    from: UserId,
+    to: UserId,
+    key: u64,
+    expires: Timestamp,
+}
+
+static OFFERS: LazyLock<Mutex<Vec<Offer>>> = LazyLock::new(Default::default);
+
+pub async fn load_offers() {
+    let Ok(offers_json) = tokio::fs::read_to_string(TRANSFERS_FILE).await else {
+        return;
+    };
+    let offers = serde_json::from_str(&offers_json).unwrap();
+    *OFFERS.lock().await = offers;
+}
+
+async fn write(offers: &[Offer]) {
+    let offers_json = serde_json::to_string(offers).unwrap();
+    tokio::fs::write(TRANSFERS_FILE, offers_json).await.unwrap();
+}
+
+/// Records an offer of the reminder with `key`, replacing any earlier offer of it, and returns
+/// the buttons for answering it.
+pub async fn offer(from: UserId, to: UserId, key: u64) -> CreateActionRow {
+    let mut id = [0; 8];
+    SystemRandom::new()
+        .fill(&mut id)
+        .expect("No secure random source");
+    let id = u64::from_le_bytes(id);
+    let now = Timestamp::now();
+    let mut offers = OFFERS.lock().await;
+    offers.retain(|offer| offer.expires > now && !(offer.from == from && offer.key == key));
+    offers.push(Offer {
+        id,
+        from,
+        to,
+        key,
+        expires: now + OFFER_LIFETIME,
+    });
+    write(&offers).await;
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("transfer:accept:{id}"))
+            .label("Accept")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("transfer:decline:{id}"))
+            .label("Decline")
+            .style(ButtonStyle::Secondary),
+    ])
+}
+
+/// Removes the offer of the reminder with `key` from `from`, if there is one.
+pub async fn withdraw(from: UserId, key: u64) {
+    let mut offers = OFFERS.lock().await;
+    offers.retain(|offer| !(offer.from == from && offer.key == key));
+    write(&offers).await;
+}
+
+async fn notify(ctx: &Context, user: UserId, notice: String) {
+    let message = CreateMessage::new().content(notice);
+    log_error(delivery::send(&ctx.http, Recipient::User(user), message).await);
+}
+
+/// Accepts or declines an offer on behalf of its recipient, returning the updated offer text.
+pub async fn resolve(ctx: &Context, id: u64, user: UserId, accept: bool) -> String {
+    let offer = {
+        let mut offers = OFFERS.lock().await;
+        let Some(position) = offers.iter().position(|o| o.id == id && o.to == user) else {
+            return "This transfer is no longer available".into();
+        };
+        let offer = offers.remove(position);
+        write(&offers).await;
+        offer
+    };
+    if offer.expires <= Timestamp::now() {
+        return "This transfer offer has expired".into();
+    }
+    let mut cache = REMINDERS.lock().await;
+    let from = cache.entry(offer.from).or_default();
+    let Some(position) = from.iter().position(|r| r.key == offer.key) else {
+        return "That reminder no longer exists".into();
+    };
+
+    if !accept {
+        let message = from[position].message.clone();
+        drop(cache);
+        let notice = format!("<@{user}> declined your reminder '{message}'");
+        notify(ctx, offer.from, notice).await;
+        return format!("Declined the reminder '{message}'");
+    }
+
+    let reminder = from.remove(position);
+    audit::record(offer.from, &reminder, Action::TransferredOut, "$transfer").await;
+    audit::record(user, &reminder, Action::TransferredIn, "$transfer").await;
+    let message = reminder.message.clone();
+    let to = cache.entry(user).or_default();
+    to.push(reminder);
+    to.sort_by(|a, b| a.time.cmp(&b.time));
+    drop(cache);
+    save();
+
+    let notice = format!("<@{user}> accepted your reminder '{message}'");
+    notify(ctx, offer.from, notice).await;
+    format!("You now own the reminder '{message}'")
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, fmt::Display, sync::LazyLock};
+
+use jiff::Timestamp;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, CreateMessage, GuildId, Http, MessageId, UserId};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit,
+    delivery::{self, DeliveryStatus, Recipient},
+    log_error, save, stats, validate_message, CommandError, Reply, DELIVERY_PREFIX,
+    MAX_MESSAGE_LENGTH,
+};
+
+pub const TRIGGERS_FILE: &str = "triggers.json";
+
+/// Trigger reminders each user can have waiting at once.
+const MAX_TRIGGERS: usize = 25;
+/// Shortest time between two notifications from the same keyword trigger, in ms.
+const KEYWORD_COOLDOWN: i64 = 10 * 60 * 1000;
+/// Characters kept free in trigger messages for the note on what fired them.
+const NOTE_ALLOWANCE: usize = 100;
+
+/// Something that happens on Discord, rather than a time, that a reminder waits for.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub enum Trigger {
+    VoiceJoin(UserId),
+    /// Stays subscribed until cancelled, lowercased so matching ignores case.
+    Keyword {
+        channel: ChannelId,
+        keyword: String,
+    },
+}
+
+impl Trigger {
+    fn repeats(&self) -> bool {
+        matches!(self, Trigger::Keyword { .. })
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::VoiceJoin(user) => write!(f, "when <@{user}> joins voice"),
+            Trigger::Keyword { channel, keyword } => {
+                write!(f, "when <#{channel}> mentions \"{keyword}\"")
+            }
+        }
+    }
+}
+
+/// A reminder sent when its trigger happens in the server it was set in, once unless the trigger repeats.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TriggerReminder {
+    trigger: Trigger,
+    guild: GuildId,
+    message: String,
+    key: u64,
+}
+
+pub static TRIGGERS: LazyLock<Mutex<HashMap<UserId, Vec<TriggerReminder>>>> =
+    LazyLock::new(Default::default);
+
+/// When each repeating trigger last notified, by key.
+static LAST_FIRED: LazyLock<Mutex<HashMap<u64, Timestamp>>> = LazyLock::new(Default::default);
+
+pub enum TriggerCommand {
+    List,
+    Cancel(u64),
+}
+
+/// A gateway event triggers are checked against.
+pub enum Event {
+    VoiceJoin {
+        guild: GuildId,
+        user: UserId,
+        channel: ChannelId,
+    },
+    Message {
+        guild: GuildId,
+        author: UserId,
+        channel: ChannelId,
+        message: MessageId,
+        content: String,
+    },
+}
+
+impl Event {
+    fn fires(&self, owner: UserId, reminder: &TriggerReminder) -> bool {
+        match (self, &reminder.trigger) {
+            (Event::VoiceJoin { guild, user, .. }, Trigger::VoiceJoin(target)) => {
+                *guild == reminder.guild && user == target
+            }
+            (
+                Event::Message {
+                    guild,
+                    author,
+                    channel,
+                    content,
+                    ..
+                },
+                Trigger::Keyword {
+                    channel: watched,
+                    keyword,
+                },
+            ) => {
+                *guild == reminder.guild
+                    && channel == watched
+                    && *author != owner
+                    && content.to_lowercase().contains(keyword.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Event::VoiceJoin { user, channel, .. } => format!("<@{user}> joined <#{channel}>"),
+            Event::Message {
+                guild,
+                channel,
+                message,
+                ..
+            } => format!("https://discord.com/channels/{guild}/{channel}/{message}"),
+        }
+    }
+}
+
+pub async fn load_triggers() {
+    let Ok(triggers_json) = tokio::fs::read_to_string(TRIGGERS_FILE).await else {
+        return;
+    };
+    let triggers = serde_json::from_str(&triggers_json).unwrap();
+    *TRIGGERS.lock().await = triggers;
+}
+
+pub async fn schedule(
+    owner: UserId,
+    guild: GuildId,
+    trigger: Trigger,
+    message: String,
+) -> Result<Reply, CommandError> {
+    let message = validate_message(&message)?;
+    let length = message.chars().count();
+    if length > MAX_MESSAGE_LENGTH - NOTE_ALLOWANCE {
+        return Err(CommandError::MessageTooLong(length));
+    }
+    let mut triggers = TRIGGERS.lock().await;
+    let mine = triggers.entry(owner).or_default();
+    if mine.len() >= MAX_TRIGGERS {
+        return Err(CommandError::TooManyTriggers(MAX_TRIGGERS));
+    }
+    let response = format!("I'll remind you {trigger} (trigger #{})", mine.len());
+    mine.push(TriggerReminder {
+        trigger,
+        guild,
+        message,
+        key: audit::next_key(),
+    });
+    save();
+    Ok(response.into())
+}
+
+pub async fn handle(owner: UserId, command: TriggerCommand) -> Result<Reply, CommandError> {
+    let mut triggers = TRIGGERS.lock().await;
+    let mine = triggers.entry(owner).or_default();
+    let response = match command {
+        TriggerCommand::List => {
+            if mine.is_empty() {
+                return Ok("You don't have any trigger reminders".into());
+            }
+            let lines: Vec<_> = mine
+                .iter()
+                .enumerate()
+                .map(|(id, reminder)| format!("{id}: {} - {}", reminder.trigger, reminder.message))
+                .collect();
+            return Ok(format!("Your trigger reminders:\n{}", lines.join("\n")).into());
+        }
+        TriggerCommand::Cancel(id) => {
+            if mine.len() <= id as usize {
+                return Err(CommandError::InvalidID(id));
+            }
+            let reminder = mine.remove(id as usize);
+            LAST_FIRED.lock().await.remove(&reminder.key);
+            format!("Cancelled '{}'", reminder.message)
+        }
+    };
+    save();
+    Ok(response.into())
+}
+
+/// Sends every trigger reminder `event` sets off, removing the ones that don't repeat.
+/// Repeating triggers inside their cooldown are skipped.
+pub async fn fire(http: &Http, event: Event) {
+    let mut fired = vec![];
+    let mut removed = false;
+    {
+        let mut triggers = TRIGGERS.lock().await;
+        let mut last_fired = LAST_FIRED.lock().await;
+        let now = Timestamp::now();
+        for (owner, mine) in triggers.iter_mut() {
+            mine.retain(|reminder| {
+                if !event.fires(*owner, reminder) {
+                    return true;
+                }
+                if !reminder.trigger.repeats() {
+                    fired.push((*owner, reminder.clone()));
+                    removed = true;
+                    return false;
+                }
+                let cooling = last_fired.get(&reminder.key).is_some_and(|last| {
+                    now.as_millisecond() - last.as_millisecond() < KEYWORD_COOLDOWN
+                });
+                if !cooling {
+                    last_fired.insert(reminder.key, now);
+                    fired.push((*owner, reminder.clone()));
+                }
+                true
+            });
+        }
+        triggers.retain(|_, mine| !mine.is_empty());
+    }
+    for (owner, reminder) in fired {
+        let text = format!("{} ({})", reminder.message, event.describe());
+        let message = CreateMessage::new().content(format!("{DELIVERY_PREFIX}{text}"));
+        let result = delivery::send(http, Recipient::User(owner), message).await;
+        delivery::mirror(owner, &text).await;
+        let status = match result {
+            Ok(_) => DeliveryStatus::Delivered,
+            Err(_) => DeliveryStatus::Failed,
+        };
+        delivery::record(owner, &text, &result, status).await;
+        if result.is_ok() {
+            stats::record(owner, |stats| stats.delivered += 1).await;
+        }
+        log_error(result);
+    }
+    if removed {
+        save();
+    }
+}
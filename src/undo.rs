@@ -0,0 +1,123 @@
+//! A short-lived cancel button on schedule confirmations, so a typo can be undone without looking
+//! up the reminder's ID.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use jiff::{SignedDuration, Timestamp};
+use serenity::all::{
+    ActionRow, ActionRowComponent, ButtonKind, ButtonStyle, CreateActionRow, CreateButton,
+    EditMessage, Http, Message, UserId,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    audit::{self, Action},
+    log_error, save, stats, REMINDERS,
+};
+
+/// How long the button works for after the confirmation is sent.
+const UNDO_WINDOW: Duration = Duration::from_secs(60);
+
+/// Reminders that were just scheduled, by the key of the first one.
+struct Recent {
+    user: UserId,
+    keys: Vec<u64>,
+    created: Timestamp,
+}
+
+/// Only kept in memory, a restart ends the window early.
+static RECENT: LazyLock<Mutex<HashMap<u64, Recent>>> = LazyLock::new(Default::default);
+
+fn expired(created: Timestamp) -> bool {
+    Timestamp::now().duration_since(created) > SignedDuration::try_from(UNDO_WINDOW).unwrap()
+}
+
+/// A cancel button for reminders `user` just scheduled.
+pub async fn button(user: UserId, keys: Vec<u64>) -> Option<CreateActionRow> {
+    let first = *keys.first()?;
+    let mut recent = RECENT.lock().await;
+    recent.retain(|_, recent| !expired(recent.created));
+    recent.insert(
+        first,
+        Recent {
+            user,
+            keys,
+            created: Timestamp::now(),
+        },
+    );
+    let button = CreateButton::new(format!("undo:{user}:{first}"))
+        .label("✖ Cancel")
+        .style(ButtonStyle::Danger);
+    Some(CreateActionRow::Buttons(vec![button]))
+}
+
+/// Cancels the reminders behind a cancel button, if it's still within its window.
+pub async fn undo(user: UserId, first: u64) -> Result<String, &'static str> {
+    let recent = RECENT
+        .lock()
+        .await
+        .remove(&first)
+        .filter(|recent| recent.user == user && !expired(recent.created))
+        .ok_or("It's too late to undo this, use `$cr <id>` instead")?;
+    let mut cache = REMINDERS.lock().await;
+    let list = cache.entry(user).or_default();
+    let mut cancelled = 0;
+    for key in recent.keys {
+        let Some(index) = list.iter().position(|reminder| reminder.key == key) else {
+            continue;
+        };
+        let reminder = list.remove(index);
+        audit::record(user, &reminder, Action::Cancelled, "undo").await;
+        stats::record(user, |stats| stats.cancelled += 1).await;
+        cancelled += 1;
+    }
+    drop(cache);
+    save();
+    match cancelled {
+        0 => Err("These reminders are already gone"),
+        1 => Ok("Cancelled the reminder".into()),
+        n => Ok(format!("Cancelled {n} reminders")),
+    }
+}
+
+fn is_undo(row: &ActionRow) -> bool {
+    row.components.iter().any(|component| match component {
+        ActionRowComponent::Button(button) => matches!(
+            &button.data,
+            ButtonKind::NonLink { custom_id, .. } if custom_id.starts_with("undo:")
+        ),
+        _ => false,
+    })
+}
+
+/// Takes the cancel button off a sent confirmation once its window is over.
+pub fn expire(http: Arc<Http>, mut message: Message) {
+    if !message.components.iter().any(is_undo) {
+        return;
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(UNDO_WINDOW).await;
+        let rows = message
+            .components
+            .iter()
+            .filter(|row| !is_undo(row))
+            .map(|row| {
+                let buttons = row
+                    .components
+                    .iter()
+                    .filter_map(|component| match component {
+                        ActionRowComponent::Button(button) => Some(button.clone().into()),
+                        _ => None,
+                    })
+                    .collect();
+                CreateActionRow::Buttons(buttons)
+            })
+            .collect();
+        let edit = EditMessage::new().components(rows);
+        log_error(message.edit(&http, edit).await);
+    });
+}
@@ -0,0 +1,109 @@
+//! Counts of which commands get used and how often they fail to parse, for `$admin stats`.
+//! Only the kind of command is kept, never who sent it or what was in it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+};
+
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use tokio::sync::Mutex;
+
+use crate::{stats::STATS, Reminder};
+
+pub const USAGE_FILE: &str = "usage.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    since: Zoned,
+    /// By `Command::kind`
+    commands: BTreeMap<String, u64>,
+    /// Known commands that didn't parse
+    invalid: u64,
+    unknown: u64,
+}
+
+impl Default for Usage {
+    fn default() -> Self {
+        Usage {
+            since: Zoned::now(),
+            commands: BTreeMap::new(),
+            invalid: 0,
+            unknown: 0,
+        }
+    }
+}
+
+pub enum Outcome {
+    Parsed(&'static str),
+    Invalid,
+    Unknown,
+}
+
+pub static USAGE: LazyLock<Mutex<Usage>> = LazyLock::new(Default::default);
+
+pub async fn load_usage() {
+    let Ok(usage_json) = tokio::fs::read_to_string(USAGE_FILE).await else {
+        return;
+    };
+    let usage = serde_json::from_str(&usage_json).unwrap();
+    *USAGE.lock().await = usage;
+}
+
+pub async fn record(outcome: Outcome) {
+    let mut usage = USAGE.lock().await;
+    match outcome {
+        Outcome::Parsed(kind) => *usage.commands.entry(kind.to_string()).or_default() += 1,
+        Outcome::Invalid => usage.invalid += 1,
+        Outcome::Unknown => usage.unknown += 1,
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => part as f64 * 100.0 / total as f64,
+    }
+}
+
+pub async fn report(reminders: &HashMap<UserId, Vec<Reminder>>) -> String {
+    let usage = USAGE.lock().await;
+    let parsed: u64 = usage.commands.values().sum();
+    let failed = usage.invalid + usage.unknown;
+    let total = parsed + failed;
+    let mut lines = vec![
+        format!(
+            "Commands since {}: {total}",
+            usage.since.strftime("%B %-d, %Y")
+        ),
+        format!(
+            "Failed to parse: {failed} ({:.1}%), {} invalid and {} unknown",
+            percent(failed, total),
+            usage.invalid,
+            usage.unknown
+        ),
+    ];
+    let mut commands: Vec<_> = usage.commands.iter().collect();
+    commands.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let commands: Vec<_> = commands
+        .iter()
+        .map(|(kind, count)| format!("{kind} {count} ({:.1}%)", percent(**count, parsed)))
+        .collect();
+    if !commands.is_empty() {
+        lines.push(format!("By command: {}", commands.join(", ")));
+    }
+    let users = reminders.values().filter(|list| !list.is_empty()).count();
+    let active: usize = reminders.values().map(Vec::len).sum();
+    let stats = STATS.read().await;
+    let scheduled: u64 = stats.values().map(|stats| stats.scheduled).sum();
+    lines.push(format!(
+        "Reminders per user: {:.1} active across {users} users, {:.1} scheduled across {} users",
+        active as f64 / users.max(1) as f64,
+        scheduled as f64 / stats.len().max(1) as f64,
+        stats.len()
+    ));
+    lines.join("\n")
+}
@@ -0,0 +1,90 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use reqwest::{redirect::Policy, Url};
+
+/// Longer than the Telegram long poll, so that isn't cut short.
+const TIMEOUT: Duration = Duration::from_secs(60);
+/// Redirects followed within the same host by `public_client`.
+const MAX_REDIRECTS: usize = 5;
+
+const USER_AGENT: &str = concat!("reedbot/", env!("CARGO_PKG_VERSION"));
+
+/// Shared by every integration that calls an outside HTTP API, so connections are pooled.
+pub static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+        .expect("Couldn't build the HTTP client")
+});
+
+/// Whether an address is on the public internet, rather than the bot's own machine or network.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let shared = first == 100 && (64..128).contains(&second);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || shared
+                || first == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || unique_local
+                    || link_local)
+            }
+        },
+    }
+}
+
+/// A client for a URL given by a user, refusing hosts that resolve to a private, loopback or
+/// link-local address. The client is pinned to the address that was checked, and only follows
+/// redirects within the same host, so neither a second lookup nor a redirect can lead elsewhere.
+pub async fn public_client(url: &Url, timeout: Duration) -> Result<reqwest::Client, String> {
+    let host = url.host_str().ok_or("The URL has no host")?.to_string();
+    let port = url.port_or_known_default().ok_or("The URL has no port")?;
+    let lookup = host.trim_start_matches('[').trim_end_matches(']');
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((lookup, port))
+        .await
+        .map_err(|_| format!("Couldn't find {host}"))?
+        .collect();
+    let address = match addresses.first() {
+        Some(address) if addresses.iter().all(|address| is_public(address.ip())) => *address,
+        Some(_) => return Err(format!("{host} isn't a public address")),
+        None => return Err(format!("Couldn't find {host}")),
+    };
+    let same_host = host.clone();
+    let redirects = Policy::custom(move |attempt| {
+        if attempt.previous().len() < MAX_REDIRECTS
+            && attempt.url().host_str() == Some(same_host.as_str())
+        {
+            attempt.follow()
+        } else {
+            attempt.stop()
+        }
+    });
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(USER_AGENT)
+        .redirect(redirects)
+        .resolve(lookup, address)
+        .build()
+        .map_err(|err| err.to_string())
+}